@@ -0,0 +1,8 @@
+create table room_ban (
+    room_id uuid not null,
+    account_id account_id not null,
+    created_at timestamptz not null default now(),
+
+    foreign key (room_id) references room (id) on delete cascade,
+    primary key (room_id, account_id)
+);
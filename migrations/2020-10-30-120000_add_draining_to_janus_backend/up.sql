@@ -0,0 +1 @@
+ALTER TABLE janus_backend ADD COLUMN draining BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+ALTER TABLE room DROP COLUMN backend_group;
+ALTER TABLE janus_backend DROP COLUMN "group";
@@ -0,0 +1,2 @@
+ALTER TABLE janus_backend ADD COLUMN "group" TEXT;
+ALTER TABLE room ADD COLUMN backend_group TEXT;
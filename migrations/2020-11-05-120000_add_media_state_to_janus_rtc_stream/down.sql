@@ -0,0 +1,2 @@
+ALTER TABLE janus_rtc_stream DROP COLUMN video_receiving;
+ALTER TABLE janus_rtc_stream DROP COLUMN audio_receiving;
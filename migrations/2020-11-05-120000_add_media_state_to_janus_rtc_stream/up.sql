@@ -0,0 +1,2 @@
+ALTER TABLE janus_rtc_stream ADD COLUMN audio_receiving BOOLEAN;
+ALTER TABLE janus_rtc_stream ADD COLUMN video_receiving BOOLEAN;
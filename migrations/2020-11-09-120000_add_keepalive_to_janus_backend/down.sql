@@ -0,0 +1,2 @@
+ALTER TABLE janus_backend DROP COLUMN keepalive_latency;
+ALTER TABLE janus_backend DROP COLUMN keepalive_misses;
@@ -0,0 +1,2 @@
+ALTER TABLE janus_backend ADD COLUMN keepalive_misses INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE janus_backend ADD COLUMN keepalive_latency INTEGER;
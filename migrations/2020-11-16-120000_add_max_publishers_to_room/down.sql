@@ -0,0 +1 @@
+ALTER TABLE room DROP COLUMN max_publishers;
@@ -0,0 +1 @@
+ALTER TABLE room ADD COLUMN max_publishers INTEGER;
@@ -0,0 +1,2 @@
+DROP TABLE room_role;
+DROP TYPE agent_role;
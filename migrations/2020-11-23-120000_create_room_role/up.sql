@@ -0,0 +1,11 @@
+CREATE TYPE agent_role AS ENUM ('host', 'speaker', 'listener');
+
+CREATE TABLE room_role (
+    room_id UUID NOT NULL,
+    account_id ACCOUNT_ID NOT NULL,
+    role AGENT_ROLE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    FOREIGN KEY (room_id) REFERENCES room (id) ON DELETE CASCADE,
+    PRIMARY KEY (room_id, account_id)
+);
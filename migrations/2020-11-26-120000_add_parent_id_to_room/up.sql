@@ -0,0 +1,2 @@
+ALTER TABLE room ADD COLUMN parent_id UUID REFERENCES room (id) ON DELETE CASCADE;
+CREATE INDEX room_parent_id_idx ON room (parent_id);
@@ -0,0 +1,13 @@
+CREATE TABLE message (
+    id UUID DEFAULT gen_random_uuid(),
+    room_id UUID NOT NULL,
+    agent_id AGENT_ID NOT NULL,
+    label TEXT NOT NULL,
+    data JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    FOREIGN KEY (room_id) REFERENCES room (id) ON DELETE CASCADE,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX message_room_id_created_at_idx ON message (room_id, created_at);
@@ -0,0 +1,13 @@
+CREATE TABLE webhook (
+    id UUID DEFAULT gen_random_uuid(),
+    audience TEXT NOT NULL,
+    label TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX webhook_next_attempt_at_idx ON webhook (next_attempt_at);
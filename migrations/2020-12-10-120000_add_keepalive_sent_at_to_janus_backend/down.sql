@@ -0,0 +1 @@
+ALTER TABLE janus_backend DROP COLUMN keepalive_sent_at;
@@ -0,0 +1 @@
+ALTER TABLE janus_backend ADD COLUMN keepalive_sent_at TIMESTAMPTZ;
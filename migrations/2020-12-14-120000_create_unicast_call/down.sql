@@ -0,0 +1 @@
+DROP TABLE unicast_call;
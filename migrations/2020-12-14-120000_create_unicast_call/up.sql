@@ -0,0 +1,10 @@
+CREATE TABLE unicast_call (
+    correlation_data TEXT NOT NULL,
+    reqp JSONB NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    deadline TIMESTAMPTZ NOT NULL,
+
+    PRIMARY KEY (correlation_data)
+);
+
+CREATE INDEX unicast_call_deadline_idx ON unicast_call (deadline);
@@ -4,13 +4,15 @@ use chrono::{DateTime, Utc};
 use diesel::pg::PgConnection;
 use diesel::r2d2::{ConnectionManager, PooledConnection};
 use slog::{Logger, OwnedKV, SendSyncRefUnwindSafeKV};
-use svc_agent::{queue_counter::QueueCounterHandle, AgentId};
+use svc_agent::{mqtt::Agent, queue_counter::QueueCounterHandle, AgentId};
 use svc_authz::cache::ConnectionPool as RedisConnectionPool;
 use svc_authz::ClientMap as Authz;
 
 use crate::app::error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind};
 use crate::app::metrics::{DynamicStatsCollector, Metric};
-use crate::backend::janus::Client as JanusClient;
+use crate::app::rate_limiter::RateLimiter;
+use crate::app::unicast_watchdog::UnicastWatchdog;
+use crate::backend::janus::{Balancer as JanusBalancer, Client as JanusClient};
 use crate::config::Config;
 use crate::db::ConnectionPool as Db;
 
@@ -24,10 +26,13 @@ pub(crate) trait GlobalContext: Sync {
     fn db(&self) -> &Db;
     fn agent_id(&self) -> &AgentId;
     fn janus_client(&self) -> Arc<JanusClient>;
+    fn janus_balancer(&self) -> &JanusBalancer;
     fn janus_topics(&self) -> &JanusTopics;
     fn queue_counter(&self) -> &Option<QueueCounterHandle>;
     fn redis_pool(&self) -> &Option<RedisConnectionPool>;
     fn dynamic_stats(&self) -> Option<&DynamicStatsCollector>;
+    fn rate_limiter(&self) -> &RateLimiter;
+    fn unicast_watchdog(&self) -> Option<&UnicastWatchdog>;
     fn get_metrics(&self) -> anyhow::Result<Vec<Metric>>;
 
     fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, AppError> {
@@ -56,10 +61,13 @@ pub(crate) struct AppContext {
     db: Db,
     agent_id: AgentId,
     janus_client: Arc<JanusClient>,
+    janus_balancer: Arc<JanusBalancer>,
     janus_topics: JanusTopics,
     queue_counter: Option<QueueCounterHandle>,
     redis_pool: Option<RedisConnectionPool>,
     dynamic_stats: Option<Arc<DynamicStatsCollector>>,
+    rate_limiter: Arc<RateLimiter>,
+    unicast_watchdog: Option<Arc<UnicastWatchdog>>,
 }
 
 impl AppContext {
@@ -71,6 +79,7 @@ impl AppContext {
         janus_topics: JanusTopics,
     ) -> Self {
         let agent_id = AgentId::new(&config.agent_label, config.id.to_owned());
+        let janus_balancer = JanusBalancer::new(config.balancer.strategy);
 
         Self {
             config: Arc::new(config),
@@ -78,10 +87,13 @@ impl AppContext {
             db,
             agent_id,
             janus_client: Arc::new(janus_client),
+            janus_balancer: Arc::new(janus_balancer),
             janus_topics,
             queue_counter: None,
             redis_pool: None,
             dynamic_stats: Some(Arc::new(DynamicStatsCollector::start())),
+            rate_limiter: Arc::new(RateLimiter::new()),
+            unicast_watchdog: None,
         }
     }
 
@@ -98,6 +110,16 @@ impl AppContext {
             ..self
         }
     }
+
+    pub(crate) fn add_unicast_watchdog(self, agent: Agent) -> Self {
+        let watchdog = UnicastWatchdog::new(&self.config.message, self.db.clone())
+            .start(agent, self.dynamic_stats.clone());
+
+        Self {
+            unicast_watchdog: Some(Arc::new(watchdog)),
+            ..self
+        }
+    }
 }
 
 impl GlobalContext for AppContext {
@@ -121,6 +143,10 @@ impl GlobalContext for AppContext {
         self.janus_client.clone()
     }
 
+    fn janus_balancer(&self) -> &JanusBalancer {
+        &self.janus_balancer
+    }
+
     fn janus_topics(&self) -> &JanusTopics {
         &self.janus_topics
     }
@@ -137,6 +163,14 @@ impl GlobalContext for AppContext {
         self.dynamic_stats.as_deref()
     }
 
+    fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    fn unicast_watchdog(&self) -> Option<&UnicastWatchdog> {
+        self.unicast_watchdog.as_deref()
+    }
+
     fn get_metrics(&self) -> anyhow::Result<Vec<Metric>> {
         crate::app::metrics::Collector::new(self).get()
     }
@@ -181,6 +215,10 @@ impl<'a, C: GlobalContext> GlobalContext for AppMessageContext<'a, C> {
         self.global_context.janus_client()
     }
 
+    fn janus_balancer(&self) -> &JanusBalancer {
+        self.global_context.janus_balancer()
+    }
+
     fn janus_topics(&self) -> &JanusTopics {
         self.global_context.janus_topics()
     }
@@ -197,6 +235,14 @@ impl<'a, C: GlobalContext> GlobalContext for AppMessageContext<'a, C> {
         self.global_context.dynamic_stats()
     }
 
+    fn rate_limiter(&self) -> &RateLimiter {
+        self.global_context.rate_limiter()
+    }
+
+    fn unicast_watchdog(&self) -> Option<&UnicastWatchdog> {
+        self.global_context.unicast_watchdog()
+    }
+
     fn get_metrics(&self) -> anyhow::Result<Vec<Metric>> {
         self.global_context.get_metrics()
     }
@@ -1,149 +1,2000 @@
+use std::result::Result as StdResult;
+
 use async_std::stream;
 use async_trait::async_trait;
-use serde_derive::Deserialize;
-use svc_agent::mqtt::{IncomingRequestProperties, ResponseStatus};
+use chrono::{DateTime, Utc};
+use diesel::Connection;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::json;
+use svc_agent::mqtt::{
+    IncomingRequestProperties, IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties,
+    OutgoingMessage, OutgoingRequest, ResponseStatus, ShortTermTimingProperties,
+    TrackingProperties,
+};
+use svc_agent::{AccountId, AgentId, Authenticable};
 use uuid::Uuid;
 
-use crate::app::context::Context;
-use crate::app::endpoint::prelude::*;
-use crate::db;
+use crate::app::context::Context;
+use crate::app::endpoint::prelude::*;
+use crate::app::endpoint::room::{SubscriptionRequest, MQTT_GW_API_VERSION};
+use crate::app::endpoint::subscription::RoomEnterLeaveEvent;
+use crate::db;
+use crate::db::room_role::Role;
+use crate::util::generate_correlation_data;
+
+///////////////////////////////////////////////////////////////////////////////
+
+const MAX_LIMIT: i64 = 25;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListRequest {
+    room_id: Uuid,
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ListItem {
+    #[serde(flatten)]
+    agent: db::agent::Object,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<Role>,
+}
+
+pub(crate) struct ListHandler;
+
+#[async_trait]
+impl RequestHandler for ListHandler {
+    type Payload = ListRequest;
+    const ERROR_TITLE: &'static str = "Failed to list agents";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.room_id, helpers::RoomTimeRequirement::Open)?;
+
+        // Authorize agents listing in the room.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id, "agents"];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "list")
+            .await?;
+
+        // Get agents list in the room.
+        let agents = {
+            let conn = context.get_conn()?;
+
+            db::agent::ListQuery::new()
+                .room_id(payload.room_id)
+                .offset(payload.offset.unwrap_or_else(|| 0))
+                .limit(std::cmp::min(
+                    payload.limit.unwrap_or_else(|| MAX_LIMIT),
+                    MAX_LIMIT,
+                ))
+                .execute(&conn)?
+        };
+
+        // Add agents' room roles.
+        let roles = {
+            let conn = context.get_conn()?;
+            db::room_role::ListQuery::new(payload.room_id).execute(&conn)?
+        };
+
+        let items = agents
+            .into_iter()
+            .map(|agent| {
+                let role = roles
+                    .iter()
+                    .find(|room_role| room_role.account_id() == agent.agent_id().as_account_id())
+                    .map(|room_role| room_role.role());
+
+                ListItem { agent, role }
+            })
+            .collect::<Vec<ListItem>>();
+
+        // Respond with agents list.
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            items,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct KickRequest {
+    room_id: Uuid,
+    agent_id: AgentId,
+    #[serde(default)]
+    ban: bool,
+}
+
+pub(crate) struct KickHandler;
+
+#[async_trait]
+impl RequestHandler for KickHandler {
+    type Payload = KickRequest;
+    const ERROR_TITLE: &'static str = "Failed to kick agent";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.room_id, helpers::RoomTimeRequirement::Open)?;
+
+        // Authorize agent kicking in the room.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id, "agents"];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "delete")
+            .await?;
+
+        let (row_count, backend_requests) = {
+            let conn = context.get_conn()?;
+
+            conn.transaction::<_, AppError, _>(|| {
+                // Ban the account so it couldn't enter the room again.
+                if payload.ban {
+                    db::room_ban::InsertQuery::new(room.id(), payload.agent_id.as_account_id())
+                        .execute(&conn)?;
+                }
+
+                // Delete agent from the DB.
+                let row_count = db::agent::DeleteQuery::new()
+                    .agent_id(&payload.agent_id)
+                    .room_id(room.id())
+                    .execute(&conn)?;
+
+                // `agent.leave` requests to Janus instances that host active streams in this room.
+                let backend_requests = if row_count == 1 {
+                    helpers::leave_backends(
+                        context,
+                        room.id(),
+                        &payload.agent_id,
+                        reqp.tracking(),
+                        &conn,
+                    )?
+                } else {
+                    vec![]
+                };
+
+                Ok((row_count, backend_requests))
+            })?
+        };
+
+        // Banning an agent which is not online in the room is still fine.
+        if row_count == 0 && !payload.ban {
+            return Err(anyhow!("Agent is not online in the room"))
+                .error(AppErrorKind::AgentNotEnteredTheRoom);
+        }
+
+        let mut messages = vec![helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        )];
+
+        if row_count == 1 {
+            // Send dynamic subscription deletion request to the broker on behalf of the agent.
+            // See the comment in `room.leave` handler on why this is a unicast request.
+            let subscription_request = SubscriptionRequest::new(
+                payload.agent_id.to_owned(),
+                vec!["rooms", &room_id, "events"],
+            );
+
+            let props = reqp.to_request(
+                "subscription.delete",
+                reqp.response_topic(),
+                &generate_correlation_data(),
+                ShortTermTimingProperties::until_now(context.start_timestamp()),
+            );
+
+            let outgoing_request = OutgoingRequest::unicast(
+                subscription_request,
+                props,
+                &payload.agent_id,
+                MQTT_GW_API_VERSION,
+            );
+
+            messages.push(Box::new(outgoing_request) as Box<dyn IntoPublishableMessage + Send>);
+
+            // Notify the room that the agent has left and has been kicked.
+            for &label in &["room.leave", "agent.kick"] {
+                messages.push(helpers::build_notification(
+                    label,
+                    &format!("rooms/{}/events", room.id()),
+                    RoomEnterLeaveEvent::new(room.id(), payload.agent_id.to_owned()),
+                    reqp,
+                    context.start_timestamp(),
+                ));
+            }
+
+            messages.extend(backend_requests);
+        }
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MoveRequest {
+    room_id: Uuid,
+    agent_id: AgentId,
+    target_room_id: Uuid,
+}
+
+pub(crate) struct MoveHandler;
+
+#[async_trait]
+impl RequestHandler for MoveHandler {
+    type Payload = MoveRequest;
+    const ERROR_TITLE: &'static str = "Failed to move agent";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.room_id, helpers::RoomTimeRequirement::Open)?;
+
+        let target_room = helpers::find_room_by_id(
+            context,
+            payload.target_room_id,
+            helpers::RoomTimeRequirement::Open,
+        )?;
+
+        // Agents are being moved only between a room and its breakout rooms.
+        let is_related = target_room.parent_id() == Some(room.id())
+            || room.parent_id() == Some(target_room.id())
+            || (room.parent_id().is_some() && room.parent_id() == target_room.parent_id());
+
+        if !is_related {
+            return Err(anyhow!(
+                "Room '{}' is not related to room '{}'",
+                target_room.id(),
+                room.id()
+            ))
+            .error(AppErrorKind::RoomNotRelated);
+        }
+
+        // Authorize moving agents out of the room.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id, "agents"];
+
+        let authz_time =
+            helpers::authorize_by_role(context, &room, reqp, object, "update", &[Role::Host])
+                .await?;
+
+        // Authorize moving agents into the target room.
+        let target_room_id = target_room.id().to_string();
+        let object = vec!["rooms", &target_room_id, "agents"];
+
+        let target_authz_time = helpers::authorize_by_role(
+            context,
+            &target_room,
+            reqp,
+            object,
+            "update",
+            &[Role::Host],
+        )
+        .await?;
+
+        let authz_time = authz_time + target_authz_time;
+
+        // Re-register the agent in the target room. The same checks as on entering apply
+        // so the agent may end up in the target room's lobby.
+        let (status, backend_requests) = {
+            let conn = context.get_conn()?;
+
+            let is_host =
+                db::room_role::FindQuery::new(target_room.id(), payload.agent_id.as_account_id())
+                    .execute(&conn)?
+                    .map(|room_role| room_role.role() == Role::Host)
+                    .unwrap_or(false);
+
+            conn.transaction::<_, AppError, _>(|| {
+                let row_count = db::agent::DeleteQuery::new()
+                    .agent_id(&payload.agent_id)
+                    .room_id(room.id())
+                    .execute(&conn)?;
+
+                if row_count == 0 {
+                    return Err(anyhow!("Agent is not online in the room"))
+                        .error(AppErrorKind::AgentNotEnteredTheRoom);
+                }
+
+                let status =
+                    helpers::register_agent(target_room.id(), &payload.agent_id, is_host, &conn)?;
+
+                // `agent.leave` requests to Janus instances that host active streams in this room.
+                let backend_requests = helpers::leave_backends(
+                    context,
+                    room.id(),
+                    &payload.agent_id,
+                    reqp.tracking(),
+                    &conn,
+                )?;
+
+                Ok((status, backend_requests))
+            })?
+        };
+
+        let mut messages = vec![helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        )];
+
+        // Resubscribe the agent from the room's events to the target room's events.
+        // An agent put to the target room's lobby doesn't get subscribed until admitted.
+        // See the comment in `room.enter` handler on why these are unicast requests.
+        let mut subscriptions = vec![("subscription.delete", &room_id)];
+
+        if status != db::agent::Status::Waiting {
+            subscriptions.push(("subscription.create", &target_room_id));
+        }
+
+        for &(method, object_room_id) in &subscriptions {
+            let subscription_request = SubscriptionRequest::new(
+                payload.agent_id.to_owned(),
+                vec!["rooms", object_room_id, "events"],
+            );
+
+            let props = reqp.to_request(
+                method,
+                reqp.response_topic(),
+                &generate_correlation_data(),
+                ShortTermTimingProperties::until_now(context.start_timestamp()),
+            );
+
+            let outgoing_request = OutgoingRequest::unicast(
+                subscription_request,
+                props,
+                &payload.agent_id,
+                MQTT_GW_API_VERSION,
+            );
+
+            messages.push(Box::new(outgoing_request) as Box<dyn IntoPublishableMessage + Send>);
+        }
+
+        // Notify the room that the agent has left. The target room gets notified with
+        // `room.enter` when the broker creates the subscription.
+        messages.push(helpers::build_notification(
+            "room.leave",
+            &format!("rooms/{}/events", room.id()),
+            RoomEnterLeaveEvent::new(room.id(), payload.agent_id.to_owned()),
+            reqp,
+            context.start_timestamp(),
+        ));
+
+        // Let the target room's hosts know that the agent is waiting in the lobby.
+        if status == db::agent::Status::Waiting {
+            messages.push(helpers::build_notification(
+                "agent.wait",
+                &format!("rooms/{}/lobby", target_room.id()),
+                RoomEnterLeaveEvent::new(target_room.id(), payload.agent_id.to_owned()),
+                reqp,
+                context.start_timestamp(),
+            ));
+        }
+
+        messages.extend(backend_requests);
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct GrantRequest {
+    room_id: Uuid,
+    agent_id: AgentId,
+    role: Role,
+}
+
+pub(crate) struct GrantHandler;
+
+#[async_trait]
+impl RequestHandler for GrantHandler {
+    type Payload = GrantRequest;
+    const ERROR_TITLE: &'static str = "Failed to grant role";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.room_id, helpers::RoomTimeRequirement::Open)?;
+
+        // Authorize managing roles in the room. Hosts are allowed to delegate their rights.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id, "roles"];
+
+        let authz_time =
+            helpers::authorize_by_role(context, &room, reqp, object, "update", &[Role::Host])
+                .await?;
+
+        // Assign the role to the agent's account.
+        let room_role = {
+            let conn = context.get_conn()?;
+
+            db::room_role::UpsertQuery::new(
+                room.id(),
+                payload.agent_id.as_account_id(),
+                payload.role,
+            )
+            .execute(&conn)?
+        };
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            room_role.clone(),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        let notification = helpers::build_notification(
+            "agent.grant",
+            &format!("rooms/{}/events", room.id()),
+            room_role,
+            reqp,
+            context.start_timestamp(),
+        );
+
+        Ok(Box::new(stream::from_iter(vec![response, notification])))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RevokeRequest {
+    room_id: Uuid,
+    agent_id: AgentId,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct RevokeEvent {
+    room_id: Uuid,
+    account_id: AccountId,
+}
+
+pub(crate) struct RevokeHandler;
+
+#[async_trait]
+impl RequestHandler for RevokeHandler {
+    type Payload = RevokeRequest;
+    const ERROR_TITLE: &'static str = "Failed to revoke role";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.room_id, helpers::RoomTimeRequirement::Open)?;
+
+        // Authorize managing roles in the room. Hosts are allowed to delegate their rights.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id, "roles"];
+
+        let authz_time =
+            helpers::authorize_by_role(context, &room, reqp, object, "update", &[Role::Host])
+                .await?;
+
+        // Remove the role from the agent's account.
+        let row_count = {
+            let conn = context.get_conn()?;
+
+            db::room_role::DeleteQuery::new(room.id(), payload.agent_id.as_account_id())
+                .execute(&conn)?
+        };
+
+        let mut messages = vec![helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        )];
+
+        // Revoking a role which is not granted is a no-op.
+        if row_count == 1 {
+            let event = RevokeEvent {
+                room_id: room.id(),
+                account_id: payload.agent_id.as_account_id().to_owned(),
+            };
+
+            messages.push(helpers::build_notification(
+                "agent.revoke",
+                &format!("rooms/{}/events", room.id()),
+                event,
+                reqp,
+                context.start_timestamp(),
+            ));
+        }
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct AdmitRequest {
+    room_id: Uuid,
+    agent_id: AgentId,
+}
+
+pub(crate) struct AdmitHandler;
+
+#[async_trait]
+impl RequestHandler for AdmitHandler {
+    type Payload = AdmitRequest;
+    const ERROR_TITLE: &'static str = "Failed to admit agent";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.room_id, helpers::RoomTimeRequirement::Open)?;
+
+        // Authorize managing the room's lobby.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id, "lobby"];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "update")
+            .await?;
+
+        // Move the agent from the lobby to `in_progress` state. The agent takes a slot
+        // in the room so the same checks as on entering apply.
+        {
+            let conn = context.get_conn()?;
+
+            conn.transaction::<_, AppError, _>(|| {
+                helpers::lock_room_for_agent(room.id(), &payload.agent_id, &conn)?;
+
+                // Only the first of concurrent admissions or rejections succeeds.
+                let row_count =
+                    db::agent::BulkStatusUpdateQuery::new(db::agent::Status::InProgress)
+                        .agent_id(&payload.agent_id)
+                        .room_id(room.id())
+                        .status(db::agent::Status::Waiting)
+                        .execute(&conn)?;
+
+                ensure_agent_was_waiting(row_count)
+            })?;
+        }
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        // Send dynamic subscription creation request to the broker on behalf of the agent.
+        // See the comment in `room.enter` handler on why this is a unicast request.
+        let subscription_request = SubscriptionRequest::new(
+            payload.agent_id.to_owned(),
+            vec!["rooms", &room_id, "events"],
+        );
+
+        let props = reqp.to_request(
+            "subscription.create",
+            reqp.response_topic(),
+            &generate_correlation_data(),
+            ShortTermTimingProperties::until_now(context.start_timestamp()),
+        );
+
+        let outgoing_request = OutgoingRequest::unicast(
+            subscription_request,
+            props,
+            &payload.agent_id,
+            MQTT_GW_API_VERSION,
+        );
+
+        let notification = helpers::build_notification(
+            "agent.admit",
+            &format!("rooms/{}/lobby", room.id()),
+            RoomEnterLeaveEvent::new(room.id(), payload.agent_id.to_owned()),
+            reqp,
+            context.start_timestamp(),
+        );
+
+        let messages = vec![
+            response,
+            Box::new(outgoing_request) as Box<dyn IntoPublishableMessage + Send>,
+            notification,
+        ];
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+pub(crate) type RejectRequest = AdmitRequest;
+pub(crate) struct RejectHandler;
+
+#[async_trait]
+impl RequestHandler for RejectHandler {
+    type Payload = RejectRequest;
+    const ERROR_TITLE: &'static str = "Failed to reject agent";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.room_id, helpers::RoomTimeRequirement::Open)?;
+
+        // Authorize managing the room's lobby.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id, "lobby"];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "update")
+            .await?;
+
+        // Remove the agent from the lobby.
+        {
+            let conn = context.get_conn()?;
+
+            let row_count = db::agent::DeleteQuery::new()
+                .agent_id(&payload.agent_id)
+                .room_id(room.id())
+                .status(db::agent::Status::Waiting)
+                .execute(&conn)?;
+
+            ensure_agent_was_waiting(row_count)?;
+        }
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        let notification = helpers::build_notification(
+            "agent.reject",
+            &format!("rooms/{}/lobby", room.id()),
+            RoomEnterLeaveEvent::new(room.id(), payload.agent_id.to_owned()),
+            reqp,
+            context.start_timestamp(),
+        );
+
+        Ok(Box::new(stream::from_iter(vec![response, notification])))
+    }
+}
+
+fn ensure_agent_was_waiting(row_count: usize) -> StdResult<(), AppError> {
+    if row_count == 1 {
+        Ok(())
+    } else {
+        Err(anyhow!("Agent is not waiting in the room's lobby"))
+            .error(AppErrorKind::AgentNotEnteredTheRoom)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct NetworkQualityEventData {
+    agent_id: AgentId,
+    rtc_id: Uuid,
+    rtc_stream_id: Uuid,
+    uplink: bool,
+}
+
+impl NetworkQualityEventData {
+    pub(crate) fn new(agent_id: AgentId, rtc_id: Uuid, rtc_stream_id: Uuid, uplink: bool) -> Self {
+        Self {
+            agent_id,
+            rtc_id,
+            rtc_stream_id,
+            uplink,
+        }
+    }
+}
+
+pub(crate) type NetworkQualityEvent = OutgoingMessage<NetworkQualityEventData>;
+
+pub(crate) fn network_quality_event(
+    room_id: Uuid,
+    data: NetworkQualityEventData,
+    start_timestamp: DateTime<Utc>,
+    tracking: &TrackingProperties,
+) -> NetworkQualityEvent {
+    let uri = format!("rooms/{}/events", room_id);
+    let timing = ShortTermTimingProperties::until_now(start_timestamp);
+    let mut props = OutgoingEventProperties::new("agent.network_quality", timing);
+    props.set_tracking(tracking.to_owned());
+    OutgoingEvent::broadcast(data, props, &uri)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    mod list {
+        use serde_derive::Deserialize;
+        use svc_agent::AgentId;
+        use uuid::Uuid;
+
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[derive(Deserialize)]
+        struct Agent {
+            agent_id: AgentId,
+            room_id: Uuid,
+        }
+
+        #[test]
+        fn list_agents() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room and put the agent online.
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                    room
+                };
+
+                // Allow agent to list agents in the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "agents"],
+                    "list",
+                );
+
+                // Make agent.list request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    offset: None,
+                    limit: None,
+                };
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Agents listing failed");
+
+                // Assert response.
+                let (agents, respp) = find_response::<Vec<Agent>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(agents.len(), 1);
+                assert_eq!(&agents[0].agent_id, agent.agent_id());
+                assert_eq!(agents[0].room_id, room.id());
+            });
+        }
+
+        #[test]
+        fn list_agents_with_roles() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room, put the agent online and make it a speaker.
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+
+                    db::room_role::UpsertQuery::new(room.id(), agent.account_id(), Role::Speaker)
+                        .execute(&conn)
+                        .expect("Failed to grant role");
+
+                    room
+                };
+
+                // Allow agent to list agents in the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "agents"],
+                    "list",
+                );
+
+                // Make agent.list request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    offset: None,
+                    limit: None,
+                };
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Agents listing failed");
+
+                // Assert response.
+                let (agents, _) = find_response::<Vec<serde_json::Value>>(messages.as_slice());
+                assert_eq!(agents.len(), 1);
+                assert_eq!(agents[0]["role"], "speaker");
+            });
+        }
+
+        #[test]
+        fn list_agents_not_authorized() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    shared_helpers::insert_room(&conn)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    offset: None,
+                    limit: None,
+                };
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agents listing");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+
+        #[test]
+        fn list_agents_closed_room() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create closed room.
+                    shared_helpers::insert_closed_room(&conn)
+                };
+
+                // Allow agent to list agents in the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "agents"],
+                    "list",
+                );
+
+                // Make agent.list request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    offset: None,
+                    limit: None,
+                };
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agents listing");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "room_closed");
+            });
+        }
+
+        #[test]
+        fn list_agents_missing_room() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(TestDb::new(), TestAuthz::new());
+
+                let payload = ListRequest {
+                    room_id: Uuid::new_v4(),
+                    offset: None,
+                    limit: None,
+                };
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agents listing");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "room_not_found");
+            });
+        }
+    }
+
+    mod kick {
+        use serde_derive::Deserialize;
+        use svc_agent::AgentId;
+
+        use crate::test_helpers::find_event_by_predicate;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[derive(Deserialize)]
+        struct DynSubRequest {
+            subject: AgentId,
+            object: Vec<String>,
+        }
+
+        #[derive(Deserialize)]
+        struct KickEvent {
+            id: Uuid,
+            agent_id: AgentId,
+        }
+
+        #[test]
+        fn kick_agent() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+                let kicked_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room and put the agent to kick online.
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, kicked_agent.agent_id(), room.id());
+                    room
+                };
+
+                // Allow agent to kick agents in the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "agents"],
+                    "delete",
+                );
+
+                // Make agent.kick request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = KickRequest {
+                    room_id: room.id(),
+                    agent_id: kicked_agent.agent_id().to_owned(),
+                    ban: true,
+                };
+
+                let messages = handle_request::<KickHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Agent kicking failed");
+
+                // Assert response.
+                let (_, respp) = find_response::<serde_json::Value>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                // Assert dynamic subscription deletion request.
+                let (payload, reqp, topic) = find_request::<DynSubRequest>(messages.as_slice());
+                assert!(topic.starts_with(&format!("agents/{}/", kicked_agent.agent_id())));
+                assert_eq!(reqp.method(), "subscription.delete");
+                assert_eq!(&payload.subject, kicked_agent.agent_id());
+                assert_eq!(payload.object, vec!["rooms", &room_id, "events"]);
+
+                // Assert notification.
+                let (event, _, topic) =
+                    find_event_by_predicate::<KickEvent, _>(messages.as_slice(), |evp, _, _| {
+                        evp.label() == "agent.kick"
+                    })
+                    .expect("agent.kick event not found");
+
+                assert!(topic.ends_with(&format!("rooms/{}/events", room.id())));
+                assert_eq!(event.id, room.id());
+                assert_eq!(&event.agent_id, kicked_agent.agent_id());
+
+                // Assert the agent is gone and its account is banned.
+                let conn = context.db().get().expect("Failed to get DB connection");
+
+                let agents = db::agent::ListQuery::new()
+                    .room_id(room.id())
+                    .execute(&conn)
+                    .expect("Failed to list agents");
+
+                assert!(agents.is_empty());
+
+                let ban = db::room_ban::FindQuery::new(room.id(), kicked_agent.account_id())
+                    .execute(&conn)
+                    .expect("Failed to find ban");
+
+                assert!(ban.is_some());
+            });
+        }
+
+        #[test]
+        fn kick_agent_not_entered() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+                let kicked_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    shared_helpers::insert_room(&conn)
+                };
+
+                // Allow agent to kick agents in the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "agents"],
+                    "delete",
+                );
+
+                // Make agent.kick request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = KickRequest {
+                    room_id: room.id(),
+                    agent_id: kicked_agent.agent_id().to_owned(),
+                    ban: false,
+                };
+
+                let err = handle_request::<KickHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agent kicking");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "agent_not_entered_the_room");
+            });
+        }
+
+        #[test]
+        fn kick_agent_not_authorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let kicked_agent = TestAgent::new("web", "user456", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, kicked_agent.agent_id(), room.id());
+                    room
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = KickRequest {
+                    room_id: room.id(),
+                    agent_id: kicked_agent.agent_id().to_owned(),
+                    ban: false,
+                };
+
+                let err = handle_request::<KickHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agent kicking");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
+
+    mod admit {
+        use std::ops::Bound;
+
+        use chrono::Utc;
+        use serde_derive::Deserialize;
+        use svc_agent::AgentId;
+
+        use crate::app::API_VERSION;
+        use crate::test_helpers::find_event_by_predicate;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[derive(Deserialize)]
+        struct DynSubRequest {
+            subject: AgentId,
+            object: Vec<String>,
+        }
+
+        #[derive(Deserialize)]
+        struct LobbyEvent {
+            id: Uuid,
+            agent_id: AgentId,
+        }
+
+        #[test]
+        fn admit_agent() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "host", USR_AUDIENCE);
+                let waiting_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room and put the agent to the lobby.
+                    let room = shared_helpers::insert_room(&conn);
+
+                    factory::Agent::new()
+                        .agent_id(waiting_agent.agent_id())
+                        .room_id(room.id())
+                        .status(db::agent::Status::Waiting)
+                        .insert(&conn);
+
+                    room
+                };
+
+                // Allow agent to manage the lobby.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "lobby"],
+                    "update",
+                );
+
+                // Make agent.admit request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = AdmitRequest {
+                    room_id: room.id(),
+                    agent_id: waiting_agent.agent_id().to_owned(),
+                };
+
+                let messages = handle_request::<AdmitHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Agent admission failed");
+
+                // Assert response.
+                let (_, respp) = find_response::<serde_json::Value>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                // Assert dynamic subscription creation request.
+                let (payload, reqp, topic) = find_request::<DynSubRequest>(messages.as_slice());
+                assert!(topic.starts_with(&format!("agents/{}/", waiting_agent.agent_id())));
+                assert_eq!(reqp.method(), "subscription.create");
+                assert_eq!(&payload.subject, waiting_agent.agent_id());
+                assert_eq!(payload.object, vec!["rooms", &room_id, "events"]);
+
+                // Assert notification.
+                let (event, _, topic) =
+                    find_event_by_predicate::<LobbyEvent, _>(messages.as_slice(), |evp, _, _| {
+                        evp.label() == "agent.admit"
+                    })
+                    .expect("agent.admit event not found");
+
+                let expected_topic = format!(
+                    "apps/conference.{}/api/{}/rooms/{}/lobby",
+                    SVC_AUDIENCE,
+                    API_VERSION,
+                    room.id(),
+                );
+
+                assert_eq!(topic, expected_topic);
+                assert_eq!(event.id, room.id());
+                assert_eq!(&event.agent_id, waiting_agent.agent_id());
+
+                // Assert the agent has left the lobby.
+                let conn = context.db().get().expect("Failed to get DB connection");
+
+                let agents = db::agent::ListQuery::new()
+                    .room_id(room.id())
+                    .status(db::agent::Status::InProgress)
+                    .execute(&conn)
+                    .expect("Failed to list agents");
+
+                assert_eq!(agents.len(), 1);
+            });
+        }
+
+        #[test]
+        fn admit_agent_not_waiting() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "host", USR_AUDIENCE);
+                let online_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, online_agent.agent_id(), room.id());
+                    room
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "lobby"],
+                    "update",
+                );
+
+                let mut context = TestContext::new(db, authz);
+
+                let payload = AdmitRequest {
+                    room_id: room.id(),
+                    agent_id: online_agent.agent_id().to_owned(),
+                };
+
+                let err = handle_request::<AdmitHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agent admission");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "agent_not_entered_the_room");
+            });
+        }
+
+        #[test]
+        fn admit_agent_to_full_room() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "host", USR_AUDIENCE);
+                let waiting_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let online_agent = TestAgent::new("web", "user456", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room with the only slot taken and put the agent to the lobby.
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                        .max_agents(1)
+                        .insert(&conn);
+
+                    shared_helpers::insert_agent(&conn, online_agent.agent_id(), room.id());
+
+                    factory::Agent::new()
+                        .agent_id(waiting_agent.agent_id())
+                        .room_id(room.id())
+                        .status(db::agent::Status::Waiting)
+                        .insert(&conn);
+
+                    room
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "lobby"],
+                    "update",
+                );
+
+                let mut context = TestContext::new(db, authz);
+
+                let payload = AdmitRequest {
+                    room_id: room.id(),
+                    agent_id: waiting_agent.agent_id().to_owned(),
+                };
+
+                let err = handle_request::<AdmitHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agent admission");
+
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "room_full");
+
+                // Assert the agent is still in the lobby.
+                let conn = context.db().get().expect("Failed to get DB connection");
+
+                let agents = db::agent::ListQuery::new()
+                    .agent_id(waiting_agent.agent_id())
+                    .room_id(room.id())
+                    .status(db::agent::Status::Waiting)
+                    .execute(&conn)
+                    .expect("Failed to list agents");
+
+                assert_eq!(agents.len(), 1);
+            });
+        }
+
+        #[test]
+        fn admit_agent_not_authorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user456", USR_AUDIENCE);
+                let waiting_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    let room = shared_helpers::insert_room(&conn);
+
+                    factory::Agent::new()
+                        .agent_id(waiting_agent.agent_id())
+                        .room_id(room.id())
+                        .status(db::agent::Status::Waiting)
+                        .insert(&conn);
+
+                    room
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = AdmitRequest {
+                    room_id: room.id(),
+                    agent_id: waiting_agent.agent_id().to_owned(),
+                };
+
+                let err = handle_request::<AdmitHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agent admission");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
+
+    mod reject {
+        use serde_derive::Deserialize;
+        use svc_agent::AgentId;
+
+        use crate::app::API_VERSION;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[derive(Deserialize)]
+        struct LobbyEvent {
+            id: Uuid,
+            agent_id: AgentId,
+        }
+
+        #[test]
+        fn reject_agent() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "host", USR_AUDIENCE);
+                let waiting_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room and put the agent to the lobby.
+                    let room = shared_helpers::insert_room(&conn);
+
+                    factory::Agent::new()
+                        .agent_id(waiting_agent.agent_id())
+                        .room_id(room.id())
+                        .status(db::agent::Status::Waiting)
+                        .insert(&conn);
+
+                    room
+                };
+
+                // Allow agent to manage the lobby.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "lobby"],
+                    "update",
+                );
+
+                // Make agent.reject request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = RejectRequest {
+                    room_id: room.id(),
+                    agent_id: waiting_agent.agent_id().to_owned(),
+                };
+
+                let messages = handle_request::<RejectHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Agent rejection failed");
+
+                // Assert response.
+                let (_, respp) = find_response::<serde_json::Value>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                // Assert notification.
+                let (event, evp, topic) = find_event::<LobbyEvent>(messages.as_slice());
+                assert_eq!(evp.label(), "agent.reject");
+
+                let expected_topic = format!(
+                    "apps/conference.{}/api/{}/rooms/{}/lobby",
+                    SVC_AUDIENCE,
+                    API_VERSION,
+                    room.id(),
+                );
+
+                assert_eq!(topic, expected_topic);
+                assert_eq!(event.id, room.id());
+                assert_eq!(&event.agent_id, waiting_agent.agent_id());
+
+                // Assert the agent is gone.
+                let conn = context.db().get().expect("Failed to get DB connection");
+
+                let agents = db::agent::ListQuery::new()
+                    .room_id(room.id())
+                    .status(db::agent::Status::Waiting)
+                    .execute(&conn)
+                    .expect("Failed to list agents");
+
+                assert!(agents.is_empty());
+            });
+        }
+
+        #[test]
+        fn reject_agent_not_waiting() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "host", USR_AUDIENCE);
+                let online_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, online_agent.agent_id(), room.id());
+                    room
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "lobby"],
+                    "update",
+                );
+
+                let mut context = TestContext::new(db, authz);
+
+                let payload = RejectRequest {
+                    room_id: room.id(),
+                    agent_id: online_agent.agent_id().to_owned(),
+                };
+
+                let err = handle_request::<RejectHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agent rejection");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "agent_not_entered_the_room");
+
+                // Assert the agent is still in the room.
+                let conn = context.db().get().expect("Failed to get DB connection");
+
+                let agents = db::agent::ListQuery::new()
+                    .agent_id(online_agent.agent_id())
+                    .room_id(room.id())
+                    .execute(&conn)
+                    .expect("Failed to list agents");
+
+                assert_eq!(agents.len(), 1);
+            });
+        }
+    }
+
+    mod move_agent {
+        use std::ops::Bound;
+
+        use chrono::{SubsecRound, Utc};
+        use serde_derive::Deserialize;
+        use svc_agent::AgentId;
+
+        use crate::test_helpers::find_request_by_predicate;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[derive(Deserialize)]
+        struct DynSubRequest {
+            subject: AgentId,
+            object: Vec<String>,
+        }
+
+        #[test]
+        fn move_agent_to_breakout_room() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+                let moved_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, breakout_room) = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create a room with a breakout room and put the agent to move online.
+                    let room = shared_helpers::insert_room(&conn);
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    let breakout_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .parent_id(room.id())
+                        .insert(&conn);
+
+                    shared_helpers::insert_agent(&conn, moved_agent.agent_id(), room.id());
+                    (room, breakout_room)
+                };
+
+                // Allow agent to move agents in both rooms.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let breakout_room_id = breakout_room.id().to_string();
+
+                for object_room_id in &[&room_id, &breakout_room_id] {
+                    authz.allow(
+                        agent.account_id(),
+                        vec!["rooms", object_room_id, "agents"],
+                        "update",
+                    );
+                }
+
+                // Make agent.move request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = MoveRequest {
+                    room_id: room.id(),
+                    agent_id: moved_agent.agent_id().to_owned(),
+                    target_room_id: breakout_room.id(),
+                };
+
+                let messages = handle_request::<MoveHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Agent moving failed");
+
+                // Assert response.
+                let (_, respp) = find_response::<serde_json::Value>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                // Assert dynamic subscription requests.
+                for &(method, object_room_id) in &[
+                    ("subscription.delete", &room_id),
+                    ("subscription.create", &breakout_room_id),
+                ] {
+                    let (payload, _, topic) = find_request_by_predicate::<DynSubRequest, _>(
+                        messages.as_slice(),
+                        |reqp, _| reqp.method() == method,
+                    )
+                    .expect("Subscription request not found");
+
+                    assert!(topic.starts_with(&format!("agents/{}/", moved_agent.agent_id())));
+                    assert_eq!(&payload.subject, moved_agent.agent_id());
+                    assert_eq!(payload.object, vec!["rooms", object_room_id, "events"]);
+                }
+
+                // Assert room.leave notification.
+                let (_, evp, topic) = find_event::<serde_json::Value>(messages.as_slice());
+                assert_eq!(evp.label(), "room.leave");
+                assert!(topic.ends_with(&format!("rooms/{}/events", room.id())));
+
+                // Assert the agent is registered in the breakout room.
+                let conn = context.db().get().expect("Failed to get DB connection");
+
+                let agents = db::agent::ListQuery::new()
+                    .agent_id(moved_agent.agent_id())
+                    .room_id(breakout_room.id())
+                    .status(db::agent::Status::InProgress)
+                    .execute(&conn)
+                    .expect("Failed to list agents");
+
+                assert_eq!(agents.len(), 1);
+            });
+        }
+
+        #[test]
+        fn move_agent_to_breakout_room_without_rights() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+                let moved_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, breakout_room) = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    let room = shared_helpers::insert_room(&conn);
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    let breakout_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .parent_id(room.id())
+                        .insert(&conn);
+
+                    shared_helpers::insert_agent(&conn, moved_agent.agent_id(), room.id());
+                    (room, breakout_room)
+                };
+
+                // Allow agent to move agents in the room but not in the breakout room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "agents"],
+                    "update",
+                );
+
+                let mut context = TestContext::new(db, authz);
+
+                let payload = MoveRequest {
+                    room_id: room.id(),
+                    agent_id: moved_agent.agent_id().to_owned(),
+                    target_room_id: breakout_room.id(),
+                };
+
+                let err = handle_request::<MoveHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agent moving");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+
+        #[test]
+        fn move_agent_to_full_breakout_room() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+                let moved_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (room, breakout_room) = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create a breakout room with a single slot taken by another agent.
+                    let room = shared_helpers::insert_room(&conn);
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    let breakout_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .parent_id(room.id())
+                        .max_agents(1)
+                        .insert(&conn);
+
+                    let other_agent = TestAgent::new("web", "user456", USR_AUDIENCE);
+                    shared_helpers::insert_agent(&conn, other_agent.agent_id(), breakout_room.id());
+                    shared_helpers::insert_agent(&conn, moved_agent.agent_id(), room.id());
+                    (room, breakout_room)
+                };
+
+                // Allow agent to move agents in both rooms.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let breakout_room_id = breakout_room.id().to_string();
+
+                for object_room_id in &[&room_id, &breakout_room_id] {
+                    authz.allow(
+                        agent.account_id(),
+                        vec!["rooms", object_room_id, "agents"],
+                        "update",
+                    );
+                }
 
-///////////////////////////////////////////////////////////////////////////////
+                let mut context = TestContext::new(db, authz);
 
-const MAX_LIMIT: i64 = 25;
+                let payload = MoveRequest {
+                    room_id: room.id(),
+                    agent_id: moved_agent.agent_id().to_owned(),
+                    target_room_id: breakout_room.id(),
+                };
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct ListRequest {
-    room_id: Uuid,
-    offset: Option<i64>,
-    limit: Option<i64>,
-}
+                let err = handle_request::<MoveHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agent moving");
 
-pub(crate) struct ListHandler;
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "room_full");
 
-#[async_trait]
-impl RequestHandler for ListHandler {
-    type Payload = ListRequest;
-    const ERROR_TITLE: &'static str = "Failed to list agents";
+                // Assert the agent is still in the room.
+                let conn = context.db().get().expect("Failed to get DB connection");
 
-    async fn handle<C: Context>(
-        context: &mut C,
-        payload: Self::Payload,
-        reqp: &IncomingRequestProperties,
-    ) -> Result {
-        let room =
-            helpers::find_room_by_id(context, payload.room_id, helpers::RoomTimeRequirement::Open)?;
+                let agents = db::agent::ListQuery::new()
+                    .agent_id(moved_agent.agent_id())
+                    .room_id(room.id())
+                    .execute(&conn)
+                    .expect("Failed to list agents");
 
-        // Authorize agents listing in the room.
-        let room_id = room.id().to_string();
-        let object = vec!["rooms", &room_id, "agents"];
+                assert_eq!(agents.len(), 1);
+            });
+        }
 
-        let authz_time = context
-            .authz()
-            .authorize(room.audience(), reqp, object, "list")
-            .await?;
+        #[test]
+        fn move_banned_agent_to_breakout_room() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+                let moved_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-        // Get agents list in the room.
-        let agents = {
-            let conn = context.get_conn()?;
+                let (room, breakout_room) = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
 
-            db::agent::ListQuery::new()
-                .room_id(payload.room_id)
-                .offset(payload.offset.unwrap_or_else(|| 0))
-                .limit(std::cmp::min(
-                    payload.limit.unwrap_or_else(|| MAX_LIMIT),
-                    MAX_LIMIT,
-                ))
-                .execute(&conn)?
-        };
+                    // Create a breakout room with the agent's account banned in it.
+                    let room = shared_helpers::insert_room(&conn);
+                    let now = Utc::now().trunc_subsecs(0);
 
-        // Respond with agents list.
-        Ok(Box::new(stream::once(helpers::build_response(
-            ResponseStatus::OK,
-            agents,
-            reqp,
-            context.start_timestamp(),
-            Some(authz_time),
-        ))))
-    }
-}
+                    let breakout_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .parent_id(room.id())
+                        .insert(&conn);
 
-///////////////////////////////////////////////////////////////////////////////
+                    db::room_ban::InsertQuery::new(breakout_room.id(), moved_agent.account_id())
+                        .execute(&conn)
+                        .expect("Failed to ban account");
 
-#[cfg(test)]
-mod tests {
-    mod list {
-        use serde_derive::Deserialize;
-        use svc_agent::AgentId;
-        use uuid::Uuid;
+                    shared_helpers::insert_agent(&conn, moved_agent.agent_id(), room.id());
+                    (room, breakout_room)
+                };
 
-        use crate::test_helpers::prelude::*;
+                // Allow agent to move agents in both rooms.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                let breakout_room_id = breakout_room.id().to_string();
 
-        use super::super::*;
+                for object_room_id in &[&room_id, &breakout_room_id] {
+                    authz.allow(
+                        agent.account_id(),
+                        vec!["rooms", object_room_id, "agents"],
+                        "update",
+                    );
+                }
 
-        ///////////////////////////////////////////////////////////////////////////
+                let mut context = TestContext::new(db, authz);
 
-        #[derive(Deserialize)]
-        struct Agent {
-            agent_id: AgentId,
-            room_id: Uuid,
+                let payload = MoveRequest {
+                    room_id: room.id(),
+                    agent_id: moved_agent.agent_id().to_owned(),
+                    target_room_id: breakout_room.id(),
+                };
+
+                let err = handle_request::<MoveHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on agent moving");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "agent_banned");
+            });
         }
 
         #[test]
-        fn list_agents() {
+        fn move_agent_to_unrelated_room() {
             async_std::task::block_on(async {
                 let db = TestDb::new();
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+                let moved_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-                let room = {
+                let (room, other_room) = {
                     let conn = db
                         .connection_pool()
                         .get()
                         .expect("Failed to get DB connection");
 
-                    // Create room and put the agent online.
                     let room = shared_helpers::insert_room(&conn);
-                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
-                    room
+                    let other_room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, moved_agent.agent_id(), room.id());
+                    (room, other_room)
                 };
 
-                // Allow agent to list agents in the room.
                 let mut authz = TestAuthz::new();
                 let room_id = room.id().to_string();
 
                 authz.allow(
                     agent.account_id(),
                     vec!["rooms", &room_id, "agents"],
-                    "list",
+                    "update",
                 );
 
-                // Make agent.list request.
                 let mut context = TestContext::new(db, authz);
 
-                let payload = ListRequest {
+                let payload = MoveRequest {
                     room_id: room.id(),
-                    offset: None,
-                    limit: None,
+                    agent_id: moved_agent.agent_id().to_owned(),
+                    target_room_id: other_room.id(),
                 };
 
-                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                let err = handle_request::<MoveHandler>(&mut context, &agent, payload)
                     .await
-                    .expect("Agents listing failed");
+                    .expect_err("Unexpected success on agent moving");
+
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "room_not_related");
+            });
+        }
+    }
+
+    mod grant {
+        use crate::app::API_VERSION;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[test]
+        fn grant_role() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+                let speaker = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    shared_helpers::insert_room(&conn)
+                };
+
+                // Allow agent to manage roles in the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "roles"],
+                    "update",
+                );
+
+                // Make agent.grant request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = GrantRequest {
+                    room_id: room.id(),
+                    agent_id: speaker.agent_id().to_owned(),
+                    role: Role::Speaker,
+                };
+
+                let messages = handle_request::<GrantHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Role granting failed");
 
                 // Assert response.
-                let (agents, respp) = find_response::<Vec<Agent>>(messages.as_slice());
+                let (room_role, respp) =
+                    find_response::<db::room_role::Object>(messages.as_slice());
+
                 assert_eq!(respp.status(), ResponseStatus::OK);
-                assert_eq!(agents.len(), 1);
-                assert_eq!(&agents[0].agent_id, agent.agent_id());
-                assert_eq!(agents[0].room_id, room.id());
+                assert_eq!(room_role.account_id(), speaker.account_id());
+                assert_eq!(room_role.role(), Role::Speaker);
+
+                // Assert notification.
+                let (_, evp, topic) = find_event::<db::room_role::Object>(messages.as_slice());
+                assert_eq!(evp.label(), "agent.grant");
+
+                let expected_topic = format!(
+                    "apps/conference.{}/api/{}/rooms/{}/events",
+                    SVC_AUDIENCE,
+                    API_VERSION,
+                    room.id(),
+                );
+
+                assert_eq!(topic, expected_topic);
+
+                // Assert the role is persisted.
+                let conn = context.db().get().expect("Failed to get DB connection");
+
+                let room_role = db::room_role::FindQuery::new(room.id(), speaker.account_id())
+                    .execute(&conn)
+                    .expect("Failed to find role")
+                    .expect("Role not found");
+
+                assert_eq!(room_role.role(), Role::Speaker);
             });
         }
 
         #[test]
-        fn list_agents_not_authorized() {
+        fn grant_role_as_host() {
             async_std::task::block_on(async {
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
                 let db = TestDb::new();
+                let agent = TestAgent::new("web", "host", USR_AUDIENCE);
+                let speaker = TestAgent::new("web", "user123", USR_AUDIENCE);
 
                 let room = {
                     let conn = db
@@ -151,20 +2002,68 @@ mod tests {
                         .get()
                         .expect("Failed to get DB connection");
 
-                    shared_helpers::insert_room(&conn)
+                    // Create room and make the agent a host in it.
+                    let room = shared_helpers::insert_room(&conn);
+
+                    db::room_role::UpsertQuery::new(room.id(), agent.account_id(), Role::Host)
+                        .execute(&conn)
+                        .expect("Failed to grant role");
+
+                    room
                 };
 
+                // Make agent.grant request with no authz rules.
                 let mut context = TestContext::new(db, TestAuthz::new());
 
-                let payload = ListRequest {
+                let payload = GrantRequest {
                     room_id: room.id(),
-                    offset: None,
-                    limit: None,
+                    agent_id: speaker.agent_id().to_owned(),
+                    role: Role::Speaker,
                 };
 
-                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                let messages = handle_request::<GrantHandler>(&mut context, &agent, payload)
                     .await
-                    .expect_err("Unexpected success on agents listing");
+                    .expect("Role granting failed");
+
+                let (_, respp) = find_response::<db::room_role::Object>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+            });
+        }
+
+        #[test]
+        fn grant_role_as_speaker() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "speaker", USR_AUDIENCE);
+                let other_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room and make the agent a speaker in it.
+                    let room = shared_helpers::insert_room(&conn);
+
+                    db::room_role::UpsertQuery::new(room.id(), agent.account_id(), Role::Speaker)
+                        .execute(&conn)
+                        .expect("Failed to grant role");
+
+                    room
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = GrantRequest {
+                    room_id: room.id(),
+                    agent_id: other_agent.agent_id().to_owned(),
+                    role: Role::Host,
+                };
+
+                let err = handle_request::<GrantHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on role granting");
 
                 assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
                 assert_eq!(err.kind(), "access_denied");
@@ -172,10 +2071,11 @@ mod tests {
         }
 
         #[test]
-        fn list_agents_closed_room() {
+        fn grant_role_as_speaker_authorized() {
             async_std::task::block_on(async {
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
                 let db = TestDb::new();
+                let agent = TestAgent::new("web", "admin", USR_AUDIENCE);
+                let other_agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
                 let room = {
                     let conn = db
@@ -183,56 +2083,111 @@ mod tests {
                         .get()
                         .expect("Failed to get DB connection");
 
-                    // Create closed room.
-                    shared_helpers::insert_closed_room(&conn)
+                    // Create room and make the agent a speaker in it.
+                    let room = shared_helpers::insert_room(&conn);
+
+                    db::room_role::UpsertQuery::new(room.id(), agent.account_id(), Role::Speaker)
+                        .execute(&conn)
+                        .expect("Failed to grant role");
+
+                    room
                 };
 
-                // Allow agent to list agents in the room.
+                // The role doesn't allow managing roles but the authz service does.
                 let mut authz = TestAuthz::new();
                 let room_id = room.id().to_string();
 
                 authz.allow(
                     agent.account_id(),
-                    vec!["rooms", &room_id, "agents"],
-                    "list",
+                    vec!["rooms", &room_id, "roles"],
+                    "update",
                 );
 
-                // Make agent.list request.
                 let mut context = TestContext::new(db, authz);
 
-                let payload = ListRequest {
+                let payload = GrantRequest {
                     room_id: room.id(),
-                    offset: None,
-                    limit: None,
+                    agent_id: other_agent.agent_id().to_owned(),
+                    role: Role::Host,
                 };
 
-                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                let messages = handle_request::<GrantHandler>(&mut context, &agent, payload)
                     .await
-                    .expect_err("Unexpected success on agents listing");
+                    .expect("Role granting failed");
 
-                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-                assert_eq!(err.kind(), "room_closed");
+                let (room_role, respp) =
+                    find_response::<db::room_role::Object>(messages.as_slice());
+
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(room_role.role(), Role::Host);
             });
         }
+    }
+
+    mod revoke {
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
 
         #[test]
-        fn list_agents_missing_room() {
+        fn revoke_role() {
             async_std::task::block_on(async {
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-                let mut context = TestContext::new(TestDb::new(), TestAuthz::new());
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "host", USR_AUDIENCE);
+                let speaker = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-                let payload = ListRequest {
-                    room_id: Uuid::new_v4(),
-                    offset: None,
-                    limit: None,
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room with a host and a speaker.
+                    let room = shared_helpers::insert_room(&conn);
+
+                    db::room_role::UpsertQuery::new(room.id(), agent.account_id(), Role::Host)
+                        .execute(&conn)
+                        .expect("Failed to grant role");
+
+                    db::room_role::UpsertQuery::new(room.id(), speaker.account_id(), Role::Speaker)
+                        .execute(&conn)
+                        .expect("Failed to grant role");
+
+                    room
                 };
 
-                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                // Make agent.revoke request.
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = RevokeRequest {
+                    room_id: room.id(),
+                    agent_id: speaker.agent_id().to_owned(),
+                };
+
+                let messages = handle_request::<RevokeHandler>(&mut context, &agent, payload)
                     .await
-                    .expect_err("Unexpected success on agents listing");
+                    .expect("Role revoking failed");
 
-                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-                assert_eq!(err.kind(), "room_not_found");
+                // Assert response.
+                let (_, respp) = find_response::<serde_json::Value>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                // Assert notification.
+                let (payload, evp, _) = find_event::<serde_json::Value>(messages.as_slice());
+                assert_eq!(evp.label(), "agent.revoke");
+                assert_eq!(payload["account_id"], speaker.account_id().to_string());
+
+                // Assert the role is gone.
+                let conn = context.db().get().expect("Failed to get DB connection");
+
+                let maybe_room_role =
+                    db::room_role::FindQuery::new(room.id(), speaker.account_id())
+                        .execute(&conn)
+                        .expect("Failed to find role");
+
+                assert!(maybe_room_role.is_none());
             });
         }
     }
@@ -2,17 +2,21 @@ use std::ops::Bound;
 
 use anyhow::anyhow;
 use chrono::{DateTime, Duration, Utc};
+use diesel::pg::PgConnection;
 use serde::Serialize;
 use svc_agent::mqtt::{
     IncomingRequestProperties, IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties,
-    OutgoingResponse, ResponseStatus, ShortTermTimingProperties,
+    OutgoingRequest, OutgoingResponse, ResponseStatus, ShortTermTimingProperties,
+    TrackingProperties,
 };
+use svc_agent::{AgentId, Authenticable};
 use uuid::Uuid;
 
 use crate::app::context::Context;
 use crate::app::error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind};
 use crate::app::API_VERSION;
 use crate::db;
+use crate::util::generate_correlation_data;
 
 ///////////////////////////////////////////////////////////////////////////////
 
@@ -46,6 +50,32 @@ pub(crate) fn build_notification(
     Box::new(OutgoingEvent::broadcast(payload, props, path))
 }
 
+/// Builds a notification addressed to a single agent.
+///
+/// Events can't be addressed to an agent so it's being sent as a unicast request.
+/// The response topic is empty since it's not meant to be responded.
+pub(crate) fn build_unicast_notification(
+    label: &'static str,
+    agent_id: &AgentId,
+    payload: impl Serialize + Send + 'static,
+    reqp: &IncomingRequestProperties,
+    start_timestamp: DateTime<Utc>,
+) -> Box<dyn IntoPublishableMessage + Send> {
+    let props = reqp.to_request(
+        label,
+        "",
+        &generate_correlation_data(),
+        ShortTermTimingProperties::until_now(start_timestamp),
+    );
+
+    Box::new(OutgoingRequest::unicast(
+        payload,
+        props,
+        agent_id,
+        API_VERSION,
+    ))
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 pub(crate) enum RoomTimeRequirement {
@@ -138,3 +168,185 @@ pub(crate) fn add_room_logger_tags<C: Context>(context: &mut C, room: &db::room:
         context.add_logger_tags(o!("scope" => scope.to_string()));
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Authorizes the action by the agent's role in the room falling back to the authz service.
+///
+/// Accounts having one of `roles` in the room are allowed without asking the authz service.
+/// Accounts having any other role or no role at all are authorized by the authz service as usual.
+pub(crate) async fn authorize_by_role<C: Context>(
+    context: &mut C,
+    room: &db::room::Object,
+    reqp: &IncomingRequestProperties,
+    object: Vec<&str>,
+    action: &str,
+    roles: &[db::room_role::Role],
+) -> Result<Duration, AppError> {
+    let maybe_role = {
+        let conn = context.get_conn()?;
+
+        db::room_role::FindQuery::new(room.id(), reqp.as_account_id())
+            .execute(&conn)?
+            .map(|room_role| room_role.role())
+    };
+
+    match maybe_role {
+        Some(role) if roles.contains(&role) => Ok(Duration::zero()),
+        _ => context
+            .authz()
+            .authorize(room.audience(), reqp, object, action)
+            .await
+            .map_err(AppError::from),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Stops streams published by the agent in the room and builds `agent.leave` requests
+/// to the backends hosting active streams of the room.
+pub(crate) fn leave_backends<C: Context>(
+    context: &C,
+    room_id: Uuid,
+    agent_id: &AgentId,
+    tracking: &TrackingProperties,
+    conn: &PgConnection,
+) -> Result<Vec<Box<dyn IntoPublishableMessage + Send>>, AppError> {
+    let streams = db::janus_rtc_stream::ListQuery::new()
+        .room_id(room_id)
+        .active(true)
+        .execute(conn)?;
+
+    for stream in streams.iter() {
+        // If the agent is a publisher.
+        if stream.sent_by() == agent_id {
+            // Stop the stream.
+            db::janus_rtc_stream::stop(stream.id(), conn)?;
+
+            // Put stream readers into `ready` status since the stream has gone.
+            db::agent::BulkStatusUpdateQuery::new(db::agent::Status::Ready)
+                .room_id(room_id)
+                .status(db::agent::Status::Connected)
+                .execute(conn)?;
+        }
+    }
+
+    // Send agent.leave requests to those backends where the agent is connected to.
+    let mut backend_ids = streams
+        .iter()
+        .map(|stream| stream.backend_id())
+        .collect::<Vec<&AgentId>>();
+
+    backend_ids.dedup();
+
+    let backends = db::janus_backend::ListQuery::new()
+        .ids(&backend_ids[..])
+        .execute(conn)?;
+
+    let mut messages: Vec<Box<dyn IntoPublishableMessage + Send>> = vec![];
+
+    for backend in backends {
+        let req = context
+            .janus_client()
+            .agent_leave_request(
+                backend.session_id(),
+                backend.handle_id(),
+                agent_id,
+                backend.id(),
+                tracking,
+            )
+            .map_err(|err| err.context("Error creating a backend request"))
+            .error(AppErrorKind::MessageBuildingFailed)?;
+
+        messages.push(Box::new(req));
+    }
+
+    Ok(messages)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Seconds for the broker to subscribe an entering agent after which the entrance
+// is considered abandoned and stops taking a slot in the room.
+const ENTRANCE_TIMEOUT: i64 = 60;
+
+/// Locks the room and checks that the agent is allowed to take a slot in it
+/// by the room's bans and agents limit.
+///
+/// Must be called inside a transaction so that the room stays locked until the agent's
+/// row gets updated. Otherwise concurrent entrances could exceed the limit
+/// and a concurrent ban could be missed.
+pub(crate) fn lock_room_for_agent(
+    room_id: Uuid,
+    agent_id: &AgentId,
+    conn: &PgConnection,
+) -> Result<db::room::Object, AppError> {
+    let room = db::room::lock(room_id, conn)?
+        .ok_or_else(|| anyhow!("Room not found"))
+        .error(AppErrorKind::RoomNotFound)?;
+
+    let ban = db::room_ban::FindQuery::new(room.id(), agent_id.as_account_id()).execute(conn)?;
+
+    if ban.is_some() {
+        return Err(anyhow!("The account is banned in the room")).error(AppErrorKind::AgentBanned);
+    }
+
+    // Agents that have entered take slots as well as the ones entering at the moment
+    // since they stay `in_progress` until the broker subscribes them. Abandoned entrances
+    // expire after a while. The agent's own row is left out so entering the room again
+    // doesn't take one more slot.
+    if let Some(max_agents) = room.max_agents() {
+        let agents_count = db::agent::CountQuery::new()
+            .room_id(room.id())
+            .status(db::agent::Status::Ready)
+            .status(db::agent::Status::Connected)
+            .in_progress_since(Utc::now() - Duration::seconds(ENTRANCE_TIMEOUT))
+            .except_agent_id(agent_id)
+            .execute(conn)?;
+
+        if agents_count >= i64::from(max_agents) {
+            return Err(anyhow!("The room has reached its agents limit"))
+                .error(AppErrorKind::RoomFull);
+        }
+    }
+
+    Ok(room)
+}
+
+/// Registers the agent in the room checking the room's bans and agents limit.
+///
+/// Locks the room so it must be called inside a transaction. Agents entering a lobby room
+/// for the first time are put to `waiting` state unless they are hosts.
+pub(crate) fn register_agent(
+    room_id: Uuid,
+    agent_id: &AgentId,
+    is_host: bool,
+    conn: &PgConnection,
+) -> Result<db::agent::Status, AppError> {
+    let room = lock_room_for_agent(room_id, agent_id, conn)?;
+
+    // Agents already admitted to the room don't return to the lobby on re-entrance.
+    let is_admitted = !db::agent::ListQuery::new()
+        .agent_id(agent_id)
+        .room_id(room.id())
+        .execute(conn)?
+        .is_empty()
+        || !db::agent::ListQuery::new()
+            .agent_id(agent_id)
+            .room_id(room.id())
+            .status(db::agent::Status::InProgress)
+            .execute(conn)?
+            .is_empty();
+
+    let status = if room.lobby() && !is_host && !is_admitted {
+        db::agent::Status::Waiting
+    } else {
+        db::agent::Status::InProgress
+    };
+
+    db::agent::InsertQuery::new(agent_id, room.id())
+        .status(status)
+        .execute(conn)?;
+
+    Ok(status)
+}
@@ -10,13 +10,13 @@ use svc_agent::mqtt::{
     OutgoingResponse, OutgoingResponseProperties, ResponseStatus, ShortTermTimingProperties,
     SubscriptionTopic,
 };
-use svc_agent::{Addressable, AgentId, Subscription};
+use svc_agent::{Addressable, AgentId, Authenticable, Subscription};
 use uuid::Uuid;
 
 use crate::app::context::Context;
 use crate::app::endpoint::prelude::*;
 use crate::app::API_VERSION;
-use crate::db::{self, room::Object as Room};
+use crate::db::{self, room::Object as Room, room_role::Role as RoomRole};
 use crate::util::{from_base64, to_base64};
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -50,6 +50,7 @@ impl RequestHandler for UnicastHandler {
             let conn = context.get_conn()?;
             check_room_presence(&room, reqp.as_agent_id(), &conn)?;
             check_room_presence(&room, &payload.agent_id, &conn)?;
+            check_rate_limit(context, &room, reqp, None)?;
         }
 
         let response_topic =
@@ -69,6 +70,11 @@ impl RequestHandler for UnicastHandler {
             ShortTermTimingProperties::until_now(context.start_timestamp()),
         );
 
+        // Track the call to reply to the caller with an error if the callee doesn't answer in time.
+        if let Some(watchdog) = context.unicast_watchdog() {
+            watchdog.register(&correlation_data, reqp, context.start_timestamp())?;
+        }
+
         let req = OutgoingRequest::unicast(
             payload.data.to_owned(),
             props,
@@ -88,6 +94,58 @@ pub(crate) struct BroadcastRequest {
     room_id: Uuid,
     data: JsonValue,
     label: Option<String>,
+    filter: Option<BroadcastFilter>,
+}
+
+/// Restricts broadcast recipients to the agents in the room matching all of the specified criteria.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct BroadcastFilter {
+    agent_ids: Option<Vec<AgentId>>,
+    account_labels: Option<Vec<String>>,
+    role: Option<RoomRole>,
+}
+
+impl BroadcastFilter {
+    fn resolve(&self, room_id: Uuid, conn: &PgConnection) -> StdResult<Vec<AgentId>, AppError> {
+        let agents = db::agent::ListQuery::new().room_id(room_id).execute(conn)?;
+
+        let room_roles = match self.role {
+            Some(_) => db::room_role::ListQuery::new(room_id).execute(conn)?,
+            None => vec![],
+        };
+
+        let recipients = agents
+            .iter()
+            .map(|agent| agent.agent_id())
+            .filter(|agent_id| self.matches(agent_id, &room_roles))
+            .cloned()
+            .collect();
+
+        Ok(recipients)
+    }
+
+    fn matches(&self, agent_id: &AgentId, room_roles: &[db::room_role::Object]) -> bool {
+        let account_id = agent_id.as_account_id();
+
+        let is_agent_id_matched = match self.agent_ids {
+            Some(ref agent_ids) => agent_ids.contains(agent_id),
+            None => true,
+        };
+
+        let is_account_label_matched = match self.account_labels {
+            Some(ref labels) => labels.iter().any(|label| label == account_id.label()),
+            None => true,
+        };
+
+        let is_role_matched = match self.role {
+            Some(role) => room_roles
+                .iter()
+                .any(|room_role| room_role.account_id() == account_id && room_role.role() == role),
+            None => true,
+        };
+
+        is_agent_id_matched && is_account_label_matched && is_role_matched
+    }
 }
 
 pub(crate) struct BroadcastHandler;
@@ -102,7 +160,7 @@ impl RequestHandler for BroadcastHandler {
         payload: Self::Payload,
         reqp: &IncomingRequestProperties,
     ) -> Result {
-        let room = {
+        let (room, maybe_recipients) = {
             let room = helpers::find_room_by_id(
                 context,
                 payload.room_id,
@@ -111,7 +169,28 @@ impl RequestHandler for BroadcastHandler {
 
             let conn = context.get_conn()?;
             check_room_presence(&room, &reqp.as_agent_id(), &conn)?;
-            room
+            check_rate_limit(context, &room, reqp, payload.label.as_deref())?;
+
+            let maybe_recipients = match payload.filter {
+                Some(ref filter) => Some(filter.resolve(room.id(), &conn)?),
+                None => None,
+            };
+
+            // Store the message for `message.list` if its label is configured to be persistent.
+            // Targeted messages are not stored since they're not meant for everyone in the room.
+            if let (Some(ref label), None) = (&payload.label, &maybe_recipients) {
+                if context.config().message.persistent_labels.contains(label) {
+                    db::message::InsertQuery::new(
+                        room.id(),
+                        reqp.as_agent_id(),
+                        label,
+                        &payload.data,
+                    )
+                    .execute(&conn)?;
+                }
+            }
+
+            (room, maybe_recipients)
         };
 
         if let Some(stats) = context.dynamic_stats() {
@@ -120,7 +199,6 @@ impl RequestHandler for BroadcastHandler {
             }
         }
 
-        // Respond and broadcast to the room topic.
         let response = helpers::build_response(
             ResponseStatus::OK,
             json!({}),
@@ -129,15 +207,112 @@ impl RequestHandler for BroadcastHandler {
             None,
         );
 
-        let notification = helpers::build_notification(
-            "message.broadcast",
-            &format!("rooms/{}/events", room.id()),
-            payload.data,
+        let mut messages = vec![response];
+
+        match maybe_recipients {
+            // Fan out to the matching agents only.
+            Some(recipients) => {
+                for agent_id in recipients {
+                    messages.push(helpers::build_unicast_notification(
+                        "message.broadcast.notify",
+                        &agent_id,
+                        payload.data.clone(),
+                        reqp,
+                        context.start_timestamp(),
+                    ));
+                }
+            }
+            // Broadcast to the room topic.
+            None => {
+                messages.push(helpers::build_notification(
+                    "message.broadcast",
+                    &format!("rooms/{}/events", room.id()),
+                    payload.data,
+                    reqp,
+                    context.start_timestamp(),
+                ));
+            }
+        }
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+const MAX_LIMIT: i64 = 25;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListRequest {
+    room_id: Uuid,
+    label: Option<String>,
+    #[serde(default)]
+    #[serde(with = "crate::serde::ts_seconds_option_bound_tuple")]
+    time: Option<db::room::Time>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+pub(crate) struct ListHandler;
+
+#[async_trait]
+impl RequestHandler for ListHandler {
+    type Payload = ListRequest;
+    const ERROR_TITLE: &'static str = "Failed to list messages";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.room_id, helpers::RoomTimeRequirement::Any)?;
+
+        // Authorize messages listing in the room.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id, "messages"];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "list")
+            .await?;
+
+        if payload.offset.map(|offset| offset < 0).unwrap_or(false)
+            || payload.limit.map(|limit| limit < 0).unwrap_or(false)
+        {
+            return Err(anyhow!("Negative offset or limit")).error(AppErrorKind::InvalidPagination);
+        }
+
+        // Get messages list in the room.
+        let messages = {
+            let conn = context.get_conn()?;
+
+            let mut query = db::message::ListQuery::new(room.id())
+                .offset(payload.offset.unwrap_or_else(|| 0))
+                .limit(std::cmp::min(
+                    payload.limit.unwrap_or_else(|| MAX_LIMIT),
+                    MAX_LIMIT,
+                ));
+
+            if let Some(ref label) = payload.label {
+                query = query.label(label);
+            }
+
+            if let Some(time) = payload.time {
+                query = query.time(time);
+            }
+
+            query.execute(&conn)?
+        };
+
+        // Respond with messages list.
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            messages,
             reqp,
             context.start_timestamp(),
-        );
-
-        Ok(Box::new(stream::from_iter(vec![response, notification])))
+            Some(authz_time),
+        ))))
     }
 }
 
@@ -154,6 +329,17 @@ impl ResponseHandler for CallbackHandler {
         payload: Self::Payload,
         respp: &IncomingResponseProperties,
     ) -> Result {
+        if let Some(watchdog) = context.unicast_watchdog() {
+            if !watchdog.finish(respp.correlation_data())? {
+                warn!(
+                    context.logger(),
+                    "Dropping callback to a timed out or already answered unicast call"
+                );
+
+                return Ok(Box::new(stream::empty()));
+            }
+        }
+
         let reqp = from_base64::<IncomingRequestProperties>(respp.correlation_data())
             .error(AppErrorKind::MessageParsingFailed)?;
 
@@ -198,6 +384,45 @@ fn check_room_presence(
     }
 }
 
+fn check_rate_limit<C: Context>(
+    context: &C,
+    room: &Room,
+    reqp: &IncomingRequestProperties,
+    label: Option<&str>,
+) -> StdResult<(), AppError> {
+    let rate_limit = &context.config().message.rate_limit;
+
+    // Labels with their own limits have separate buckets, others share the agent's bucket.
+    let label = label.filter(|label| rate_limit.labels.contains_key(*label));
+
+    let bucket_config = match rate_limit.bucket_config(room.audience(), label) {
+        Some(bucket_config) => bucket_config,
+        None => return Ok(()),
+    };
+
+    let key = match label {
+        Some(label) => format!("{}.{}.{}", room.id(), reqp.as_agent_id(), label),
+        None => format!("{}.{}", room.id(), reqp.as_agent_id()),
+    };
+
+    let redis_pool = context.redis_pool().as_ref();
+    let is_allowed = context
+        .rate_limiter()
+        .check(redis_pool, &key, bucket_config);
+
+    if is_allowed {
+        return Ok(());
+    }
+
+    if let Some(stats) = context.dynamic_stats() {
+        let stats_key = format!("{}_rate_limited", reqp.method().replace('.', "_"));
+        stats.collect(&stats_key, 1);
+    }
+
+    Err(anyhow!("Too many messages from the agent in the room"))
+        .error(AppErrorKind::RateLimitExceeded)
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
@@ -252,7 +477,7 @@ mod test {
                     .expect("Unicast message sending failed");
 
                 // Assert outgoing request.
-                let (payload, _reqp, topic) = find_request::<JsonValue>(messages.as_slice());
+                let (payload, reqp, topic) = find_request::<JsonValue>(messages.as_slice());
 
                 let expected_topic = format!(
                     "agents/{}/api/{}/in/conference.{}",
@@ -263,6 +488,14 @@ mod test {
 
                 assert_eq!(topic, expected_topic);
                 assert_eq!(payload, json!({"key": "value"}));
+
+                // Assert the call is awaiting for a callback.
+                let watchdog = context
+                    .unicast_watchdog()
+                    .expect("Missing unicast watchdog");
+                assert!(watchdog
+                    .finish(reqp.correlation_data())
+                    .expect("Failed to finish call"));
             });
         }
 
@@ -405,6 +638,7 @@ mod test {
                     room_id: room.id(),
                     data: json!({ "key": "value" }),
                     label: None,
+                    filter: None,
                 };
 
                 let messages = handle_request::<BroadcastHandler>(&mut context, &sender, payload)
@@ -430,6 +664,182 @@ mod test {
             });
         }
 
+        #[test]
+        fn broadcast_persistent_message() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+
+                // Insert room with online agent.
+                let room = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        let room = shared_helpers::insert_room(&conn);
+                        let agent_factory = factory::Agent::new().room_id(room.id());
+                        agent_factory.agent_id(sender.agent_id()).insert(&conn);
+                        room
+                    })
+                    .expect("Failed to insert room");
+
+                // Make message.broadcast requests with persistent and non-persistent labels.
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                for label in &["chat", "cursor"] {
+                    let payload = BroadcastRequest {
+                        room_id: room.id(),
+                        data: json!({ "key": label }),
+                        label: Some(label.to_string()),
+                        filter: None,
+                    };
+
+                    handle_request::<BroadcastHandler>(&mut context, &sender, payload)
+                        .await
+                        .expect("Broadcast message sending failed");
+                }
+
+                // Assert only the persistent one is stored.
+                let conn = context.db().get().expect("Failed to get DB connection");
+
+                let messages = db::message::ListQuery::new(room.id())
+                    .execute(&conn)
+                    .expect("Failed to list messages");
+
+                assert_eq!(messages.len(), 1);
+                assert_eq!(messages[0].label(), "chat");
+                assert_eq!(messages[0].data(), &json!({ "key": "chat" }));
+            });
+        }
+
+        #[test]
+        fn broadcast_message_over_rate_limit() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+
+                // Insert room with online agent.
+                let room = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        let room = shared_helpers::insert_room(&conn);
+                        let agent_factory = factory::Agent::new().room_id(room.id());
+                        agent_factory.agent_id(sender.agent_id()).insert(&conn);
+                        room
+                    })
+                    .expect("Failed to insert room");
+
+                // The `limited` label allows a single message in the test config.
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = BroadcastRequest {
+                    room_id: room.id(),
+                    data: json!({ "key": "value" }),
+                    label: Some(String::from("limited")),
+                    filter: None,
+                };
+
+                handle_request::<BroadcastHandler>(&mut context, &sender, payload)
+                    .await
+                    .expect("Broadcast message sending failed");
+
+                let payload = BroadcastRequest {
+                    room_id: room.id(),
+                    data: json!({ "key": "value" }),
+                    label: Some(String::from("limited")),
+                    filter: None,
+                };
+
+                let err = handle_request::<BroadcastHandler>(&mut context, &sender, payload)
+                    .await
+                    .expect_err("Unexpected success on broadcast message sending");
+
+                assert_eq!(err.status(), ResponseStatus::TOO_MANY_REQUESTS);
+                assert_eq!(err.kind(), "rate_limit_exceeded");
+
+                // Messages with other labels are not limited.
+                let payload = BroadcastRequest {
+                    room_id: room.id(),
+                    data: json!({ "key": "value" }),
+                    label: None,
+                    filter: None,
+                };
+
+                handle_request::<BroadcastHandler>(&mut context, &sender, payload)
+                    .await
+                    .expect("Broadcast message sending failed");
+            });
+        }
+
+        #[test]
+        fn broadcast_message_with_filter() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+                let host = TestAgent::new("web", "host", USR_AUDIENCE);
+                let listener = TestAgent::new("web", "listener", USR_AUDIENCE);
+
+                // Insert room with online agents and a host role.
+                let room = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        let room = shared_helpers::insert_room(&conn);
+
+                        for agent in &[&sender, &host, &listener] {
+                            factory::Agent::new()
+                                .room_id(room.id())
+                                .agent_id(agent.agent_id())
+                                .insert(&conn);
+                        }
+
+                        db::room_role::UpsertQuery::new(
+                            room.id(),
+                            host.account_id(),
+                            RoomRole::Host,
+                        )
+                        .execute(&conn)
+                        .expect("Failed to insert room role");
+
+                        room
+                    })
+                    .expect("Failed to insert room");
+
+                // Make message.broadcast request to hosts only.
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = BroadcastRequest {
+                    room_id: room.id(),
+                    data: json!({ "key": "value" }),
+                    label: None,
+                    filter: Some(BroadcastFilter {
+                        role: Some(RoomRole::Host),
+                        ..Default::default()
+                    }),
+                };
+
+                let messages = handle_request::<BroadcastHandler>(&mut context, &sender, payload)
+                    .await
+                    .expect("Broadcast message sending failed");
+
+                // Assert the only notification is sent to the host.
+                assert_eq!(messages.len(), 2);
+                let (payload, reqp, topic) = find_request::<JsonValue>(messages.as_slice());
+
+                let expected_topic = format!(
+                    "agents/{}/api/{}/in/conference.{}",
+                    host.agent_id(),
+                    API_VERSION,
+                    SVC_AUDIENCE,
+                );
+
+                assert_eq!(reqp.method(), "message.broadcast.notify");
+                assert_eq!(reqp.response_topic(), "");
+                assert_eq!(topic, expected_topic);
+                assert_eq!(payload, json!({"key": "value"}));
+            });
+        }
+
         #[test]
         fn broadcast_message_to_missing_room() {
             async_std::task::block_on(async {
@@ -441,6 +851,7 @@ mod test {
                     room_id: Uuid::new_v4(),
                     data: json!({ "key": "value" }),
                     label: None,
+                    filter: None,
                 };
 
                 let err = handle_request::<BroadcastHandler>(&mut context, &sender, payload)
@@ -472,6 +883,7 @@ mod test {
                     room_id: room.id(),
                     data: json!({ "key": "value" }),
                     label: None,
+                    filter: None,
                 };
 
                 let err = handle_request::<BroadcastHandler>(&mut context, &sender, payload)
@@ -483,4 +895,255 @@ mod test {
             });
         }
     }
+
+    mod callback {
+        use crate::test_helpers::build_respp_with_correlation_data;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn callback_handled_by_another_replica() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+                let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
+
+                // Insert room with online both sender and receiver.
+                let room = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        let room = shared_helpers::insert_room(&conn);
+
+                        for agent in &[&sender, &receiver] {
+                            factory::Agent::new()
+                                .room_id(room.id())
+                                .agent_id(agent.agent_id())
+                                .insert(&conn);
+                        }
+
+                        room
+                    })
+                    .expect("Failed to insert room");
+
+                // Make message.unicast request to one replica.
+                let mut context = TestContext::new(db.clone(), TestAuthz::new());
+
+                let payload = UnicastRequest {
+                    agent_id: receiver.agent_id().to_owned(),
+                    room_id: room.id(),
+                    data: json!({ "key": "value" }),
+                };
+
+                let messages = handle_request::<UnicastHandler>(&mut context, &sender, payload)
+                    .await
+                    .expect("Unicast message sending failed");
+
+                let (_, reqp, _) = find_request::<JsonValue>(messages.as_slice());
+
+                // Make message.callback to another replica sharing the same DB.
+                let mut other_context = TestContext::new(db, TestAuthz::new());
+                let respp =
+                    build_respp_with_correlation_data(receiver.agent_id(), reqp.correlation_data());
+
+                let messages =
+                    CallbackHandler::handle(&mut other_context, json!({ "key": "value" }), &respp)
+                        .await
+                        .expect("Callback handling failed");
+
+                // Assert the callback has been relayed to the caller.
+                let messages = parse_messages(messages).await;
+                let (payload, respp) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(payload, json!({ "key": "value" }));
+
+                // Assert the call is finished so it can't time out on the first replica.
+                let watchdog = context
+                    .unicast_watchdog()
+                    .expect("Missing unicast watchdog");
+
+                assert!(!watchdog
+                    .finish(reqp.correlation_data())
+                    .expect("Failed to finish call"));
+            });
+        }
+
+        #[test]
+        fn callback_to_unknown_call() {
+            async_std::task::block_on(async {
+                let mut context = TestContext::new(TestDb::new(), TestAuthz::new());
+                let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+                let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
+
+                // The call has either timed out or been answered already.
+                let reqp = build_reqp(sender.agent_id(), "message.unicast");
+                let corr_data = to_base64(&reqp).expect("Failed to encode reqp");
+                let respp = build_respp_with_correlation_data(receiver.agent_id(), &corr_data);
+
+                let messages =
+                    CallbackHandler::handle(&mut context, json!({ "key": "value" }), &respp)
+                        .await
+                        .expect("Callback handling failed");
+
+                // Assert the callback has been dropped.
+                let messages = parse_messages(messages).await;
+                assert!(messages.is_empty());
+            });
+        }
+    }
+
+    mod list {
+        use serde_derive::Deserialize;
+
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[derive(Deserialize)]
+        struct Message {
+            label: String,
+            data: JsonValue,
+        }
+
+        #[test]
+        fn list_messages() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room with messages of different labels.
+                    let room = shared_helpers::insert_room(&conn);
+
+                    for (label, data) in &[
+                        ("chat", json!({ "text": "hello" })),
+                        ("poll", json!({ "question": "?" })),
+                    ] {
+                        db::message::InsertQuery::new(room.id(), agent.agent_id(), label, data)
+                            .execute(&conn)
+                            .expect("Failed to insert message");
+                    }
+
+                    room
+                };
+
+                // Allow agent to list messages in the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "messages"],
+                    "list",
+                );
+
+                // Make message.list request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    label: Some(String::from("chat")),
+                    time: None,
+                    offset: None,
+                    limit: None,
+                };
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Messages listing failed");
+
+                // Assert response.
+                let (messages, respp) = find_response::<Vec<Message>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(messages.len(), 1);
+                assert_eq!(messages[0].label, "chat");
+                assert_eq!(messages[0].data, json!({ "text": "hello" }));
+            });
+        }
+
+        #[test]
+        fn list_messages_negative_pagination() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    shared_helpers::insert_room(&conn)
+                };
+
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "messages"],
+                    "list",
+                );
+
+                let mut context = TestContext::new(db, authz);
+
+                for &(offset, limit) in &[(Some(-1), None), (None, Some(-1))] {
+                    let payload = ListRequest {
+                        room_id: room.id(),
+                        label: None,
+                        time: None,
+                        offset,
+                        limit,
+                    };
+
+                    let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect_err("Unexpected success on messages listing");
+
+                    assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                    assert_eq!(err.kind(), "invalid_pagination");
+                }
+            });
+        }
+
+        #[test]
+        fn list_messages_not_authorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    shared_helpers::insert_room(&conn)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    label: None,
+                    time: None,
+                    offset: None,
+                    limit: None,
+                };
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on messages listing");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
 }
@@ -50,21 +50,34 @@ macro_rules! request_routes {
 
 // Request routes configuration: method => RequestHandler
 request_routes!(
+    "agent.admit" => agent::AdmitHandler,
+    "agent.grant" => agent::GrantHandler,
+    "agent.kick" => agent::KickHandler,
     "agent.list" => agent::ListHandler,
+    "agent.move" => agent::MoveHandler,
+    "agent.reject" => agent::RejectHandler,
+    "agent.revoke" => agent::RevokeHandler,
     "message.broadcast" => message::BroadcastHandler,
+    "message.list" => message::ListHandler,
     "message.unicast" => message::UnicastHandler,
+    "recording.list" => recording::ListHandler,
+    "recording.read" => recording::ReadHandler,
+    "room.close" => room::CloseHandler,
     "room.create" => room::CreateHandler,
     "room.delete" => room::DeleteHandler,
     "room.enter" => room::EnterHandler,
     "room.leave" => room::LeaveHandler,
+    "room.list" => room::ListHandler,
     "room.read" => room::ReadHandler,
     "room.update" => room::UpdateHandler,
+    "room.upload" => room::UploadHandler,
     "rtc.connect" => rtc::ConnectHandler,
     "rtc.create" => rtc::CreateHandler,
     "rtc.list" => rtc::ListHandler,
     "rtc.read" => rtc::ReadHandler,
     "rtc_signal.create" => rtc_signal::CreateHandler,
     "rtc_stream.list" => rtc_stream::ListHandler,
+    "system.drain" => system::DrainHandler,
     "system.vacuum" => system::VacuumHandler
 );
 
@@ -139,10 +152,11 @@ event_routes!(
 
 ///////////////////////////////////////////////////////////////////////////////
 
-mod agent;
+pub(crate) mod agent;
 pub(crate) mod helpers;
 mod message;
 mod metric;
+mod recording;
 mod room;
 pub(crate) mod rtc;
 pub(crate) mod rtc_signal;
@@ -0,0 +1,445 @@
+use std::ops::Bound;
+use std::result::Result as StdResult;
+
+use async_std::stream;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use svc_agent::mqtt::{IncomingRequestProperties, ResponseStatus};
+use svc_agent::AgentId;
+use uuid::Uuid;
+
+use crate::app::context::Context;
+use crate::app::endpoint::prelude::*;
+use crate::app::endpoint::system;
+use crate::db;
+use crate::db::recording::{Object as Recording, Status as RecordingStatus};
+use crate::db::room::Object as Room;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Serialize)]
+struct RecordingData {
+    rtc_id: Uuid,
+    status: RecordingStatus,
+    #[serde(
+        serialize_with = "crate::serde::milliseconds_bound_tuples_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    segments: Option<Vec<(Bound<i64>, Bound<i64>)>>,
+    #[serde(
+        serialize_with = "crate::serde::ts_milliseconds_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    started_at: Option<DateTime<Utc>>,
+    backend_id: AgentId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri: Option<String>,
+}
+
+impl RecordingData {
+    fn new<C: Context>(
+        context: &C,
+        room: &Room,
+        recording: Recording,
+    ) -> StdResult<Self, AppError> {
+        // The URI is known only after the recording has been uploaded.
+        let uri = match recording.status() {
+            RecordingStatus::Ready => {
+                let config = system::upload_config(context, room)?;
+                Some(system::recording_uri(config, room, &recording))
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            rtc_id: recording.rtc_id(),
+            status: recording.status().to_owned(),
+            segments: recording.segments().to_owned(),
+            started_at: recording.started_at().to_owned(),
+            backend_id: recording.backend_id().to_owned(),
+            uri,
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReadRequest {
+    rtc_id: Uuid,
+}
+
+pub(crate) struct ReadHandler;
+
+#[async_trait]
+impl RequestHandler for ReadHandler {
+    type Payload = ReadRequest;
+    const ERROR_TITLE: &'static str = "Failed to read recording";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room = helpers::find_room_by_rtc_id(
+            context,
+            payload.rtc_id,
+            helpers::RoomTimeRequirement::Any,
+        )?;
+
+        // Authorize recording reading the same way as rtc reading.
+        let rtc_id = payload.rtc_id.to_string();
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "read")
+            .await?;
+
+        // Return recording.
+        let recording = {
+            let conn = context.get_conn()?;
+
+            db::recording::FindQuery::new(payload.rtc_id)
+                .execute(&conn)?
+                .ok_or_else(|| anyhow!("Recording not found"))
+                .error(AppErrorKind::RecordingNotFound)?
+        };
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            RecordingData::new(context, &room, recording)?,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+const MAX_LIMIT: i64 = 25;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListRequest {
+    room_id: Uuid,
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+pub(crate) struct ListHandler;
+
+#[async_trait]
+impl RequestHandler for ListHandler {
+    type Payload = ListRequest;
+    const ERROR_TITLE: &'static str = "Failed to list recordings";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.room_id, helpers::RoomTimeRequirement::Any)?;
+
+        // Authorize recording listing the same way as rtc listing.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id, "rtcs"];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "list")
+            .await?;
+
+        if payload.offset.map(|offset| offset < 0).unwrap_or(false)
+            || payload.limit.map(|limit| limit < 0).unwrap_or(false)
+        {
+            return Err(anyhow!("Negative offset or limit")).error(AppErrorKind::InvalidPagination);
+        }
+
+        // Return recording list.
+        let mut query = db::recording::ListQuery::new(payload.room_id);
+
+        if let Some(offset) = payload.offset {
+            query = query.offset(offset);
+        }
+
+        let limit = std::cmp::min(payload.limit.unwrap_or_else(|| MAX_LIMIT), MAX_LIMIT);
+        query = query.limit(limit);
+
+        let recordings = {
+            let conn = context.get_conn()?;
+            query.execute(&conn)?
+        };
+
+        let recordings = recordings
+            .into_iter()
+            .map(|recording| RecordingData::new(context, &room, recording))
+            .collect::<StdResult<Vec<RecordingData>, AppError>>()?;
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            recordings,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    mod read {
+        use serde_json::Value as JsonValue;
+
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn read_recording() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+
+                let rtc = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        // Insert an uploaded recording.
+                        let rtc = shared_helpers::insert_rtc(&conn);
+                        let backend = shared_helpers::insert_janus_backend(&conn);
+                        shared_helpers::insert_recording(&conn, &rtc, &backend);
+                        let segments = vec![(Bound::Included(0), Bound::Excluded(1000))];
+
+                        db::recording::UpdateQuery::new(rtc.id())
+                            .status(RecordingStatus::Ready)
+                            .started_at(Utc::now())
+                            .segments(segments)
+                            .execute(&conn)
+                            .unwrap();
+
+                        rtc
+                    })
+                    .unwrap();
+
+                // Allow agent to read the rtc.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let room_id = rtc.room_id().to_string();
+                let rtc_id = rtc.id().to_string();
+                let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+                authz.allow(agent.account_id(), object, "read");
+
+                // Make recording.read request.
+                let mut context = TestContext::new(db, authz);
+                let payload = ReadRequest { rtc_id: rtc.id() };
+
+                let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Recording reading failed");
+
+                // Assert response.
+                let (resp, respp) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(resp["rtc_id"], rtc.id().to_string());
+                assert_eq!(resp["status"], "ready");
+                assert_eq!(resp["segments"], serde_json::json!([[0, 1000]]));
+
+                assert_eq!(
+                    resp["uri"],
+                    format!(
+                        "s3://origin.webinar.{}/{}.source.webm",
+                        USR_AUDIENCE,
+                        rtc.id()
+                    ),
+                );
+            });
+        }
+
+        #[test]
+        fn read_recording_not_authorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let rtc = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| shared_helpers::insert_rtc(&conn))
+                    .unwrap();
+
+                // Make recording.read request.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let payload = ReadRequest { rtc_id: rtc.id() };
+
+                let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success reading recording");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+
+        #[test]
+        fn read_recording_missing() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+
+                let rtc = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| shared_helpers::insert_rtc(&conn))
+                    .unwrap();
+
+                // Allow agent to read the rtc.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let room_id = rtc.room_id().to_string();
+                let rtc_id = rtc.id().to_string();
+                let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+                authz.allow(agent.account_id(), object, "read");
+
+                // Make recording.read request.
+                let mut context = TestContext::new(db, authz);
+                let payload = ReadRequest { rtc_id: rtc.id() };
+
+                let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success reading recording");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "recording_not_found");
+            });
+        }
+    }
+
+    mod list {
+        use serde_json::Value as JsonValue;
+
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn list_recordings() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+
+                let (room, rtc) = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        // Insert a room with an in-progress recording and another room's one.
+                        let backend = shared_helpers::insert_janus_backend(&conn);
+                        let room = shared_helpers::insert_room(&conn);
+                        let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                        let other_rtc = shared_helpers::insert_rtc(&conn);
+                        shared_helpers::insert_recording(&conn, &rtc, &backend);
+                        shared_helpers::insert_recording(&conn, &other_rtc, &backend);
+                        (room, rtc)
+                    })
+                    .unwrap();
+
+                // Allow agent to list rtcs in the room.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id, "rtcs"];
+                authz.allow(agent.account_id(), object, "list");
+
+                // Make recording.list request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    offset: None,
+                    limit: None,
+                };
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Recordings listing failed");
+
+                // Assert response.
+                let (resp, respp) = find_response::<Vec<JsonValue>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(resp.len(), 1);
+                assert_eq!(resp[0]["rtc_id"], rtc.id().to_string());
+                assert_eq!(resp[0]["status"], "in_progress");
+                assert!(resp[0].get("uri").is_none());
+            });
+        }
+
+        #[test]
+        fn list_recordings_negative_pagination() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+
+                let room = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| shared_helpers::insert_room(&conn))
+                    .unwrap();
+
+                // Allow agent to list rtcs in the room.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let room_id = room.id().to_string();
+                let object = vec!["rooms", &room_id, "rtcs"];
+                authz.allow(agent.account_id(), object, "list");
+
+                let mut context = TestContext::new(db, authz);
+
+                for &(offset, limit) in &[(Some(-1), None), (None, Some(-1))] {
+                    let payload = ListRequest {
+                        room_id: room.id(),
+                        offset,
+                        limit,
+                    };
+
+                    let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect_err("Unexpected success listing recordings");
+
+                    assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                    assert_eq!(err.kind(), "invalid_pagination");
+                }
+            });
+        }
+
+        #[test]
+        fn list_recordings_not_authorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let room = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| shared_helpers::insert_room(&conn))
+                    .unwrap();
+
+                // Make recording.list request.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = ListRequest {
+                    room_id: room.id(),
+                    offset: None,
+                    limit: None,
+                };
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success listing recordings");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
+}
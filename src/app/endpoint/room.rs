@@ -3,8 +3,9 @@ use std::ops::Bound;
 use async_std::stream;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use diesel::Connection;
 use serde_derive::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
+use serde_json::{json, Value as JsonValue};
 use svc_agent::mqtt::{
     IncomingRequestProperties, IntoPublishableMessage, OutgoingRequest, ResponseStatus,
     ShortTermTimingProperties,
@@ -14,20 +15,24 @@ use uuid::Uuid;
 
 use crate::app::context::Context;
 use crate::app::endpoint::prelude::*;
+use crate::app::endpoint::subscription::RoomEnterLeaveEvent;
+use crate::app::endpoint::system;
+use crate::app::webhook;
 use crate::db;
+use crate::util::generate_correlation_data;
 
 ///////////////////////////////////////////////////////////////////////////////
 
-const MQTT_GW_API_VERSION: &str = "v1";
+pub(crate) const MQTT_GW_API_VERSION: &str = "v1";
 
 #[derive(Debug, Serialize)]
-struct SubscriptionRequest {
+pub(crate) struct SubscriptionRequest {
     subject: AgentId,
     object: Vec<String>,
 }
 
 impl SubscriptionRequest {
-    fn new(subject: AgentId, object: Vec<&str>) -> Self {
+    pub(crate) fn new(subject: AgentId, object: Vec<&str>) -> Self {
         Self {
             subject,
             object: object.iter().map(|&s| s.into()).collect(),
@@ -46,12 +51,63 @@ pub(crate) struct CreateRequest {
     backend: db::room::RoomBackend,
     reserve: Option<i32>,
     tags: Option<JsonValue>,
+    backend_group: Option<String>,
+    max_agents: Option<i32>,
+    max_publishers: Option<i32>,
+    lobby: Option<bool>,
+    breakout_rooms: Option<u8>,
 }
 
 impl CreateRequest {
     fn default_backend() -> db::room::RoomBackend {
         db::room::RoomBackend::None
     }
+
+    fn insert_query(&self) -> db::room::InsertQuery<'_> {
+        let mut q = db::room::InsertQuery::new(self.time, &self.audience, self.backend);
+
+        if let Some(reserve) = self.reserve {
+            q = q.reserve(reserve);
+        }
+
+        if let Some(ref tags) = self.tags {
+            q = q.tags(tags);
+        }
+
+        if let Some(ref backend_group) = self.backend_group {
+            q = q.backend_group(backend_group);
+        }
+
+        if let Some(max_agents) = self.max_agents {
+            q = q.max_agents(max_agents);
+        }
+
+        if let Some(max_publishers) = self.max_publishers {
+            q = q.max_publishers(max_publishers);
+        }
+
+        if let Some(lobby) = self.lobby {
+            q = q.lobby(lobby);
+        }
+
+        q
+    }
+
+    // Breakout rooms inherit the parent's time, audience, backend, group and tags only.
+    fn breakout_insert_query(&self, parent_id: Uuid) -> db::room::InsertQuery<'_> {
+        let mut q = db::room::InsertQuery::new(self.time, &self.audience, self.backend)
+            .parent_id(parent_id);
+
+        if let Some(ref tags) = self.tags {
+            q = q.tags(tags);
+        }
+
+        if let Some(ref backend_group) = self.backend_group {
+            q = q.backend_group(backend_group);
+        }
+
+        q
+    }
 }
 
 pub(crate) struct CreateHandler;
@@ -72,21 +128,25 @@ impl RequestHandler for CreateHandler {
             .authorize(&payload.audience, reqp, vec!["rooms"], "create")
             .await?;
 
-        // Create a room.
-        let room = {
-            let mut q =
-                db::room::InsertQuery::new(payload.time, &payload.audience, payload.backend);
+        // Create a room along with its breakout rooms.
+        let (room, breakout_rooms) = {
+            let conn = context.get_conn()?;
 
-            if let Some(reserve) = payload.reserve {
-                q = q.reserve(reserve);
-            }
+            conn.transaction::<_, AppError, _>(|| {
+                let room = payload.insert_query().execute(&conn)?;
+                let mut breakout_rooms = vec![];
 
-            if let Some(ref tags) = payload.tags {
-                q = q.tags(tags);
-            }
+                for _ in 0..payload.breakout_rooms.unwrap_or(0) {
+                    let breakout_room = payload.breakout_insert_query(room.id()).execute(&conn)?;
+                    breakout_rooms.push(breakout_room);
+                }
 
-            let conn = context.get_conn()?;
-            q.execute(&conn)?
+                for room in std::iter::once(&room).chain(breakout_rooms.iter()) {
+                    webhook::enqueue(context, room.audience(), "room.create", room, &conn)?;
+                }
+
+                Ok((room, breakout_rooms))
+            })?
         };
 
         helpers::add_room_logger_tags(context, &room);
@@ -101,15 +161,19 @@ impl RequestHandler for CreateHandler {
             Some(authz_time),
         );
 
-        let notification = helpers::build_notification(
-            "room.create",
-            &format!("audiences/{}/events", payload.audience),
-            room,
-            reqp,
-            context.start_timestamp(),
-        );
+        let mut messages = vec![response];
 
-        Ok(Box::new(stream::from_iter(vec![response, notification])))
+        for room in std::iter::once(room).chain(breakout_rooms) {
+            messages.push(helpers::build_notification(
+                "room.create",
+                &format!("audiences/{}/events", payload.audience),
+                room,
+                reqp,
+                context.start_timestamp(),
+            ));
+        }
+
+        Ok(Box::new(stream::from_iter(messages)))
     }
 }
 
@@ -156,6 +220,93 @@ impl RequestHandler for ReadHandler {
 
 ///////////////////////////////////////////////////////////////////////////////
 
+const MAX_LIMIT: i64 = 25;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListRequest {
+    audience: String,
+    #[serde(default)]
+    #[serde(with = "crate::serde::ts_seconds_option_bound_tuple")]
+    time: Option<db::room::Time>,
+    backend: Option<db::room::RoomBackend>,
+    closed: Option<bool>,
+    tags: Option<JsonValue>,
+    parent_id: Option<Uuid>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+pub(crate) struct ListHandler;
+
+#[async_trait]
+impl RequestHandler for ListHandler {
+    type Payload = ListRequest;
+    const ERROR_TITLE: &'static str = "Failed to list rooms";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authorize room listing on the tenant.
+        let authz_time = context
+            .authz()
+            .authorize(&payload.audience, reqp, vec!["rooms"], "list")
+            .await?;
+
+        if payload.offset.map(|offset| offset < 0).unwrap_or(false)
+            || payload.limit.map(|limit| limit < 0).unwrap_or(false)
+        {
+            return Err(anyhow!("Negative offset or limit")).error(AppErrorKind::InvalidPagination);
+        }
+
+        // Return room list.
+        let mut query = db::room::ListQuery::new().audience(&payload.audience);
+
+        if let Some(time) = payload.time {
+            query = query.time(time);
+        }
+
+        if let Some(backend) = payload.backend {
+            query = query.backend(backend);
+        }
+
+        if let Some(closed) = payload.closed {
+            query = query.closed(closed);
+        }
+
+        if let Some(tags) = payload.tags {
+            query = query.tags(tags);
+        }
+
+        if let Some(parent_id) = payload.parent_id {
+            query = query.parent_id(parent_id);
+        }
+
+        if let Some(offset) = payload.offset {
+            query = query.offset(offset);
+        }
+
+        let limit = std::cmp::min(payload.limit.unwrap_or(MAX_LIMIT), MAX_LIMIT);
+        query = query.limit(limit);
+
+        let rooms = {
+            let conn = context.get_conn()?;
+            query.execute(&conn)?
+        };
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            rooms,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Deserialize, Default)]
 pub(crate) struct UpdateRequest {
     id: Uuid,
@@ -166,6 +317,10 @@ pub(crate) struct UpdateRequest {
     backend: Option<db::room::RoomBackend>,
     reserve: Option<Option<i32>>,
     tags: Option<JsonValue>,
+    backend_group: Option<Option<String>>,
+    max_agents: Option<Option<i32>>,
+    max_publishers: Option<Option<i32>>,
+    lobby: Option<bool>,
 }
 pub(crate) struct UpdateHandler;
 
@@ -225,10 +380,23 @@ impl RequestHandler for UpdateHandler {
                 .audience(payload.audience)
                 .backend(payload.backend)
                 .reserve(payload.reserve)
-                .tags(payload.tags);
+                .tags(payload.tags)
+                .backend_group(payload.backend_group)
+                .max_agents(payload.max_agents)
+                .max_publishers(payload.max_publishers)
+                .lobby(payload.lobby);
 
             let conn = context.get_conn()?;
-            query.execute(&conn)?
+
+            conn.transaction::<_, AppError, _>(|| {
+                let room = query.execute(&conn)?;
+
+                if room_was_open && room_closed_by_update {
+                    webhook::enqueue(context, room.audience(), "room.close", &room, &conn)?;
+                }
+
+                Ok(room)
+            })?
         };
 
         // Respond and broadcast to the audience topic.
@@ -279,6 +447,211 @@ impl RequestHandler for UpdateHandler {
 
 ///////////////////////////////////////////////////////////////////////////////
 
+pub(crate) type CloseRequest = ReadRequest;
+pub(crate) struct CloseHandler;
+
+#[async_trait]
+impl RequestHandler for CloseHandler {
+    type Payload = CloseRequest;
+    const ERROR_TITLE: &'static str = "Failed to close room";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.id, helpers::RoomTimeRequirement::Open)?;
+
+        // Authorize room closing on the tenant the same way as updating.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "update")
+            .await?;
+
+        // Truncate room time to now, kick agents out and pick up the recordings to upload.
+        let (room, agent_ids, backend_requests, recordings) = {
+            let conn = context.get_conn()?;
+
+            conn.transaction::<_, AppError, _>(|| {
+                let time = (room.time().0.to_owned(), Bound::Excluded(Utc::now()));
+
+                let room = db::room::UpdateQuery::new(room.id())
+                    .time(Some(time))
+                    .execute(&conn)?;
+
+                // Agents that have entered the room or are about to.
+                let mut agents = db::agent::ListQuery::new()
+                    .room_id(room.id())
+                    .execute(&conn)?;
+
+                agents.extend(
+                    db::agent::ListQuery::new()
+                        .room_id(room.id())
+                        .status(db::agent::Status::InProgress)
+                        .execute(&conn)?,
+                );
+
+                let agent_ids = agents
+                    .into_iter()
+                    .map(|agent| agent.agent_id().to_owned())
+                    .collect::<Vec<AgentId>>();
+
+                // Stop the agents' streams and make backends drop them.
+                let mut backend_requests = vec![];
+
+                for agent_id in agent_ids.iter() {
+                    backend_requests.extend(helpers::leave_backends(
+                        context,
+                        room.id(),
+                        agent_id,
+                        reqp.tracking(),
+                        &conn,
+                    )?);
+                }
+
+                db::agent::DeleteQuery::new()
+                    .room_id(room.id())
+                    .execute(&conn)?;
+
+                let recordings = db::room::in_progress_recordings(room.id(), &conn)?;
+
+                for (_, recording, _) in recordings.iter() {
+                    system::record_upload_attempt(context, recording, &conn)?;
+                }
+
+                webhook::enqueue(context, room.audience(), "room.close", &room, &conn)?;
+                Ok((room, agent_ids, backend_requests, recordings))
+            })?
+        };
+
+        let mut responses = vec![helpers::build_response(
+            ResponseStatus::OK,
+            room.clone(),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        )];
+
+        // Upload in-progress recordings right away instead of waiting for `system.vacuum`.
+        for (_, recording, backend) in recordings {
+            let backreq = system::upload_stream_request(
+                context,
+                Some(reqp.tracking()),
+                &room,
+                &recording,
+                &backend,
+            )?;
+
+            responses.push(backreq);
+        }
+
+        // Unsubscribe the kicked agents from the room's events and notify the room.
+        // See the comment in `room.leave` handler on why these are unicast requests.
+        for agent_id in agent_ids {
+            let subscription_request =
+                SubscriptionRequest::new(agent_id.to_owned(), vec!["rooms", &room_id, "events"]);
+
+            let props = reqp.to_request(
+                "subscription.delete",
+                reqp.response_topic(),
+                &generate_correlation_data(),
+                ShortTermTimingProperties::until_now(context.start_timestamp()),
+            );
+
+            let outgoing_request = OutgoingRequest::unicast(
+                subscription_request,
+                props,
+                &agent_id,
+                MQTT_GW_API_VERSION,
+            );
+
+            responses.push(Box::new(outgoing_request) as Box<dyn IntoPublishableMessage + Send>);
+
+            responses.push(helpers::build_notification(
+                "room.leave",
+                &format!("rooms/{}/events", room.id()),
+                RoomEnterLeaveEvent::new(room.id(), agent_id),
+                reqp,
+                context.start_timestamp(),
+            ));
+        }
+
+        responses.extend(backend_requests);
+
+        responses.push(helpers::build_notification(
+            "room.close",
+            &format!("rooms/{}/events", room.id()),
+            room.clone(),
+            reqp,
+            context.start_timestamp(),
+        ));
+
+        responses.push(helpers::build_notification(
+            "room.close",
+            &format!("audiences/{}/events", room.audience()),
+            room,
+            reqp,
+            context.start_timestamp(),
+        ));
+
+        Ok(Box::new(stream::from_iter(responses)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+pub(crate) type UploadRequest = ReadRequest;
+pub(crate) struct UploadHandler;
+
+#[async_trait]
+impl RequestHandler for UploadHandler {
+    type Payload = UploadRequest;
+    const ERROR_TITLE: &'static str = "Failed to upload room";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let room =
+            helpers::find_room_by_id(context, payload.id, helpers::RoomTimeRequirement::Any)?;
+
+        // Authorize re-sending the upload event on the tenant the same way as updating.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "update")
+            .await?;
+
+        let event = {
+            let conn = context.get_conn()?;
+
+            system::room_upload_event(context, &room, Some(reqp.tracking()), &conn)?
+                .ok_or_else(|| anyhow!("Some of the room's recordings are still in progress"))
+                .error(AppErrorKind::UploadNotFinished)?
+        };
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            room,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        let event = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
+        Ok(Box::new(stream::from_iter(vec![response, event])))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
 pub(crate) type DeleteRequest = ReadRequest;
 pub(crate) struct DeleteHandler;
 
@@ -358,10 +731,54 @@ impl RequestHandler for EnterHandler {
             .authorize(room.audience(), reqp, object.clone(), "subscribe")
             .await?;
 
-        // Register agent in `in_progress` state.
-        {
+        // In a lobby room hosts are agents allowed to watch the lobby. They don't wait there.
+        let is_host = if room.lobby() {
+            let object = vec!["rooms", &room_id, "lobby"];
+
+            match context
+                .authz()
+                .authorize(room.audience(), reqp, object, "subscribe")
+                .await
+            {
+                Ok(_) => true,
+                Err(err) => match err.kind() {
+                    svc_authz::ErrorKind::Forbidden(_) => false,
+                    _ => return Err(AppError::from(err)),
+                },
+            }
+        } else {
+            false
+        };
+
+        // Register agent in `in_progress` state unless its account is banned in the room.
+        // Agents entering a lobby room for the first time get `waiting` state instead.
+        let status = {
             let conn = context.get_conn()?;
-            db::agent::InsertQuery::new(reqp.as_agent_id(), room.id()).execute(&conn)?;
+
+            conn.transaction::<_, AppError, _>(|| {
+                helpers::register_agent(room.id(), reqp.as_agent_id(), is_host, &conn)
+            })?
+        };
+
+        // Let hosts know that the agent is waiting in the lobby.
+        if status == db::agent::Status::Waiting {
+            let response = helpers::build_response(
+                ResponseStatus::ACCEPTED,
+                json!({}),
+                reqp,
+                context.start_timestamp(),
+                Some(authz_time),
+            );
+
+            let notification = helpers::build_notification(
+                "agent.wait",
+                &format!("rooms/{}/lobby", room.id()),
+                RoomEnterLeaveEvent::new(room.id(), reqp.as_agent_id().to_owned()),
+                reqp,
+                context.start_timestamp(),
+            );
+
+            return Ok(Box::new(stream::from_iter(vec![response, notification])));
         }
 
         // Send dynamic subscription creation request to the broker.
@@ -388,7 +805,29 @@ impl RequestHandler for EnterHandler {
         //        to send a multicast request to the broker.
         let outgoing_request = OutgoingRequest::unicast(payload, props, reqp, MQTT_GW_API_VERSION);
         let boxed_request = Box::new(outgoing_request) as Box<dyn IntoPublishableMessage + Send>;
-        Ok(Box::new(stream::once(boxed_request)))
+        let mut messages = vec![boxed_request];
+
+        // Subscribe hosts to the lobby notifications as well.
+        if is_host {
+            let payload = SubscriptionRequest::new(
+                reqp.as_agent_id().to_owned(),
+                vec!["rooms", &room_id, "lobby"],
+            );
+
+            let props = reqp.to_request(
+                "subscription.create",
+                reqp.response_topic(),
+                &generate_correlation_data(),
+                ShortTermTimingProperties::until_now(context.start_timestamp()),
+            );
+
+            let outgoing_request =
+                OutgoingRequest::unicast(payload, props, reqp, MQTT_GW_API_VERSION);
+
+            messages.push(Box::new(outgoing_request) as Box<dyn IntoPublishableMessage + Send>);
+        }
+
+        Ok(Box::new(stream::from_iter(messages)))
     }
 }
 
@@ -501,6 +940,11 @@ mod test {
                     backend: db::room::RoomBackend::Janus,
                     reserve: Some(123),
                     tags: Some(json!({ "foo": "bar" })),
+                    backend_group: Some(String::from("eu")),
+                    max_agents: Some(30),
+                    max_publishers: None,
+                    lobby: None,
+                    breakout_rooms: None,
                 };
 
                 let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
@@ -515,6 +959,8 @@ mod test {
                 assert_eq!(room.backend(), db::room::RoomBackend::Janus);
                 assert_eq!(room.reserve(), Some(123));
                 assert_eq!(room.tags(), &json!({ "foo": "bar" }));
+                assert_eq!(room.backend_group(), Some("eu"));
+                assert_eq!(room.max_agents(), Some(30));
 
                 // Assert notification.
                 let (room, evp, topic) = find_event::<Room>(messages.as_slice());
@@ -525,47 +971,112 @@ mod test {
                 assert_eq!(room.backend(), db::room::RoomBackend::Janus);
                 assert_eq!(room.reserve(), Some(123));
                 assert_eq!(room.tags(), &json!({ "foo": "bar" }));
+                assert_eq!(room.backend_group(), Some("eu"));
+                assert_eq!(room.max_agents(), Some(30));
             });
         }
 
         #[test]
-        fn create_room_unauthorized() {
+        fn create_with_breakout_rooms() {
             async_std::task::block_on(async {
-                let mut context = TestContext::new(TestDb::new(), TestAuthz::new());
+                // Allow user to create rooms.
+                let mut authz = TestAuthz::new();
                 let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["rooms"], "create");
 
                 // Make room.create request.
+                let mut context = TestContext::new(TestDb::new(), authz);
+                let now = Utc::now().trunc_subsecs(0);
+                let time = (Bound::Included(now), Bound::Unbounded);
+
                 let payload = CreateRequest {
-                    time: (Bound::Included(Utc::now()), Bound::Unbounded),
+                    time: time.clone(),
                     audience: USR_AUDIENCE.to_owned(),
                     backend: db::room::RoomBackend::Janus,
                     reserve: None,
-                    tags: None,
+                    tags: Some(json!({ "foo": "bar" })),
+                    backend_group: None,
+                    max_agents: Some(30),
+                    max_publishers: None,
+                    lobby: None,
+                    breakout_rooms: Some(2),
                 };
 
-                let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
                     .await
-                    .expect_err("Unexpected success on room creation");
+                    .expect("Room creation failed");
 
-                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
-                assert_eq!(err.kind(), "access_denied");
-            });
-        }
-    }
+                // Assert response.
+                let (room, respp) = find_response::<Room>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(room.parent_id(), None);
 
-    mod read {
-        use crate::db::room::Object as Room;
-        use crate::test_helpers::prelude::*;
+                // Assert breakout rooms inherit the parent's attributes.
+                let conn = context.db().get().expect("Failed to get DB connection");
 
-        use super::super::*;
+                let breakout_rooms = db::room::ListQuery::new()
+                    .parent_id(room.id())
+                    .execute(&conn)
+                    .expect("Failed to list rooms");
 
-        #[test]
-        fn read_room() {
-            async_std::task::block_on(async {
-                let db = TestDb::new();
+                assert_eq!(breakout_rooms.len(), 2);
 
-                let room = {
-                    let conn = db
+                for breakout_room in breakout_rooms {
+                    assert_eq!(breakout_room.audience(), USR_AUDIENCE);
+                    assert_eq!(breakout_room.time(), &time);
+                    assert_eq!(breakout_room.backend(), db::room::RoomBackend::Janus);
+                    assert_eq!(breakout_room.tags(), &json!({ "foo": "bar" }));
+                    assert_eq!(breakout_room.max_agents(), None);
+                }
+
+                // Assert notifications for all three rooms.
+                assert_eq!(messages.len(), 4);
+            });
+        }
+
+        #[test]
+        fn create_room_unauthorized() {
+            async_std::task::block_on(async {
+                let mut context = TestContext::new(TestDb::new(), TestAuthz::new());
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                // Make room.create request.
+                let payload = CreateRequest {
+                    time: (Bound::Included(Utc::now()), Bound::Unbounded),
+                    audience: USR_AUDIENCE.to_owned(),
+                    backend: db::room::RoomBackend::Janus,
+                    reserve: None,
+                    tags: None,
+                    backend_group: None,
+                    max_agents: None,
+                    max_publishers: None,
+                    lobby: None,
+                    breakout_rooms: None,
+                };
+
+                let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room creation");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
+
+    mod read {
+        use crate::db::room::Object as Room;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn read_room() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
                         .connection_pool()
                         .get()
                         .expect("Failed to get DB connection");
@@ -641,6 +1152,191 @@ mod test {
         }
     }
 
+    mod list {
+        use std::ops::Bound;
+
+        use chrono::{Duration, SubsecRound, Utc};
+        use serde_json::json;
+
+        use crate::db::room::Object as Room;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn list_rooms() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let now = Utc::now().trunc_subsecs(0);
+
+                let (room, _other_tags_room, _closed_room) = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .backend(db::room::RoomBackend::Janus)
+                        .tags(&json!({ "webinar_id": "123", "foo": "bar" }))
+                        .insert(&conn);
+
+                    let other_tags_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .backend(db::room::RoomBackend::Janus)
+                        .tags(&json!({ "webinar_id": "456" }))
+                        .insert(&conn);
+
+                    let closed_room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((
+                            Bound::Included(now - Duration::hours(10)),
+                            Bound::Excluded(now - Duration::hours(8)),
+                        ))
+                        .backend(db::room::RoomBackend::Janus)
+                        .tags(&json!({ "webinar_id": "123" }))
+                        .insert(&conn);
+
+                    (room, other_tags_room, closed_room)
+                };
+
+                // Allow agent to list rooms.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                authz.allow(agent.account_id(), vec!["rooms"], "list");
+
+                // Make room.list request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ListRequest {
+                    audience: USR_AUDIENCE.to_owned(),
+                    time: Some((Bound::Included(now), Bound::Unbounded)),
+                    backend: Some(db::room::RoomBackend::Janus),
+                    closed: Some(false),
+                    tags: Some(json!({ "webinar_id": "123" })),
+                    parent_id: None,
+                    offset: None,
+                    limit: None,
+                };
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Rooms listing failed");
+
+                // Assert response.
+                let (rooms, respp) = find_response::<Vec<Room>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(rooms.len(), 1);
+                assert_eq!(rooms[0].id(), room.id());
+                assert_eq!(rooms[0].tags(), room.tags());
+            });
+        }
+
+        #[test]
+        fn list_rooms_paginated() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    for _ in 0..3 {
+                        shared_helpers::insert_room(&conn);
+                    }
+                }
+
+                // Allow agent to list rooms.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                authz.allow(agent.account_id(), vec!["rooms"], "list");
+
+                // Make room.list request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ListRequest {
+                    audience: USR_AUDIENCE.to_owned(),
+                    time: None,
+                    backend: None,
+                    closed: None,
+                    tags: None,
+                    parent_id: None,
+                    offset: Some(1),
+                    limit: Some(1),
+                };
+
+                let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Rooms listing failed");
+
+                // Assert response.
+                let (rooms, respp) = find_response::<Vec<Room>>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(rooms.len(), 1);
+            });
+        }
+
+        #[test]
+        fn list_rooms_negative_pagination() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                authz.allow(agent.account_id(), vec!["rooms"], "list");
+                let mut context = TestContext::new(TestDb::new(), authz);
+
+                for &(offset, limit) in &[(Some(-1), None), (None, Some(-1))] {
+                    let payload = ListRequest {
+                        audience: USR_AUDIENCE.to_owned(),
+                        time: None,
+                        backend: None,
+                        closed: None,
+                        tags: None,
+                        parent_id: None,
+                        offset,
+                        limit,
+                    };
+
+                    let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                        .await
+                        .expect_err("Unexpected success on rooms listing");
+
+                    assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                    assert_eq!(err.kind(), "invalid_pagination");
+                }
+            });
+        }
+
+        #[test]
+        fn list_rooms_not_authorized() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(TestDb::new(), TestAuthz::new());
+
+                let payload = ListRequest {
+                    audience: USR_AUDIENCE.to_owned(),
+                    time: None,
+                    backend: None,
+                    closed: None,
+                    tags: None,
+                    parent_id: None,
+                    offset: None,
+                    limit: None,
+                };
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on rooms listing");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
+
     mod update {
         use std::ops::Bound;
 
@@ -697,6 +1393,10 @@ mod test {
                     tags: Some(json!({"foo": "bar"})),
                     audience: None,
                     backend: None,
+                    backend_group: Some(Some(String::from("eu"))),
+                    max_agents: Some(Some(30)),
+                    max_publishers: Some(Some(5)),
+                    lobby: Some(true),
                 };
 
                 let messages = handle_request::<UpdateHandler>(&mut context, &agent, payload)
@@ -712,6 +1412,10 @@ mod test {
                 assert_eq!(resp_room.backend(), db::room::RoomBackend::Janus);
                 assert_eq!(resp_room.reserve(), Some(123));
                 assert_eq!(resp_room.tags(), &json!({"foo": "bar"}));
+                assert_eq!(resp_room.backend_group(), Some("eu"));
+                assert_eq!(resp_room.max_agents(), Some(30));
+                assert_eq!(resp_room.max_publishers(), Some(5));
+                assert!(resp_room.lobby());
             });
         }
 
@@ -759,6 +1463,10 @@ mod test {
                     tags: Some(json!({"foo": "bar"})),
                     audience: None,
                     backend: None,
+                    backend_group: None,
+                    max_agents: None,
+                    max_publishers: None,
+                    lobby: None,
                 };
 
                 handle_request::<UpdateHandler>(&mut context, &agent, payload)
@@ -811,6 +1519,10 @@ mod test {
                     audience: None,
                     backend: None,
                     tags: None,
+                    backend_group: None,
+                    max_agents: None,
+                    max_publishers: None,
+                    lobby: None,
                 };
 
                 let messages = handle_request::<UpdateHandler>(&mut context, &agent, payload)
@@ -898,59 +1610,151 @@ mod test {
         }
     }
 
-    mod delete {
+    mod close {
         use diesel::prelude::*;
+        use serde_json::Value as JsonValue;
 
         use crate::db::room::Object as Room;
         use crate::test_helpers::prelude::*;
+        use crate::test_helpers::{find_event_by_predicate, find_request_by_predicate};
 
         use super::super::*;
 
         #[test]
-        fn delete_room() {
+        fn close_room() {
             async_std::task::block_on(async {
                 let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-                let room = {
+                let (room, rtc) = {
                     let conn = db
                         .connection_pool()
                         .get()
                         .expect("Failed to get DB connection");
 
-                    // Create room.
-                    shared_helpers::insert_room(&conn)
+                    // Create room with an agent and an in-progress recording.
+                    let room = shared_helpers::insert_room(&conn);
+                    let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+                    shared_helpers::insert_recording(&conn, &rtc, &backend);
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                    (room, rtc)
                 };
 
-                // Allow agent to read the room.
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                // Allow agent to update the room.
                 let mut authz = TestAuthz::new();
                 let room_id = room.id().to_string();
-                authz.allow(agent.account_id(), vec!["rooms", &room_id], "delete");
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
 
-                // Make room.delete request.
+                // Make room.close request.
                 let mut context = TestContext::new(db, authz);
-                let payload = DeleteRequest { id: room.id() };
+                let payload = CloseRequest { id: room.id() };
 
-                let messages = handle_request::<DeleteHandler>(&mut context, &agent, payload)
+                let messages = handle_request::<CloseHandler>(&mut context, &agent, payload)
                     .await
-                    .expect("Room deletion failed");
+                    .expect("Room closing failed");
 
                 // Assert response.
                 let (resp_room, respp) = find_response::<Room>(messages.as_slice());
                 assert_eq!(respp.status(), ResponseStatus::OK);
-                assert_eq!(resp_room.audience(), room.audience());
-                assert_eq!(resp_room.time(), room.time());
-                assert_eq!(resp_room.backend(), room.backend());
+                assert!(resp_room.is_closed());
+
+                // Assert stream upload request to the backend.
+                find_request_by_predicate::<JsonValue, _>(messages.as_slice(), |_, p| {
+                    p["body"]["method"] == "stream.upload"
+                        && p["body"]["id"] == rtc.id().to_string()
+                })
+                .expect("Failed to find stream.upload request");
+
+                // Assert notifications to both room and audience topics.
+                for &topic_part in &["rooms", "audiences"] {
+                    find_event_by_predicate::<JsonValue, _>(
+                        messages.as_slice(),
+                        |evp, _, topic| evp.label() == "room.close" && topic.contains(topic_part),
+                    )
+                    .expect("Failed to find room.close event");
+                }
 
-                // Assert room absence in the DB.
-                let conn = context.get_conn().unwrap();
-                let query = crate::schema::room::table.find(room.id());
-                assert_eq!(query.execute(&conn).unwrap(), 0);
+                // Assert the agent is unsubscribed from the room's events and has left the room.
+                let (payload, _, topic) =
+                    find_request_by_predicate::<JsonValue, _>(messages.as_slice(), |reqp, _| {
+                        reqp.method() == "subscription.delete"
+                    })
+                    .expect("Failed to find subscription.delete request");
+
+                assert!(topic.starts_with(&format!("agents/{}/", agent.agent_id())));
+                assert_eq!(payload["subject"], agent.agent_id().to_string());
+                assert_eq!(payload["object"], json!(["rooms", &room_id, "events"]));
+
+                find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |evp, p, _| {
+                    evp.label() == "room.leave" && p["agent_id"] == agent.agent_id().to_string()
+                })
+                .expect("Failed to find room.leave event");
+
+                // Assert agents removed.
+                let conn = context.get_conn().expect("Failed to get DB connection");
+
+                let agents_count = crate::schema::agent::table
+                    .filter(crate::schema::agent::room_id.eq(room.id()))
+                    .execute(&conn)
+                    .expect("Failed to count agents");
+
+                assert_eq!(agents_count, 0);
             });
         }
 
         #[test]
-        fn delete_room_not_authorized() {
+        fn close_room_enqueue_webhook() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| shared_helpers::insert_room(&conn))
+                    .unwrap();
+
+                // Allow agent to update the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+                // Configure a webhook for the room's audience.
+                let mut context = TestContext::new(db, authz);
+
+                let webhook_config = serde_json::json!({
+                    "audiences": {
+                        USR_AUDIENCE: { "url": "http://localhost/webhook", "secret": "secret" }
+                    }
+                });
+
+                context.config_mut().webhook =
+                    Some(serde_json::from_value(webhook_config).expect("Failed to parse config"));
+
+                // Make room.close request.
+                let payload = CloseRequest { id: room.id() };
+
+                handle_request::<CloseHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room closing failed");
+
+                // Assert room.close webhook in the outbox.
+                let conn = context.get_conn().expect("Failed to get DB connection");
+
+                let webhooks = crate::schema::webhook::table
+                    .get_results::<crate::db::webhook::Object>(&conn)
+                    .expect("Failed to load webhooks");
+
+                assert_eq!(webhooks.len(), 1);
+                assert_eq!(webhooks[0].audience(), USR_AUDIENCE);
+                assert_eq!(webhooks[0].label(), "room.close");
+                assert_eq!(webhooks[0].payload()["id"], room.id().to_string());
+            });
+        }
+
+        #[test]
+        fn close_room_closed() {
             async_std::task::block_on(async {
                 let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
                 let db = TestDb::new();
@@ -961,75 +1765,333 @@ mod test {
                         .get()
                         .expect("Failed to get DB connection");
 
-                    shared_helpers::insert_room(&conn)
+                    shared_helpers::insert_closed_room(&conn)
                 };
 
                 let mut context = TestContext::new(db, TestAuthz::new());
-                let payload = DeleteRequest { id: room.id() };
+                let payload = CloseRequest { id: room.id() };
 
-                let err = handle_request::<DeleteHandler>(&mut context, &agent, payload)
+                let err = handle_request::<CloseHandler>(&mut context, &agent, payload)
                     .await
-                    .expect_err("Unexpected success on room deletion");
+                    .expect_err("Unexpected success on room closing");
 
-                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
-                assert_eq!(err.kind(), "access_denied");
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "room_closed");
             });
         }
 
         #[test]
-        fn delete_room_missing() {
+        fn close_room_unauthorized() {
             async_std::task::block_on(async {
                 let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-                let mut context = TestContext::new(TestDb::new(), TestAuthz::new());
-                let payload = DeleteRequest { id: Uuid::new_v4() };
+                let db = TestDb::new();
 
-                let err = handle_request::<DeleteHandler>(&mut context, &agent, payload)
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    shared_helpers::insert_room(&conn)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let payload = CloseRequest { id: room.id() };
+
+                let err = handle_request::<CloseHandler>(&mut context, &agent, payload)
                     .await
-                    .expect_err("Unexpected success on room deletion");
+                    .expect_err("Unexpected success on room closing");
 
-                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-                assert_eq!(err.kind(), "room_not_found");
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
             });
         }
     }
 
-    mod enter {
+    mod upload {
+        use serde_json::Value as JsonValue;
+
+        use crate::app::API_VERSION;
+        use crate::db::recording::Status as RecordingStatus;
+        use crate::test_helpers::find_event_by_predicate;
         use crate::test_helpers::prelude::*;
 
         use super::super::*;
-        use super::DynSubRequest;
 
         #[test]
-        fn enter_room() {
+        fn upload_room() {
             async_std::task::block_on(async {
                 let db = TestDb::new();
+                let agent = TestAgent::new("alpha", "conference-postprocessing", SVC_AUDIENCE);
 
-                let room = {
+                let (room, rtc) = {
                     let conn = db
                         .connection_pool()
                         .get()
                         .expect("Failed to get DB connection");
 
-                    // Create room.
-                    shared_helpers::insert_room(&conn)
+                    // Create closed room with an uploaded recording.
+                    let room = shared_helpers::insert_closed_room(&conn);
+                    let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+                    shared_helpers::insert_recording(&conn, &rtc, &backend);
+                    let segments = vec![(Bound::Included(0), Bound::Excluded(1000))];
+
+                    db::recording::UpdateQuery::new(rtc.id())
+                        .status(RecordingStatus::Ready)
+                        .started_at(Utc::now())
+                        .segments(segments)
+                        .execute(&conn)
+                        .expect("Failed to update recording");
+
+                    (room, rtc)
                 };
 
-                // Allow agent to subscribe to the rooms' events.
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                // Allow agent to update the room.
                 let mut authz = TestAuthz::new();
                 let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
 
-                authz.allow(
-                    agent.account_id(),
-                    vec!["rooms", &room_id, "events"],
-                    "subscribe",
-                );
-
-                // Make room.enter request.
+                // Make room.upload request.
                 let mut context = TestContext::new(db, authz);
-                let payload = EnterRequest { id: room.id() };
+                let payload = UploadRequest { id: room.id() };
 
-                let messages = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                let messages = handle_request::<UploadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room upload failed");
+
+                // Assert response.
+                let (_, respp) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+
+                // Assert room.upload event re-emitted to the audience topic.
+                let (payload, _, topic) =
+                    find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |evp, _, _| {
+                        evp.label() == "room.upload"
+                    })
+                    .expect("Failed to find room.upload event");
+
+                let expected_topic = format!(
+                    "apps/conference.{}/api/{}/audiences/{}/events",
+                    SVC_AUDIENCE, API_VERSION, USR_AUDIENCE,
+                );
+
+                assert_eq!(topic, expected_topic);
+                assert_eq!(payload["id"], room.id().to_string());
+                assert_eq!(payload["rtcs"][0]["id"], rtc.id().to_string());
+                assert_eq!(payload["rtcs"][0]["status"], "ready");
+            });
+        }
+
+        #[test]
+        fn upload_room_not_finished() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("alpha", "conference-postprocessing", SVC_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create closed room with an in-progress recording.
+                    let room = shared_helpers::insert_closed_room(&conn);
+                    let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+                    shared_helpers::insert_recording(&conn, &rtc, &backend);
+                    room
+                };
+
+                // Allow agent to update the room.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+                // Make room.upload request.
+                let mut context = TestContext::new(db, authz);
+                let payload = UploadRequest { id: room.id() };
+
+                let err = handle_request::<UploadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room upload");
+
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "upload_not_finished");
+            });
+        }
+
+        #[test]
+        fn upload_room_unauthorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    shared_helpers::insert_closed_room(&conn)
+                };
+
+                // Make room.upload request.
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let payload = UploadRequest { id: room.id() };
+
+                let err = handle_request::<UploadHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room upload");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
+
+    mod delete {
+        use diesel::prelude::*;
+
+        use crate::db::room::Object as Room;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn delete_room() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room.
+                    shared_helpers::insert_room(&conn)
+                };
+
+                // Allow agent to read the room.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+                authz.allow(agent.account_id(), vec!["rooms", &room_id], "delete");
+
+                // Make room.delete request.
+                let mut context = TestContext::new(db, authz);
+                let payload = DeleteRequest { id: room.id() };
+
+                let messages = handle_request::<DeleteHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room deletion failed");
+
+                // Assert response.
+                let (resp_room, respp) = find_response::<Room>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(resp_room.audience(), room.audience());
+                assert_eq!(resp_room.time(), room.time());
+                assert_eq!(resp_room.backend(), room.backend());
+
+                // Assert room absence in the DB.
+                let conn = context.get_conn().unwrap();
+                let query = crate::schema::room::table.find(room.id());
+                assert_eq!(query.execute(&conn).unwrap(), 0);
+            });
+        }
+
+        #[test]
+        fn delete_room_not_authorized() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    shared_helpers::insert_room(&conn)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let payload = DeleteRequest { id: room.id() };
+
+                let err = handle_request::<DeleteHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room deletion");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+
+        #[test]
+        fn delete_room_missing() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(TestDb::new(), TestAuthz::new());
+                let payload = DeleteRequest { id: Uuid::new_v4() };
+
+                let err = handle_request::<DeleteHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room deletion");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "room_not_found");
+            });
+        }
+    }
+
+    mod enter {
+        use chrono::SubsecRound;
+
+        use crate::app::API_VERSION;
+        use crate::test_helpers::find_request_by_predicate;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+        use super::DynSubRequest;
+
+        #[derive(Deserialize)]
+        struct LobbyEvent {
+            id: Uuid,
+            agent_id: AgentId,
+        }
+
+        #[test]
+        fn enter_room() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room.
+                    shared_helpers::insert_room(&conn)
+                };
+
+                // Allow agent to subscribe to the rooms' events.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "events"],
+                    "subscribe",
+                );
+
+                // Make room.enter request.
+                let mut context = TestContext::new(db, authz);
+                let payload = EnterRequest { id: room.id() };
+
+                let messages = handle_request::<EnterHandler>(&mut context, &agent, payload)
                     .await
                     .expect("Room entrance failed");
 
@@ -1131,6 +2193,404 @@ mod test {
                 assert_eq!(err.kind(), "room_closed");
             });
         }
+
+        #[test]
+        fn enter_room_banned() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room and ban the agent's account in it.
+                    let room = shared_helpers::insert_room(&conn);
+
+                    db::room_ban::InsertQuery::new(room.id(), agent.account_id())
+                        .execute(&conn)
+                        .expect("Failed to ban account");
+
+                    room
+                };
+
+                // Allow agent to subscribe to the rooms' events.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "events"],
+                    "subscribe",
+                );
+
+                // Make room.enter request.
+                let mut context = TestContext::new(db, authz);
+                let payload = EnterRequest { id: room.id() };
+
+                let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room entering");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "agent_banned");
+            });
+        }
+
+        #[test]
+        fn enter_room_full() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room with a single slot taken by another agent.
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .max_agents(1)
+                        .insert(&conn);
+
+                    let other_agent = TestAgent::new("web", "user456", USR_AUDIENCE);
+                    shared_helpers::insert_agent(&conn, other_agent.agent_id(), room.id());
+                    room
+                };
+
+                // Allow agent to subscribe to the rooms' events.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "events"],
+                    "subscribe",
+                );
+
+                // Make room.enter request.
+                let mut context = TestContext::new(db, authz);
+                let payload = EnterRequest { id: room.id() };
+
+                let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room entering");
+
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "room_full");
+            });
+        }
+
+        #[test]
+        fn enter_room_full_of_entering_agents() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room with both slots taken by agents waiting for the broker
+                    // to subscribe them.
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .max_agents(2)
+                        .insert(&conn);
+
+                    for account in &["user456", "user789"] {
+                        let other_agent = TestAgent::new("web", account, USR_AUDIENCE);
+
+                        factory::Agent::new()
+                            .agent_id(other_agent.agent_id())
+                            .room_id(room.id())
+                            .status(db::agent::Status::InProgress)
+                            .insert(&conn);
+                    }
+
+                    room
+                };
+
+                // Allow agent to subscribe to the rooms' events.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "events"],
+                    "subscribe",
+                );
+
+                // Make room.enter request.
+                let mut context = TestContext::new(db, authz);
+                let payload = EnterRequest { id: room.id() };
+
+                let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on room entering");
+
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "room_full");
+            });
+        }
+
+        #[test]
+        fn reenter_full_room() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room with a single slot taken by the agent itself.
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .max_agents(1)
+                        .insert(&conn);
+
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                    room
+                };
+
+                // Allow agent to subscribe to the rooms' events.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "events"],
+                    "subscribe",
+                );
+
+                // Make room.enter request.
+                let mut context = TestContext::new(db, authz);
+                let payload = EnterRequest { id: room.id() };
+
+                handle_request::<EnterHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room entrance failed");
+            });
+        }
+
+        #[test]
+        fn enter_room_with_slots_taken_by_not_entered_agents() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room with a single slot.
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .max_agents(1)
+                        .insert(&conn);
+
+                    // A stale row of the agent itself left from a previous entrance.
+                    factory::Agent::new()
+                        .agent_id(agent.agent_id())
+                        .room_id(room.id())
+                        .status(db::agent::Status::InProgress)
+                        .insert(&conn);
+
+                    // Another agent waiting in the lobby.
+                    let other_agent = TestAgent::new("web", "user456", USR_AUDIENCE);
+
+                    factory::Agent::new()
+                        .agent_id(other_agent.agent_id())
+                        .room_id(room.id())
+                        .status(db::agent::Status::Waiting)
+                        .insert(&conn);
+
+                    room
+                };
+
+                // Allow agent to subscribe to the rooms' events.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "events"],
+                    "subscribe",
+                );
+
+                // Make room.enter request.
+                let mut context = TestContext::new(db, authz);
+                let payload = EnterRequest { id: room.id() };
+
+                handle_request::<EnterHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room entrance failed");
+            });
+        }
+
+        #[test]
+        fn enter_lobby_room() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room with a lobby.
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .lobby(true)
+                        .insert(&conn)
+                };
+
+                // Allow agent to subscribe to the rooms' events but not to the lobby.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "events"],
+                    "subscribe",
+                );
+
+                // Make room.enter request.
+                let mut context = TestContext::new(db, authz);
+                let payload = EnterRequest { id: room.id() };
+
+                let messages = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room entrance failed");
+
+                // Assert response.
+                let (_, respp) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::ACCEPTED);
+
+                // Assert no dynamic subscription request.
+                let maybe_request = find_request_by_predicate::<DynSubRequest, _>(
+                    messages.as_slice(),
+                    |reqp, _| reqp.method() == "subscription.create",
+                );
+
+                assert!(maybe_request.is_none());
+
+                // Assert lobby notification.
+                let (payload, evp, topic) = find_event::<LobbyEvent>(messages.as_slice());
+                assert_eq!(evp.label(), "agent.wait");
+
+                let expected_topic = format!(
+                    "apps/conference.{}/api/{}/rooms/{}/lobby",
+                    SVC_AUDIENCE,
+                    API_VERSION,
+                    room.id(),
+                );
+
+                assert_eq!(topic, expected_topic);
+                assert_eq!(payload.id, room.id());
+                assert_eq!(&payload.agent_id, agent.agent_id());
+
+                // Assert the agent is waiting.
+                let conn = context.db().get().expect("Failed to get DB connection");
+
+                let agents = db::agent::ListQuery::new()
+                    .agent_id(agent.agent_id())
+                    .room_id(room.id())
+                    .status(db::agent::Status::Waiting)
+                    .execute(&conn)
+                    .expect("Failed to list agents");
+
+                assert_eq!(agents.len(), 1);
+            });
+        }
+
+        #[test]
+        fn enter_lobby_room_as_host() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "host", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let room = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Create room with a lobby.
+                    let now = Utc::now().trunc_subsecs(0);
+
+                    factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(now), Bound::Unbounded))
+                        .lobby(true)
+                        .insert(&conn)
+                };
+
+                // Allow agent to subscribe to both the rooms' events and the lobby.
+                let mut authz = TestAuthz::new();
+                let room_id = room.id().to_string();
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "events"],
+                    "subscribe",
+                );
+
+                authz.allow(
+                    agent.account_id(),
+                    vec!["rooms", &room_id, "lobby"],
+                    "subscribe",
+                );
+
+                // Make room.enter request.
+                let mut context = TestContext::new(db, authz);
+                let payload = EnterRequest { id: room.id() };
+
+                let messages = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room entrance failed");
+
+                // Assert dynamic subscription requests to both topics.
+                for topic in &["events", "lobby"] {
+                    find_request_by_predicate::<DynSubRequest, _>(
+                        messages.as_slice(),
+                        |reqp, payload| {
+                            reqp.method() == "subscription.create"
+                                && payload.object == vec!["rooms", &room_id, *topic]
+                        },
+                    )
+                    .expect("Subscription request not found");
+                }
+            });
+        }
     }
 
     mod leave {
@@ -13,6 +13,7 @@ use crate::app::context::Context;
 use crate::app::endpoint::prelude::*;
 use crate::app::handle_id::HandleId;
 use crate::db;
+use crate::db::room_role::Role;
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -266,15 +267,14 @@ impl RequestHandler for ConnectHandler {
         let room_id = room.id().to_string();
         let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
 
-        let action = match payload.intent {
-            ConnectIntent::Read => "read",
-            ConnectIntent::Write => "update",
+        // Any room role allows reading while only hosts and speakers are allowed to publish.
+        let (action, roles) = match payload.intent {
+            ConnectIntent::Read => ("read", &[Role::Host, Role::Speaker, Role::Listener][..]),
+            ConnectIntent::Write => ("update", &[Role::Host, Role::Speaker][..]),
         };
 
-        let authz_time = context
-            .authz()
-            .authorize(room.audience(), reqp, object, action)
-            .await?;
+        let authz_time =
+            helpers::authorize_by_role(context, &room, reqp, object, action, roles).await?;
 
         // Choose backend to connect.
         let backend = {
@@ -282,10 +282,9 @@ impl RequestHandler for ConnectHandler {
 
             // There are 3 cases:
             // 1. Connecting as writer for the first time. There's no recording in that case.
-            //    Select the most loaded backend that is capable to host the room's reservation.
-            //    If there's no capable backend then select the least loaded and send a warning
-            //    to Sentry. If there are no backends at all then return `no available backends`
-            //    error and also send it to Sentry.
+            //    Select the backend with the configured balancer strategy. If there are
+            //    no backends at all then return `no available backends` error and also
+            //    send it to Sentry.
             // 2. Connecting as reader with existing recording. Choose the backend of the active
             //    recording because Janus doesn't support clustering and it must be the same server
             //    that the writer is connected to.
@@ -299,37 +298,11 @@ impl RequestHandler for ConnectHandler {
                     .execute(&conn)?
                     .ok_or_else(|| anyhow!("No backend found for stream"))
                     .error(AppErrorKind::BackendNotFound)?,
-                None => match db::janus_backend::most_loaded(room.id(), &conn)? {
-                    Some(backend) => backend,
-                    None => db::janus_backend::least_loaded(room.id(), &conn)?
-                        .map(|backend| {
-                            use sentry::protocol::{value::Value, Event, Level};
-                            let backend_id = backend.id().to_string();
-
-                            warn!(crate::LOG, "No capable backends to host the reserve; falling back to the least loaded backend: room_id = {}, rtc_id = {}, backend_id = {}", room_id, rtc_id, backend_id);
-
-                            let mut extra = std::collections::BTreeMap::new();
-                            extra.insert(String::from("room_id"), Value::from(room_id));
-                            extra.insert(String::from("rtc_id"), Value::from(rtc_id));
-                            extra.insert(String::from("backend_id"), Value::from(backend_id));
-
-                            if let Some(reserve) = room.reserve() {
-                                extra.insert(String::from("reserve"), Value::from(reserve));
-                            }
-
-
-                            sentry::capture_event(Event {
-                                message: Some(String::from("No capable backends to host the reserve; falling back to the least loaded backend")),
-                                level: Level::Warning,
-                                extra,
-                                ..Default::default()
-                            });
-
-                            backend
-                        })
-                        .ok_or_else(|| anyhow!("No available backends"))
-                        .error(AppErrorKind::NoAvailableBackends)?,
-                },
+                None => context
+                    .janus_balancer()
+                    .select(&room, payload.id, &conn)?
+                    .ok_or_else(|| anyhow!("No available backends"))
+                    .error(AppErrorKind::NoAvailableBackends)?,
             };
 
             // Create recording if a writer connects for the first time.
@@ -895,6 +868,140 @@ mod test {
             });
         }
 
+        #[test]
+        fn connect_to_rtc_skipping_draining_backend() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+
+                // Insert an rtc and janus backends.
+                let (rtc, backend) = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        let backend1 = shared_helpers::insert_janus_backend(&conn);
+                        let backend2 = shared_helpers::insert_janus_backend(&conn);
+
+                        // The first backend is the most loaded one but it's draining.
+                        let rtc1 = shared_helpers::insert_rtc(&conn);
+
+                        factory::Recording::new()
+                            .rtc(&rtc1)
+                            .backend(&backend1)
+                            .insert(&conn);
+
+                        let s1a1 = TestAgent::new("web", "s1a1", USR_AUDIENCE);
+                        shared_helpers::insert_agent(&conn, s1a1.agent_id(), rtc1.room_id());
+
+                        crate::db::janus_backend::UpdateQuery::new(backend1.id())
+                            .draining(true)
+                            .execute(&conn)
+                            .unwrap();
+
+                        // The new rtc for which we will balance the stream.
+                        let rtc2 = shared_helpers::insert_rtc(&conn);
+                        (rtc2, backend2)
+                    })
+                    .unwrap();
+
+                // Allow user to read the rtc.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let room_id = rtc.room_id().to_string();
+                let rtc_id = rtc.id().to_string();
+                let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+                authz.allow(agent.account_id(), object, "read");
+
+                // Make rtc.connect request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ConnectRequest {
+                    id: rtc.id(),
+                    intent: ConnectIntent::Read,
+                };
+
+                let messages = handle_request::<ConnectHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("RTC connect failed");
+
+                // Ensure we're balanced to the backend which is not draining.
+                let (req, _reqp, topic) = find_request::<JanusAttachRequest>(messages.as_slice());
+
+                let expected_topic = format!(
+                    "agents/{}/api/{}/in/{}",
+                    backend.id(),
+                    janus::JANUS_API_VERSION,
+                    context.config().id,
+                );
+
+                assert_eq!(topic, expected_topic);
+                assert_eq!(req.session_id, backend.session_id());
+            });
+        }
+
+        #[test]
+        fn connect_to_rtc_with_existing_recording_on_draining_backend() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+
+                // Insert an rtc and janus backends.
+                let (rtc, backend) = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        let rtc = shared_helpers::insert_rtc(&conn);
+                        let _backend1 = shared_helpers::insert_janus_backend(&conn);
+                        let backend2 = shared_helpers::insert_janus_backend(&conn);
+
+                        // The second backend hosts the recording and it's draining.
+                        factory::Recording::new()
+                            .rtc(&rtc)
+                            .backend(&backend2)
+                            .insert(&conn);
+
+                        crate::db::janus_backend::UpdateQuery::new(backend2.id())
+                            .draining(true)
+                            .execute(&conn)
+                            .unwrap();
+
+                        (rtc, backend2)
+                    })
+                    .unwrap();
+
+                // Allow user to read the rtc.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let room_id = rtc.room_id().to_string();
+                let rtc_id = rtc.id().to_string();
+                let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+                authz.allow(agent.account_id(), object, "read");
+
+                // Make rtc.connect request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = ConnectRequest {
+                    id: rtc.id(),
+                    intent: ConnectIntent::Read,
+                };
+
+                let messages = handle_request::<ConnectHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("RTC connect failed");
+
+                // Ensure we're still connected to the recording's backend.
+                let (req, _reqp, topic) = find_request::<JanusAttachRequest>(messages.as_slice());
+
+                let expected_topic = format!(
+                    "agents/{}/api/{}/in/{}",
+                    backend.id(),
+                    janus::JANUS_API_VERSION,
+                    context.config().id,
+                );
+
+                assert_eq!(topic, expected_topic);
+                assert_eq!(req.session_id, backend.session_id());
+            });
+        }
+
         #[test]
         fn connect_to_rtc_with_reservation() {
             async_std::task::block_on(async {
@@ -1685,6 +1792,95 @@ mod test {
             });
         }
 
+        #[test]
+        fn connect_to_rtc_as_speaker() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let (rtc, backend) = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Insert an rtc and a janus backend and make the agent a speaker in the room.
+                    let rtc = shared_helpers::insert_rtc(&conn);
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+
+                    db::room_role::UpsertQuery::new(
+                        rtc.room_id(),
+                        agent.account_id(),
+                        Role::Speaker,
+                    )
+                    .execute(&conn)
+                    .expect("Failed to grant role");
+
+                    (rtc, backend)
+                };
+
+                // Make rtc.connect request with no authz rules.
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = ConnectRequest {
+                    id: rtc.id(),
+                    intent: ConnectIntent::Write,
+                };
+
+                let messages = handle_request::<ConnectHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("RTC connect failed");
+
+                // Assert outgoing request to Janus.
+                let (req, _reqp, _topic) = find_request::<JanusAttachRequest>(messages.as_slice());
+                assert_eq!(req.session_id, backend.session_id());
+            });
+        }
+
+        #[test]
+        fn connect_to_rtc_as_listener_for_write() {
+            async_std::task::block_on(async {
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let db = TestDb::new();
+
+                let rtc = {
+                    let conn = db
+                        .connection_pool()
+                        .get()
+                        .expect("Failed to get DB connection");
+
+                    // Insert an rtc and make the agent a listener in the room.
+                    let rtc = shared_helpers::insert_rtc(&conn);
+                    shared_helpers::insert_janus_backend(&conn);
+
+                    db::room_role::UpsertQuery::new(
+                        rtc.room_id(),
+                        agent.account_id(),
+                        Role::Listener,
+                    )
+                    .execute(&conn)
+                    .expect("Failed to grant role");
+
+                    rtc
+                };
+
+                // The listener role doesn't allow publishing and neither does the authz service.
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let payload = ConnectRequest {
+                    id: rtc.id(),
+                    intent: ConnectIntent::Write,
+                };
+
+                let err = handle_request::<ConnectHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on rtc connecting");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+
         #[test]
         fn connect_to_rtc_missing() {
             async_std::task::block_on(async {
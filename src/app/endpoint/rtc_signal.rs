@@ -4,6 +4,7 @@ use anyhow::Context as AnyhowContext;
 use async_std::stream;
 use async_trait::async_trait;
 use chrono::Duration;
+use diesel::Connection;
 use serde_derive::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use svc_agent::mqtt::{IncomingRequestProperties, IntoPublishableMessage, OutgoingResponse};
@@ -11,7 +12,7 @@ use svc_agent::Addressable;
 
 use crate::app::context::Context;
 use crate::app::endpoint::prelude::*;
-use crate::app::handle_id::HandleId;
+use crate::app::handle_id::{HandleId, UnverifiedHandleId};
 use crate::db;
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -34,7 +35,7 @@ pub(crate) type CreateResponse = OutgoingResponse<CreateResponseData>;
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct CreateRequest {
-    handle_id: HandleId,
+    handle_id: UnverifiedHandleId,
     jsep: JsonValue,
     label: Option<String>,
 }
@@ -51,12 +52,14 @@ impl RequestHandler for CreateHandler {
         payload: Self::Payload,
         reqp: &IncomingRequestProperties,
     ) -> Result {
+        let handle_id = payload.handle_id.verify(&context.config().handle_id)?;
+
         context.add_logger_tags(o!(
-            "rtc_id" => payload.handle_id.rtc_id().to_string(),
-            "rtc_stream_id" => payload.handle_id.rtc_stream_id().to_string(),
-            "janus_session_id" => payload.handle_id.janus_session_id(),
-            "janus_handle_id" => payload.handle_id.janus_handle_id(),
-            "backend_id" => payload.handle_id.backend_id().to_string(),
+            "rtc_id" => handle_id.rtc_id().to_string(),
+            "rtc_stream_id" => handle_id.rtc_stream_id().to_string(),
+            "janus_session_id" => handle_id.janus_session_id(),
+            "janus_handle_id" => handle_id.janus_handle_id(),
+            "backend_id" => handle_id.backend_id().to_string(),
         ));
 
         if let Some(ref label) = payload.label {
@@ -81,17 +84,17 @@ impl RequestHandler for CreateHandler {
                     context.add_logger_tags(o!("sdp_type" => "offer", "intent" => "read"));
 
                     // Authorization
-                    let authz_time = authorize(context, &payload, reqp, "read").await?;
+                    let (_room, authz_time) = authorize(context, &handle_id, reqp, "read").await?;
 
                     context
                         .janus_client()
                         .read_stream_request(
                             reqp.clone(),
-                            payload.handle_id.janus_session_id(),
-                            payload.handle_id.janus_handle_id(),
-                            payload.handle_id.rtc_id(),
+                            handle_id.janus_session_id(),
+                            handle_id.janus_handle_id(),
+                            handle_id.rtc_id(),
                             payload.jsep.clone(),
-                            payload.handle_id.backend_id(),
+                            handle_id.backend_id(),
                             context.start_timestamp(),
                             authz_time,
                         )
@@ -102,7 +105,7 @@ impl RequestHandler for CreateHandler {
                     context.add_logger_tags(o!("sdp_type" => "offer", "intent" => "update"));
 
                     // Authorization
-                    let authz_time = authorize(context, &payload, reqp, "update").await?;
+                    let (room, authz_time) = authorize(context, &handle_id, reqp, "update").await?;
 
                     // Updating the Real-Time Connection state
                     {
@@ -114,26 +117,49 @@ impl RequestHandler for CreateHandler {
 
                         let conn = context.get_conn()?;
 
-                        db::janus_rtc_stream::InsertQuery::new(
-                            payload.handle_id.rtc_stream_id(),
-                            payload.handle_id.janus_handle_id(),
-                            payload.handle_id.rtc_id(),
-                            payload.handle_id.backend_id(),
-                            label,
-                            reqp.as_agent_id(),
-                        )
-                        .execute(&conn)?;
+                        conn.transaction::<_, AppError, _>(|| {
+                            // Lock the room so concurrent offers can't exceed the limit.
+                            let room = db::room::lock(room.id(), &conn)?
+                                .ok_or_else(|| anyhow!("Room not found"))
+                                .error(AppErrorKind::RoomNotFound)?;
+
+                            // Limit the number of agents publishing in the room at the same time.
+                            if let Some(max_publishers) = room.max_publishers() {
+                                let publishers_count = db::janus_rtc_stream::count_publishers(
+                                    room.id(),
+                                    reqp.as_agent_id(),
+                                    &conn,
+                                )?;
+
+                                if publishers_count >= i64::from(max_publishers) {
+                                    let err = anyhow!("The room has reached its publishers limit");
+                                    return Err(err).error(AppErrorKind::PublisherLimitReached);
+                                }
+                            }
+
+                            db::janus_rtc_stream::InsertQuery::new(
+                                handle_id.rtc_stream_id(),
+                                handle_id.janus_handle_id(),
+                                handle_id.rtc_id(),
+                                handle_id.backend_id(),
+                                label,
+                                reqp.as_agent_id(),
+                            )
+                            .execute(&conn)?;
+
+                            Ok(())
+                        })?;
                     }
 
                     context
                         .janus_client()
                         .create_stream_request(
                             reqp.clone(),
-                            payload.handle_id.janus_session_id(),
-                            payload.handle_id.janus_handle_id(),
-                            payload.handle_id.rtc_id(),
+                            handle_id.janus_session_id(),
+                            handle_id.janus_handle_id(),
+                            handle_id.rtc_id(),
                             payload.jsep.clone(),
-                            payload.handle_id.backend_id(),
+                            handle_id.backend_id(),
                             context.start_timestamp(),
                             authz_time,
                         )
@@ -148,16 +174,16 @@ impl RequestHandler for CreateHandler {
                 context.add_logger_tags(o!("sdp_type" => "ice_candidate", "intent" => "read"));
 
                 // Authorization
-                let authz_time = authorize(context, &payload, reqp, "read").await?;
+                let (_room, authz_time) = authorize(context, &handle_id, reqp, "read").await?;
 
                 context
                     .janus_client()
                     .trickle_request(
                         reqp.clone(),
-                        payload.handle_id.janus_session_id(),
-                        payload.handle_id.janus_handle_id(),
+                        handle_id.janus_session_id(),
+                        handle_id.janus_handle_id(),
                         payload.jsep.clone(),
-                        payload.handle_id.backend_id(),
+                        handle_id.backend_id(),
                         context.start_timestamp(),
                         authz_time,
                     )
@@ -173,11 +199,11 @@ impl RequestHandler for CreateHandler {
 
 async fn authorize<C: Context>(
     context: &mut C,
-    payload: &CreateRequest,
+    handle_id: &HandleId,
     reqp: &IncomingRequestProperties,
     action: &str,
-) -> StdResult<Duration, AppError> {
-    let rtc_id = payload.handle_id.rtc_id();
+) -> StdResult<(db::room::Object, Duration), AppError> {
+    let rtc_id = handle_id.rtc_id();
     let room = helpers::find_room_by_rtc_id(context, rtc_id, helpers::RoomTimeRequirement::Open)?;
 
     if room.backend() != db::room::RoomBackend::Janus {
@@ -193,11 +219,12 @@ async fn authorize<C: Context>(
     let rtc_id = rtc_id.to_string();
     let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
 
-    context
+    let authz_time = context
         .authz()
         .authorize(room.audience(), reqp, object, action)
-        .await
-        .map_err(AppError::from)
+        .await?;
+
+    Ok((room, authz_time))
 }
 
 #[derive(Debug)]
@@ -263,6 +290,9 @@ fn is_sdp_recvonly(jsep: &JsonValue) -> anyhow::Result<bool> {
 #[cfg(test)]
 mod test {
     mod create {
+        use std::ops::Bound;
+
+        use chrono::{SubsecRound, Utc};
         use diesel::prelude::*;
         use serde::Deserialize;
         use serde_json::json;
@@ -379,10 +409,12 @@ mod test {
                     backend.handle_id(),
                     backend.session_id(),
                     backend.id().to_owned(),
-                );
+                    &context.config().handle_id,
+                )
+                .expect("Failed to build handle id");
 
                 let payload = CreateRequest {
-                    handle_id,
+                    handle_id: handle_id.into(),
                     jsep: json!({ "type": "offer", "sdp": SDP_OFFER }),
                     label: Some(String::from("whatever")),
                 };
@@ -426,6 +458,146 @@ mod test {
             });
         }
 
+        #[test]
+        fn create_rtc_signal_for_offer_publisher_limit_reached() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+
+                // Insert a room limited to a single publisher who is already publishing.
+                let (backend, rtc) = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        let now = Utc::now().trunc_subsecs(0);
+
+                        let room = factory::Room::new()
+                            .audience(USR_AUDIENCE)
+                            .time((Bound::Included(now), Bound::Unbounded))
+                            .backend(db::room::RoomBackend::Janus)
+                            .max_publishers(1)
+                            .insert(&conn);
+
+                        let backend = shared_helpers::insert_janus_backend(&conn);
+                        let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                        let publisher = TestAgent::new("web", "user456", USR_AUDIENCE);
+
+                        shared_helpers::insert_agent(&conn, publisher.agent_id(), room.id());
+
+                        let stream = factory::JanusRtcStream::new(USR_AUDIENCE)
+                            .backend(&backend)
+                            .rtc(&rtc)
+                            .sent_by(publisher.agent_id())
+                            .insert(&conn);
+
+                        db::janus_rtc_stream::start(stream.id(), &conn).unwrap();
+                        (backend, rtc)
+                    })
+                    .unwrap();
+
+                // Allow user to update the rtc.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let room_id = rtc.room_id().to_string();
+                let rtc_id = rtc.id().to_string();
+                let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                // Make rtc_signal.create request.
+                let mut context = TestContext::new(db, authz);
+
+                let handle_id = HandleId::new(
+                    Uuid::new_v4(),
+                    rtc.id(),
+                    backend.handle_id(),
+                    backend.session_id(),
+                    backend.id().to_owned(),
+                    &context.config().handle_id,
+                )
+                .expect("Failed to build handle id");
+
+                let payload = CreateRequest {
+                    handle_id: handle_id.into(),
+                    jsep: json!({ "type": "offer", "sdp": SDP_OFFER }),
+                    label: Some(String::from("whatever")),
+                };
+
+                let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on rtc signal creation");
+
+                assert_eq!(err.status(), ResponseStatus::UNPROCESSABLE_ENTITY);
+                assert_eq!(err.kind(), "publisher_limit_reached");
+            });
+        }
+
+        #[test]
+        fn create_rtc_signal_for_offer_after_departed_publisher() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+
+                // Insert a room limited to a single publisher with a stream offered
+                // by an agent who has left the room before the stream has started.
+                let (backend, rtc) = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        let now = Utc::now().trunc_subsecs(0);
+
+                        let room = factory::Room::new()
+                            .audience(USR_AUDIENCE)
+                            .time((Bound::Included(now), Bound::Unbounded))
+                            .backend(db::room::RoomBackend::Janus)
+                            .max_publishers(1)
+                            .insert(&conn);
+
+                        let backend = shared_helpers::insert_janus_backend(&conn);
+                        let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                        let publisher = TestAgent::new("web", "user456", USR_AUDIENCE);
+
+                        factory::JanusRtcStream::new(USR_AUDIENCE)
+                            .backend(&backend)
+                            .rtc(&rtc)
+                            .sent_by(publisher.agent_id())
+                            .insert(&conn);
+
+                        (backend, rtc)
+                    })
+                    .unwrap();
+
+                // Allow user to update the rtc.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let room_id = rtc.room_id().to_string();
+                let rtc_id = rtc.id().to_string();
+                let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+                authz.allow(agent.account_id(), object, "update");
+
+                // Make rtc_signal.create request.
+                let mut context = TestContext::new(db, authz);
+
+                let handle_id = HandleId::new(
+                    Uuid::new_v4(),
+                    rtc.id(),
+                    backend.handle_id(),
+                    backend.session_id(),
+                    backend.id().to_owned(),
+                    &context.config().handle_id,
+                )
+                .expect("Failed to build handle id");
+
+                let payload = CreateRequest {
+                    handle_id: handle_id.into(),
+                    jsep: json!({ "type": "offer", "sdp": SDP_OFFER }),
+                    label: Some(String::from("whatever")),
+                };
+
+                // The stale stream doesn't take the publisher slot.
+                handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Rtc signal creation failed");
+            });
+        }
+
         #[test]
         fn create_rtc_signal_for_offer_unauthorized() {
             async_std::task::block_on(async {
@@ -454,10 +626,12 @@ mod test {
                     backend.handle_id(),
                     backend.session_id(),
                     backend.id().to_owned(),
-                );
+                    &context.config().handle_id,
+                )
+                .expect("Failed to build handle id");
 
                 let payload = CreateRequest {
-                    handle_id,
+                    handle_id: handle_id.into(),
                     jsep: json!({ "type": "offer", "sdp": SDP_OFFER }),
                     label: Some(String::from("whatever")),
                 };
@@ -528,10 +702,12 @@ mod test {
                     backend.handle_id(),
                     backend.session_id(),
                     backend.id().to_owned(),
-                );
+                    &context.config().handle_id,
+                )
+                .expect("Failed to build handle id");
 
                 let payload = CreateRequest {
-                    handle_id,
+                    handle_id: handle_id.into(),
                     jsep: json!({ "type": "answer", "sdp": SDP_ANSWER }),
                     label: Some(String::from("whatever")),
                 };
@@ -600,10 +776,12 @@ mod test {
                     backend.handle_id(),
                     backend.session_id(),
                     backend.id().to_owned(),
-                );
+                    &context.config().handle_id,
+                )
+                .expect("Failed to build handle id");
 
                 let payload = CreateRequest {
-                    handle_id,
+                    handle_id: handle_id.into(),
                     jsep: json!({ "sdpMid": 0, "sdpMLineIndex": 0, "candidate": ICE_CANDIDATE }),
                     label: None,
                 };
@@ -661,10 +839,12 @@ mod test {
                     backend.handle_id(),
                     backend.session_id(),
                     backend.id().to_owned(),
-                );
+                    &context.config().handle_id,
+                )
+                .expect("Failed to build handle id");
 
                 let payload = CreateRequest {
-                    handle_id,
+                    handle_id: handle_id.into(),
                     jsep: json!({ "sdpMid": 0, "sdpMLineIndex": 0, "candidate": ICE_CANDIDATE }),
                     label: None,
                 };
@@ -677,5 +857,53 @@ mod test {
                 assert_eq!(err.kind(), "access_denied");
             });
         }
+
+        #[test]
+        fn create_rtc_signal_with_forged_handle_id() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let (backend, rtc) = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        (
+                            shared_helpers::insert_janus_backend(&conn),
+                            shared_helpers::insert_rtc(&conn),
+                        )
+                    })
+                    .unwrap();
+
+                // Sign the handle id with a key other than the service's one.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let mut handle_id_config = context.config().handle_id.to_owned();
+                handle_id_config.key = String::from("forged-key");
+
+                let handle_id = HandleId::new(
+                    Uuid::new_v4(),
+                    rtc.id(),
+                    backend.handle_id(),
+                    backend.session_id(),
+                    backend.id().to_owned(),
+                    &handle_id_config,
+                )
+                .expect("Failed to build handle id");
+
+                let payload = CreateRequest {
+                    handle_id: handle_id.into(),
+                    jsep: json!({ "type": "offer", "sdp": SDP_OFFER }),
+                    label: Some(String::from("whatever")),
+                };
+
+                // Make rtc_signal.create request.
+                let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on rtc signal creation");
+
+                assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                assert_eq!(err.kind(), "invalid_handle_id");
+            });
+        }
     }
 }
@@ -24,6 +24,11 @@ pub(crate) struct SubscriptionEvent {
 }
 
 impl SubscriptionEvent {
+    fn is_lobby(&self) -> bool {
+        let object: Vec<&str> = self.object.iter().map(AsRef::as_ref).collect();
+        matches!(object.as_slice(), ["rooms", _, "lobby"])
+    }
+
     fn try_room_id(&self) -> StdResult<Uuid, AppError> {
         let object: Vec<&str> = self.object.iter().map(AsRef::as_ref).collect();
 
@@ -45,6 +50,12 @@ pub(crate) struct RoomEnterLeaveEvent {
     agent_id: AgentId,
 }
 
+impl RoomEnterLeaveEvent {
+    pub(crate) fn new(id: Uuid, agent_id: AgentId) -> Self {
+        Self { id, agent_id }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 pub(crate) struct CreateHandler;
@@ -67,6 +78,11 @@ impl EventHandler for CreateHandler {
             .error(AppErrorKind::AccessDenied);
         }
 
+        // Lobby subscriptions of hosts don't affect the agent's state.
+        if payload.is_lobby() {
+            return Ok(Box::new(stream::empty()));
+        }
+
         // Find room.
         let room_id = payload.try_room_id()?;
 
@@ -124,6 +140,11 @@ impl EventHandler for DeleteHandler {
             .error(AppErrorKind::AccessDenied);
         }
 
+        // Lobby subscriptions of hosts don't affect the agent's state.
+        if payload.is_lobby() {
+            return Ok(Box::new(stream::empty()));
+        }
+
         // Delete agent from the DB.
         let room_id = payload.try_room_id()?;
         context.add_logger_tags(o!("room_id" => room_id.to_string()));
@@ -149,55 +170,13 @@ impl EventHandler for DeleteHandler {
             let mut messages = vec![boxed_event];
 
             // `agent.leave` requests to Janus instances that host active streams in this room.
-            let streams = db::janus_rtc_stream::ListQuery::new()
-                .room_id(room_id)
-                .active(true)
-                .execute(&conn)?;
-
-            for stream in streams.iter() {
-                // If the agent is a publisher.
-                if stream.sent_by() == &payload.subject {
-                    // Stop the stream.
-                    db::janus_rtc_stream::stop(stream.id(), &conn)?;
-
-                    // Put stream readers into `ready` status since the stream has gone.
-                    db::agent::BulkStatusUpdateQuery::new(db::agent::Status::Ready)
-                        .room_id(room_id)
-                        .status(db::agent::Status::Connected)
-                        .execute(&conn)?;
-                }
-            }
-
-            // Send agent.leave requests to those backends where the agent is connected to.
-            let mut backend_ids = streams
-                .iter()
-                .map(|stream| stream.backend_id())
-                .collect::<Vec<&AgentId>>();
-
-            backend_ids.dedup();
-
-            let backends = db::janus_backend::ListQuery::new()
-                .ids(&backend_ids[..])
-                .execute(&conn)?;
-
-            for backend in backends {
-                let result = context.janus_client().agent_leave_request(
-                    evp.to_owned(),
-                    backend.session_id(),
-                    backend.handle_id(),
-                    &payload.subject,
-                    backend.id(),
-                    evp.tracking(),
-                );
-
-                match result {
-                    Ok(req) => messages.push(Box::new(req)),
-                    Err(err) => {
-                        return Err(err.context("Error creating a backend request"))
-                            .error(AppErrorKind::MessageBuildingFailed);
-                    }
-                }
-            }
+            messages.extend(helpers::leave_backends(
+                context,
+                room_id,
+                &payload.subject,
+                evp.tracking(),
+                &conn,
+            )?);
 
             Ok(Box::new(stream::from_iter(messages)))
         } else {
@@ -3,23 +3,29 @@ use std::result::Result as StdResult;
 
 use async_std::stream;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use diesel::{pg::PgConnection, Connection};
 use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
 use svc_agent::mqtt::{
     IncomingRequestProperties, IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties,
-    OutgoingMessage, ShortTermTimingProperties, TrackingProperties,
+    OutgoingMessage, ResponseStatus, ShortTermTimingProperties, TrackingProperties,
 };
+use svc_agent::AgentId;
 use svc_authn::Authenticable;
 use uuid::Uuid;
 
 use crate::app::context::Context;
 use crate::app::endpoint::prelude::*;
 use crate::app::error::Error as AppError;
+use crate::app::webhook;
 use crate::backend::janus::requests::UploadStreamRequestBody;
 use crate::config::UploadConfig;
 use crate::db;
+use crate::db::janus_backend::Object as JanusBackend;
 use crate::db::recording::{Object as Recording, Status as RecordingStatus};
 use crate::db::room::Object as Room;
+use crate::template::Placeholder;
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -51,13 +57,30 @@ pub(crate) type RoomUploadEvent = OutgoingMessage<RoomUploadEventData>;
 
 ////////////////////////////////////////////////////////////////////////////////
 
+// A recording gets marked as `failed` after this number of unsuccessful uploads.
+pub(crate) const UPLOAD_MAX_ATTEMPTS: i32 = 5;
+// Seconds to wait before the first retry, doubled on each next one.
+const UPLOAD_RETRY_BASE_DELAY: i64 = 30;
+const UPLOAD_RETRY_MAX_DELAY: i64 = 3600;
+
+/// Returns the backoff before the next upload after the given number of attempts.
+pub(crate) fn upload_retry_delay(attempts: i32) -> Duration {
+    let exponent = (attempts.max(1) - 1).min(16) as u32;
+    let seconds = UPLOAD_RETRY_BASE_DELAY * 2_i64.pow(exponent);
+    Duration::seconds(seconds.min(UPLOAD_RETRY_MAX_DELAY))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Serialize)]
 struct ClosedRoomNotification {
     room_id: Uuid,
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct VacuumRequest {}
+pub(crate) struct VacuumRequest {
+    room_id: Option<Uuid>,
+}
 
 pub(crate) struct VacuumHandler;
 
@@ -68,7 +91,7 @@ impl RequestHandler for VacuumHandler {
 
     async fn handle<C: Context>(
         context: &mut C,
-        _payload: Self::Payload,
+        payload: Self::Payload,
         reqp: &IncomingRequestProperties,
     ) -> Result {
         // Authorization: only trusted subjects are allowed to perform operations with the system
@@ -79,62 +102,152 @@ impl RequestHandler for VacuumHandler {
             .authorize(audience, reqp, vec!["system"], "update")
             .await?;
 
+        let requests = vacuum(context, payload.room_id, Some(reqp.tracking()))?;
+        Ok(Box::new(stream::from_iter(requests)))
+    }
+}
+
+/// Removes agents from finished rooms and uploads their in-progress recordings.
+///
+/// Used both by `system.vacuum` and the built-in vacuum scheduler so the tracking is optional.
+/// Recordings with an upload pending are skipped until their `next_attempt_at` comes
+/// and the ones that have run out of attempts are marked as `failed`.
+pub(crate) fn vacuum<C: Context>(
+    context: &C,
+    room_id: Option<Uuid>,
+    maybe_tracking: Option<&TrackingProperties>,
+) -> StdResult<Vec<Box<dyn IntoPublishableMessage + Send>>, AppError> {
+    let conn = context.get_conn()?;
+
+    conn.transaction::<_, AppError, _>(|| {
         let mut requests = Vec::new();
-        let conn = context.get_conn()?;
-        let rooms = db::room::finished_with_in_progress_recordings(&conn)?;
+        let mut closed_rooms: Vec<Room> = Vec::new();
+        let rows = db::room::finished_with_in_progress_recordings(room_id, &conn)?;
+
+        for (room, recording, backend) in rows.into_iter() {
+            // Another replica is vacuuming this recording at the moment or has just done it.
+            let recording = match db::recording::lock_for_upload(recording.rtc_id(), &conn)? {
+                Some(recording) => recording,
+                None => continue,
+            };
+
+            if recording.attempts() >= UPLOAD_MAX_ATTEMPTS {
+                warn!(
+                    context.logger(),
+                    "Giving up uploading recording after {} attempts, rtc_id = '{}'",
+                    recording.attempts(),
+                    recording.rtc_id(),
+                );
+
+                db::recording::UpdateQuery::new(recording.rtc_id())
+                    .status(RecordingStatus::Failed)
+                    .next_attempt_at(None)
+                    .execute(&conn)?;
+
+                if let Some(event) = room_upload_event(context, &room, maybe_tracking, &conn)? {
+                    requests.push(Box::new(event) as Box<dyn IntoPublishableMessage + Send>);
+                }
+
+                continue;
+            }
 
-        for (room, recording, backend) in rooms.into_iter() {
             db::agent::DeleteQuery::new()
                 .room_id(room.id())
                 .execute(&conn)?;
 
-            let config = upload_config(context, &room)?;
+            record_upload_attempt(context, &recording, &conn)?;
 
             // TODO: Send the error as an event to "app/${APP}/audiences/${AUD}" topic
-            let backreq = context
-                .janus_client()
-                .upload_stream_request(
-                    reqp,
-                    backend.session_id(),
-                    backend.handle_id(),
-                    UploadStreamRequestBody::new(
-                        recording.rtc_id(),
-                        &config.backend,
-                        &config.bucket,
-                        &record_name(&recording),
-                    ),
-                    backend.id(),
-                    context.start_timestamp(),
-                )
-                .map_err(|err| err.context("Error creating a backend request"))
-                .error(AppErrorKind::MessageBuildingFailed)?;
-
-            requests.push(Box::new(backreq) as Box<dyn IntoPublishableMessage + Send>);
+            let backreq =
+                upload_stream_request(context, maybe_tracking, &room, &recording, &backend)?;
+
+            requests.push(backreq);
+
+            // The room gets closed on its first vacuum, upload retries don't close it again.
+            if recording.attempts() == 0 && closed_rooms.iter().all(|r| r.id() != room.id()) {
+                closed_rooms.push(room);
+            }
+        }
+
+        for room in closed_rooms {
+            webhook::enqueue(context, room.audience(), "room.close", &room, &conn)?;
 
             // Publish room closed notification
-            let closed_notification = helpers::build_notification(
-                "room.close",
-                &format!("rooms/{}/events", room.id()),
-                room,
-                reqp,
-                context.start_timestamp(),
-            );
+            let timing = ShortTermTimingProperties::until_now(context.start_timestamp());
+            let mut props = OutgoingEventProperties::new("room.close", timing);
+
+            if let Some(tracking) = maybe_tracking {
+                props.set_tracking(tracking.to_owned());
+            }
 
-            requests.push(closed_notification);
+            let uri = format!("rooms/{}/events", room.id());
+            let closed_notification = OutgoingEvent::broadcast(room, props, &uri);
+            requests.push(Box::new(closed_notification) as Box<dyn IntoPublishableMessage + Send>);
         }
 
-        Ok(Box::new(stream::from_iter(requests)))
+        Ok(requests)
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DrainRequest {
+    backend_id: AgentId,
+    draining: bool,
+}
+
+pub(crate) struct DrainHandler;
+
+#[async_trait]
+impl RequestHandler for DrainHandler {
+    type Payload = DrainRequest;
+    const ERROR_TITLE: &'static str = "Failed to drain backend";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authorization: only trusted subjects are allowed to perform operations with the system
+        let audience = context.agent_id().as_account_id().audience();
+
+        let authz_time = context
+            .authz()
+            .authorize(audience, reqp, vec!["system"], "update")
+            .await?;
+
+        // A draining backend is skipped by the balancer for new recordings
+        // but keeps serving the ones it already hosts.
+        let backend = {
+            let conn = context.get_conn()?;
+
+            db::janus_backend::UpdateQuery::new(&payload.backend_id)
+                .draining(payload.draining)
+                .execute(&conn)?
+                .ok_or_else(|| anyhow!("Janus backend not found"))
+                .error(AppErrorKind::BackendNotFound)?
+        };
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            json!({ "id": backend.id(), "draining": backend.draining() }),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        Ok(Box::new(stream::once(response)))
     }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
-pub(crate) fn upload_event<C: Context, I>(
+fn upload_event_data<C: Context, I>(
     context: &C,
     room: &db::room::Object,
     recordings: I,
-    tracking: &TrackingProperties,
-) -> StdResult<RoomUploadEvent, AppError>
+) -> StdResult<RoomUploadEventData, AppError>
 where
     I: Iterator<Item = db::recording::Object>,
 {
@@ -150,11 +263,11 @@ where
 
                 return Err(err).error(AppErrorKind::MessageBuildingFailed)?;
             }
-            RecordingStatus::Missing => None,
-            RecordingStatus::Ready => Some(format!(
-                "s3://{}/{}",
-                &upload_config(context, &room)?.bucket,
-                record_name(&recording)
+            RecordingStatus::Missing | RecordingStatus::Failed => None,
+            RecordingStatus::Ready => Some(recording_uri(
+                upload_config(context, &room)?,
+                &room,
+                &recording,
             )),
         };
 
@@ -169,20 +282,99 @@ where
         event_entries.push(entry);
     }
 
+    Ok(RoomUploadEventData {
+        id: room.id(),
+        rtcs: event_entries,
+    })
+}
+
+/// Builds `room.upload` event if none of the room's recordings is in progress anymore
+/// and enqueues the same payload for the audience's webhook.
+pub(crate) fn room_upload_event<C: Context>(
+    context: &C,
+    room: &Room,
+    maybe_tracking: Option<&TrackingProperties>,
+    conn: &PgConnection,
+) -> StdResult<Option<RoomUploadEvent>, AppError> {
+    use diesel::prelude::*;
+
+    let rtcs = db::rtc::Object::belonging_to(room).load::<db::rtc::Object>(conn)?;
+    let recs = Recording::belonging_to(&rtcs).load::<Recording>(conn)?;
+
+    let is_complete = rtcs.iter().all(|rtc| {
+        recs.iter()
+            .any(|rec| rec.rtc_id() == rtc.id() && rec.status() != &RecordingStatus::InProgress)
+    });
+
+    if !is_complete {
+        return Ok(None);
+    }
+
+    let event = upload_event_data(context, room, recs.into_iter())?;
+    webhook::enqueue(context, room.audience(), "room.upload", &event, conn)?;
+
     let uri = format!("audiences/{}/events", room.audience());
     let timing = ShortTermTimingProperties::until_now(context.start_timestamp());
     let mut props = OutgoingEventProperties::new("room.upload", timing);
-    props.set_tracking(tracking.to_owned());
 
-    let event = RoomUploadEventData {
-        id: room.id(),
-        rtcs: event_entries,
-    };
+    if let Some(tracking) = maybe_tracking {
+        props.set_tracking(tracking.to_owned());
+    }
+
+    Ok(Some(OutgoingEvent::broadcast(event, props, &uri)))
+}
+
+/// Counts an upload attempt and postpones the next one so that vacuum doesn't retry
+/// until the backend has had time to respond to this one.
+pub(crate) fn record_upload_attempt<C: Context>(
+    context: &C,
+    recording: &Recording,
+    conn: &PgConnection,
+) -> StdResult<(), AppError> {
+    let attempts = recording.attempts() + 1;
+    let upload_timeout = context.config().backend.stream_upload_timeout as i64;
+    let next_attempt_at =
+        Utc::now() + Duration::seconds(upload_timeout) + upload_retry_delay(attempts);
+
+    db::recording::UpdateQuery::new(recording.rtc_id())
+        .attempts(attempts)
+        .next_attempt_at(Some(next_attempt_at))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub(crate) fn upload_stream_request<C: Context>(
+    context: &C,
+    maybe_tracking: Option<&TrackingProperties>,
+    room: &Room,
+    recording: &Recording,
+    backend: &JanusBackend,
+) -> StdResult<Box<dyn IntoPublishableMessage + Send>, AppError> {
+    let config = upload_config(context, room)?;
+
+    let backreq = context
+        .janus_client()
+        .upload_stream_request(
+            maybe_tracking,
+            backend.session_id(),
+            backend.handle_id(),
+            UploadStreamRequestBody::new(
+                recording.rtc_id(),
+                &config.backend,
+                &config.bucket,
+                &recording_object(config, room, recording),
+            ),
+            backend.id(),
+            context.start_timestamp(),
+        )
+        .map_err(|err| err.context("Error creating a backend request"))
+        .error(AppErrorKind::MessageBuildingFailed)?;
 
-    Ok(OutgoingEvent::broadcast(event, props, &uri))
+    Ok(Box::new(backreq))
 }
 
-fn upload_config<'a, C: Context>(
+pub(crate) fn upload_config<'a, C: Context>(
     context: &'a C,
     room: &Room,
 ) -> StdResult<&'a UploadConfig, AppError> {
@@ -194,8 +386,79 @@ fn upload_config<'a, C: Context>(
         .error(AppErrorKind::ConfigKeyMissing)
 }
 
-fn record_name(recording: &Recording) -> String {
-    format!("{}.source.webm", recording.rtc_id())
+/// Renders the object name of the recording in the bucket.
+pub(crate) fn recording_object(
+    config: &UploadConfig,
+    room: &Room,
+    recording: &Recording,
+) -> String {
+    config
+        .object
+        .render(|placeholder| placeholder_value(placeholder, config, room, recording, None))
+}
+
+/// Renders the URI of the uploaded recording.
+pub(crate) fn recording_uri(config: &UploadConfig, room: &Room, recording: &Recording) -> String {
+    let object = recording_object(config, room, recording);
+
+    config.uri.render(|placeholder| {
+        placeholder_value(placeholder, config, room, recording, Some(&object))
+    })
+}
+
+fn placeholder_value(
+    placeholder: &Placeholder,
+    config: &UploadConfig,
+    room: &Room,
+    recording: &Recording,
+    object: Option<&str>,
+) -> String {
+    match placeholder {
+        Placeholder::Audience => room.audience().to_owned(),
+        Placeholder::Bucket => config.bucket.to_owned(),
+        Placeholder::Date => {
+            // The room opening date doesn't change between upload retries unlike `started_at`.
+            let opened_at = match room.time().0 {
+                Bound::Included(opened_at) | Bound::Excluded(opened_at) => opened_at,
+                Bound::Unbounded => room.created_at(),
+            };
+
+            opened_at.format("%Y-%m-%d").to_string()
+        }
+        Placeholder::Object => object.unwrap_or_default().to_owned(),
+        Placeholder::RoomId => room.id().to_string(),
+        Placeholder::RtcId => recording.rtc_id().to_string(),
+        // Missing tags are rendered empty so they don't break the upload.
+        Placeholder::Tag(key) => {
+            let value = match room.tags().get(key) {
+                Some(JsonValue::String(value)) => value.to_owned(),
+                Some(JsonValue::Null) | None => return String::new(),
+                Some(value) => value.to_string(),
+            };
+
+            // Tags are set by clients so they must not be able to put the recording
+            // outside of the intended path. Such values are rendered empty as well.
+            if is_safe_tag_value(&value) {
+                value
+            } else {
+                warn!(
+                    crate::LOG,
+                    "Unsafe value of '{}' tag in upload template, room_id = '{}'",
+                    key,
+                    room.id(),
+                );
+
+                String::new()
+            }
+        }
+    }
+}
+
+fn is_safe_tag_value(value: &str) -> bool {
+    !value.contains("..")
+        && !value
+            .chars()
+            .any(|c| c == '/' || c == '\\' || c.is_control())
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -210,6 +473,7 @@ mod test {
 
         use crate::backend::janus::JANUS_API_VERSION;
         use crate::db;
+        use crate::test_helpers::outgoing_envelope::OutgoingEnvelopeProperties;
         use crate::test_helpers::prelude::*;
         use crate::test_helpers::{find_event_by_predicate, find_request_by_predicate};
 
@@ -277,7 +541,7 @@ mod test {
 
                 // Make system.vacuum request.
                 let mut context = TestContext::new(db, authz);
-                let payload = VacuumRequest {};
+                let payload = VacuumRequest { room_id: None };
 
                 let messages = handle_request::<VacuumHandler>(&mut context, &agent, payload)
                     .await
@@ -334,6 +598,7 @@ mod test {
                         .expect("Failed to get recording from the DB");
 
                     assert_eq!(recording.status(), &RecordingStatus::InProgress);
+                    assert_eq!(recording.attempts(), 1);
 
                     find_event_by_predicate::<JsonValue, _>(&messages, |evp, p, _| {
                         evp.label() == "room.close"
@@ -345,6 +610,311 @@ mod test {
             });
         }
 
+        #[test]
+        fn vacuum_room() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let (rtc, other_rtc) = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        // Insert two closed rooms with in-progress recordings.
+                        let backend = shared_helpers::insert_janus_backend(&conn);
+                        let room = shared_helpers::insert_closed_room(&conn);
+                        let other_room = shared_helpers::insert_closed_room(&conn);
+                        let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                        let other_rtc = shared_helpers::insert_rtc_with_room(&conn, &other_room);
+                        shared_helpers::insert_recording(&conn, &rtc, &backend);
+                        shared_helpers::insert_recording(&conn, &other_rtc, &backend);
+                        (rtc, other_rtc)
+                    })
+                    .unwrap();
+
+                // Allow cron to perform vacuum.
+                let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "update");
+
+                // Make system.vacuum request for the first room only.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = VacuumRequest {
+                    room_id: Some(rtc.room_id()),
+                };
+
+                let messages = handle_request::<VacuumHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("System vacuum failed");
+
+                // Assert the stream is being uploaded only for the requested room.
+                find_request_by_predicate::<VacuumJanusRequest, _>(&messages, |_reqp, p| {
+                    p.body.method == "stream.upload" && p.body.id == rtc.id()
+                })
+                .expect("Failed to find stream.upload message for rtc");
+
+                let other_request =
+                    find_request_by_predicate::<VacuumJanusRequest, _>(&messages, |_reqp, p| {
+                        p.body.id == other_rtc.id()
+                    });
+
+                assert!(other_request.is_none());
+            });
+        }
+
+        #[test]
+        fn vacuum_postponed_recording() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let rtc = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        // Insert a closed room with a recording waiting for the upload retry.
+                        let backend = shared_helpers::insert_janus_backend(&conn);
+                        let room = shared_helpers::insert_closed_room(&conn);
+                        let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                        shared_helpers::insert_recording(&conn, &rtc, &backend);
+
+                        db::recording::UpdateQuery::new(rtc.id())
+                            .attempts(1)
+                            .next_attempt_at(Some(Utc::now() + Duration::minutes(1)))
+                            .execute(&conn)
+                            .unwrap();
+
+                        rtc
+                    })
+                    .unwrap();
+
+                // Allow cron to perform vacuum.
+                let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "update");
+
+                // Make system.vacuum request.
+                let mut context = TestContext::new(db, authz);
+                let payload = VacuumRequest { room_id: None };
+
+                let messages = handle_request::<VacuumHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("System vacuum failed");
+
+                // Assert the upload is not retried before the backoff expires.
+                let request =
+                    find_request_by_predicate::<VacuumJanusRequest, _>(&messages, |_reqp, p| {
+                        p.body.id == rtc.id()
+                    });
+
+                assert!(request.is_none());
+            });
+        }
+
+        #[test]
+        fn vacuum_room_closes_once() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let (room, retried_room) = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        // Insert a closed room with two recordings.
+                        let backend = shared_helpers::insert_janus_backend(&conn);
+                        let room = shared_helpers::insert_closed_room(&conn);
+
+                        for _ in 0..2 {
+                            let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                            shared_helpers::insert_recording(&conn, &rtc, &backend);
+                        }
+
+                        // Insert a closed room with a recording due for the upload retry.
+                        let retried_room = shared_helpers::insert_closed_room(&conn);
+                        let rtc = shared_helpers::insert_rtc_with_room(&conn, &retried_room);
+                        shared_helpers::insert_recording(&conn, &rtc, &backend);
+
+                        db::recording::UpdateQuery::new(rtc.id())
+                            .attempts(1)
+                            .next_attempt_at(Some(Utc::now() - Duration::minutes(1)))
+                            .execute(&conn)
+                            .unwrap();
+
+                        (room, retried_room)
+                    })
+                    .unwrap();
+
+                // Allow cron to perform vacuum.
+                let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "update");
+
+                // Configure a webhook for the rooms' audience.
+                let mut context = TestContext::new(db, authz);
+
+                let webhook_config = json!({
+                    "audiences": {
+                        USR_AUDIENCE: { "url": "http://localhost/webhook", "secret": "secret" }
+                    }
+                });
+
+                context.config_mut().webhook =
+                    Some(serde_json::from_value(webhook_config).expect("Failed to parse config"));
+
+                // Make system.vacuum request.
+                let payload = VacuumRequest { room_id: None };
+
+                let messages = handle_request::<VacuumHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("System vacuum failed");
+
+                // Assert a single room.close event for the room being closed.
+                let close_events_count = |room_id: Uuid| {
+                    messages
+                        .iter()
+                        .filter(|message| match message.properties() {
+                            OutgoingEnvelopeProperties::Event(evp) => {
+                                evp.label() == "room.close"
+                                    && message.payload::<JsonValue>()["id"] == room_id.to_string()
+                            }
+                            _ => false,
+                        })
+                        .count()
+                };
+
+                assert_eq!(close_events_count(room.id()), 1);
+                assert_eq!(close_events_count(retried_room.id()), 0);
+
+                // Assert a single room.close webhook in the outbox.
+                let conn = context.get_conn().expect("Failed to get DB connection");
+
+                let webhooks = crate::schema::webhook::table
+                    .get_results::<crate::db::webhook::Object>(&conn)
+                    .expect("Failed to load webhooks");
+
+                assert_eq!(webhooks.len(), 1);
+                assert_eq!(webhooks[0].payload()["id"], room.id().to_string());
+            });
+        }
+
+        #[test]
+        fn lock_recording_for_upload() {
+            let db = TestDb::new();
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let backend = shared_helpers::insert_janus_backend(&conn);
+            let room = shared_helpers::insert_closed_room(&conn);
+            let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+            shared_helpers::insert_recording(&conn, &rtc, &backend);
+
+            conn.transaction::<_, diesel::result::Error, _>(|| {
+                // Another replica has made an upload attempt since the recording has been read.
+                db::recording::UpdateQuery::new(rtc.id())
+                    .attempts(1)
+                    .next_attempt_at(Some(Utc::now() + Duration::minutes(1)))
+                    .execute(&conn)?;
+
+                let recording = db::recording::lock_for_upload(rtc.id(), &conn)?;
+                assert!(recording.is_none());
+
+                // The recording is due again after the backoff with the actual attempts number.
+                db::recording::UpdateQuery::new(rtc.id())
+                    .next_attempt_at(Some(Utc::now() - Duration::minutes(1)))
+                    .execute(&conn)?;
+
+                let recording =
+                    db::recording::lock_for_upload(rtc.id(), &conn)?.expect("Recording not locked");
+
+                assert_eq!(recording.attempts(), 1);
+                Ok(())
+            })
+            .expect("Failed to lock recording");
+        }
+
+        #[test]
+        fn vacuum_exhausted_recording() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let rtc = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| {
+                        // Insert a closed room with a recording that has run out of attempts.
+                        let backend = shared_helpers::insert_janus_backend(&conn);
+                        let room = shared_helpers::insert_closed_room(&conn);
+                        let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                        shared_helpers::insert_recording(&conn, &rtc, &backend);
+
+                        db::recording::UpdateQuery::new(rtc.id())
+                            .attempts(UPLOAD_MAX_ATTEMPTS)
+                            .next_attempt_at(Some(Utc::now() - Duration::minutes(1)))
+                            .execute(&conn)
+                            .unwrap();
+
+                        rtc
+                    })
+                    .unwrap();
+
+                // Allow cron to perform vacuum.
+                let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "update");
+
+                // Make system.vacuum request.
+                let mut context = TestContext::new(db, authz);
+                let payload = VacuumRequest { room_id: None };
+
+                let messages = handle_request::<VacuumHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("System vacuum failed");
+
+                // Assert the upload is not retried anymore.
+                let request =
+                    find_request_by_predicate::<VacuumJanusRequest, _>(&messages, |_reqp, p| {
+                        p.body.id == rtc.id()
+                    });
+
+                assert!(request.is_none());
+
+                // Assert room.upload event reporting the failed rtc.
+                let (payload, _, _) =
+                    find_event_by_predicate::<JsonValue, _>(&messages, |evp, _, _| {
+                        evp.label() == "room.upload"
+                    })
+                    .expect("Failed to find room.upload event");
+
+                assert_eq!(payload["id"], rtc.room_id().to_string());
+                assert_eq!(payload["rtcs"][0]["id"], rtc.id().to_string());
+                assert_eq!(payload["rtcs"][0]["status"], "failed");
+                assert!(payload["rtcs"][0].get("uri").is_none());
+
+                // Assert the recording is marked as failed.
+                let conn = context.get_conn().unwrap();
+
+                let recording = db::recording::FindQuery::new(rtc.id())
+                    .execute(&conn)
+                    .expect("Failed to find recording")
+                    .expect("Recording not found");
+
+                assert_eq!(recording.status(), &RecordingStatus::Failed);
+            });
+        }
+
+        #[test]
+        fn upload_retry_backoff() {
+            assert_eq!(upload_retry_delay(1), Duration::seconds(30));
+            assert_eq!(upload_retry_delay(2), Duration::seconds(60));
+            assert_eq!(upload_retry_delay(3), Duration::seconds(120));
+            assert_eq!(upload_retry_delay(100), Duration::seconds(3600));
+        }
+
         #[test]
         fn vacuum_system_unauthorized() {
             async_std::task::block_on(async {
@@ -355,7 +925,7 @@ mod test {
                 // Make system.vacuum request.
                 let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
                 let mut context = TestContext::new(db, authz);
-                let payload = VacuumRequest {};
+                let payload = VacuumRequest { room_id: None };
 
                 let err = handle_request::<VacuumHandler>(&mut context, &agent, payload)
                     .await
@@ -366,4 +936,259 @@ mod test {
             })
         }
     }
+
+    mod upload {
+        use chrono::{TimeZone, Utc};
+        use serde_json::json;
+
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn render_recording_templates() {
+            let db = TestDb::new();
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get db connection");
+            let opened_at = Utc.ymd(2020, 12, 1).and_hms(10, 0, 0);
+
+            let room = factory::Room::new()
+                .audience(USR_AUDIENCE)
+                .time((Bound::Included(opened_at), Bound::Unbounded))
+                .tags(&json!({ "scope": "webinar" }))
+                .insert(&conn);
+
+            let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+            let backend = shared_helpers::insert_janus_backend(&conn);
+            let recording = shared_helpers::insert_recording(&conn, &rtc, &backend);
+
+            let config = serde_json::from_value::<UploadConfig>(json!({
+                "backend": "EXAMPLE",
+                "bucket": "records",
+                "object": "{audience}/{date}/{tags.scope}/{tags.missing}{rtc_id}.webm",
+                "uri": "https://storage.example.org/{bucket}/{object}",
+            }))
+            .expect("Failed to parse upload config");
+
+            let object = format!("{}/2020-12-01/webinar/{}.webm", USR_AUDIENCE, rtc.id());
+            assert_eq!(recording_object(&config, &room, &recording), object);
+
+            assert_eq!(
+                recording_uri(&config, &room, &recording),
+                format!("https://storage.example.org/records/{}", object),
+            );
+        }
+
+        #[test]
+        fn render_hostile_tags() {
+            let db = TestDb::new();
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get db connection");
+
+            let config = serde_json::from_value::<UploadConfig>(json!({
+                "backend": "EXAMPLE",
+                "bucket": "records",
+                "object": "{tags.scope}/{rtc_id}.webm",
+            }))
+            .expect("Failed to parse upload config");
+
+            let hostile_values = vec![
+                json!("../../other-audience"),
+                json!("webinar/../../secrets"),
+                json!("/absolute"),
+                json!(".."),
+                json!("back\\slash"),
+                json!("line\nbreak"),
+                json!({ "nested": "../" }),
+            ];
+
+            for value in hostile_values {
+                let room = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                    .tags(&json!({ "scope": value }))
+                    .insert(&conn);
+
+                let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                let backend = shared_helpers::insert_janus_backend(&conn);
+                let recording = shared_helpers::insert_recording(&conn, &rtc, &backend);
+
+                assert_eq!(
+                    recording_object(&config, &room, &recording),
+                    format!("/{}.webm", rtc.id()),
+                );
+            }
+        }
+
+        #[test]
+        fn render_default_templates() {
+            let db = TestDb::new();
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get db connection");
+            let room = shared_helpers::insert_room(&conn);
+            let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+            let backend = shared_helpers::insert_janus_backend(&conn);
+            let recording = shared_helpers::insert_recording(&conn, &rtc, &backend);
+
+            let config = serde_json::from_value::<UploadConfig>(json!({
+                "backend": "EXAMPLE",
+                "bucket": "records",
+            }))
+            .expect("Failed to parse upload config");
+
+            assert_eq!(
+                recording_uri(&config, &room, &recording),
+                format!("s3://records/{}.source.webm", rtc.id()),
+            );
+        }
+
+        #[test]
+        fn reject_invalid_templates() {
+            let config = json!({
+                "backend": "EXAMPLE",
+                "bucket": "records",
+                "object": "{object}.webm",
+            });
+
+            assert!(serde_json::from_value::<UploadConfig>(config).is_err());
+
+            let config = json!({
+                "backend": "EXAMPLE",
+                "bucket": "records",
+                "uri": "s3://{bucket}/{unknown}",
+            });
+
+            assert!(serde_json::from_value::<UploadConfig>(config).is_err());
+
+            // Recordings of the same room would overwrite each other.
+            for object in &["{room_id}.webm", "{audience}/{date}.webm"] {
+                let config = json!({
+                    "backend": "EXAMPLE",
+                    "bucket": "records",
+                    "object": object,
+                });
+
+                assert!(serde_json::from_value::<UploadConfig>(config).is_err());
+            }
+        }
+    }
+
+    mod drain {
+        use serde_json::Value as JsonValue;
+        use svc_agent::mqtt::ResponseStatus;
+
+        use crate::db;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn drain_backend() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let backend = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| shared_helpers::insert_janus_backend(&conn))
+                    .unwrap();
+
+                let agent = TestAgent::new("alpha", "devops", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "update");
+
+                // Make system.drain request.
+                let mut context = TestContext::new(db, authz);
+
+                let payload = DrainRequest {
+                    backend_id: backend.id().to_owned(),
+                    draining: true,
+                };
+
+                let messages = handle_request::<DrainHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("System drain failed");
+
+                let (resp, respp) = find_response::<JsonValue>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(resp["draining"], true);
+
+                // Assert the backend is marked as draining in the DB.
+                let conn = context.get_conn().unwrap();
+
+                let backend = db::janus_backend::FindQuery::new()
+                    .id(backend.id().to_owned())
+                    .execute(&conn)
+                    .expect("Failed to find backend")
+                    .expect("Backend not found");
+
+                assert!(backend.draining());
+            });
+        }
+
+        #[test]
+        fn drain_backend_missing() {
+            async_std::task::block_on(async {
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let agent = TestAgent::new("alpha", "devops", SVC_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["system"], "update");
+
+                // Make system.drain request.
+                let mut context = TestContext::new(TestDb::new(), authz);
+                let backend = TestAgent::new("alpha", "janus-gateway-missing", SVC_AUDIENCE);
+
+                let payload = DrainRequest {
+                    backend_id: backend.agent_id().to_owned(),
+                    draining: true,
+                };
+
+                let err = handle_request::<DrainHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on system drain");
+
+                assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+                assert_eq!(err.kind(), "backend_not_found");
+            });
+        }
+
+        #[test]
+        fn drain_backend_unauthorized() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let mut authz = TestAuthz::new();
+                authz.set_audience(SVC_AUDIENCE);
+
+                let backend = db
+                    .connection_pool()
+                    .get()
+                    .map(|conn| shared_helpers::insert_janus_backend(&conn))
+                    .unwrap();
+
+                // Make system.drain request.
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                let mut context = TestContext::new(db, authz);
+
+                let payload = DrainRequest {
+                    backend_id: backend.id().to_owned(),
+                    draining: true,
+                };
+
+                let err = handle_request::<DrainHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on system drain");
+
+                assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+                assert_eq!(err.kind(), "access_denied");
+            });
+        }
+    }
 }
@@ -17,6 +17,7 @@ struct ErrorKindProperties {
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum ErrorKind {
     AccessDenied,
+    AgentBanned,
     AgentNotEnteredTheRoom,
     AuthorizationFailed,
     BackendRecordingMissing,
@@ -27,7 +28,9 @@ pub(crate) enum ErrorKind {
     ConfigKeyMissing,
     DbConnAcquisitionFailed,
     DbQueryFailed,
+    InvalidHandleId,
     InvalidJsepFormat,
+    InvalidPagination,
     InvalidRoomTime,
     InvalidSdpType,
     InvalidSubscriptionObject,
@@ -37,11 +40,18 @@ pub(crate) enum ErrorKind {
     NoAvailableBackends,
     NotImplemented,
     PublishFailed,
+    PublisherLimitReached,
+    RateLimitExceeded,
+    RecordingNotFound,
     ResubscriptionFailed,
     RoomClosed,
+    RoomFull,
     RoomNotFound,
+    RoomNotRelated,
     RtcNotFound,
     StatsCollectionFailed,
+    UnicastTimedOut,
+    UploadNotFinished,
 }
 
 impl ErrorKind {
@@ -82,6 +92,12 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Access denied",
                 is_notify_sentry: false,
             },
+            Self::AgentBanned => ErrorKindProperties {
+                status: ResponseStatus::FORBIDDEN,
+                kind: "agent_banned",
+                title: "Agent banned from the room",
+                is_notify_sentry: false,
+            },
             Self::AgentNotEnteredTheRoom => ErrorKindProperties {
                 status: ResponseStatus::NOT_FOUND,
                 kind: "agent_not_entered_the_room",
@@ -107,7 +123,7 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 is_notify_sentry: true,
             },
             Self::BackendRequestTimedOut => ErrorKindProperties {
-                status: ResponseStatus::FAILED_DEPENDENCY,
+                status: ResponseStatus::GATEWAY_TIMEOUT,
                 kind: "backend_request_timed_out",
                 title: "Janus request timed out",
                 is_notify_sentry: true,
@@ -142,12 +158,24 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Database query failed",
                 is_notify_sentry: true,
             },
+            Self::InvalidHandleId => ErrorKindProperties {
+                status: ResponseStatus::BAD_REQUEST,
+                kind: "invalid_handle_id",
+                title: "Invalid handle ID",
+                is_notify_sentry: false,
+            },
             Self::InvalidJsepFormat => ErrorKindProperties {
                 status: ResponseStatus::BAD_REQUEST,
                 kind: "invalid_jsep_format",
                 title: "Invalid JSEP format",
                 is_notify_sentry: false,
             },
+            Self::InvalidPagination => ErrorKindProperties {
+                status: ResponseStatus::BAD_REQUEST,
+                kind: "invalid_pagination",
+                title: "Invalid pagination",
+                is_notify_sentry: false,
+            },
             Self::InvalidRoomTime => ErrorKindProperties {
                 status: ResponseStatus::BAD_REQUEST,
                 kind: "invalid_room_time",
@@ -202,6 +230,24 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Publish failed",
                 is_notify_sentry: true,
             },
+            Self::PublisherLimitReached => ErrorKindProperties {
+                status: ResponseStatus::UNPROCESSABLE_ENTITY,
+                kind: "publisher_limit_reached",
+                title: "Publisher limit reached",
+                is_notify_sentry: false,
+            },
+            Self::RateLimitExceeded => ErrorKindProperties {
+                status: ResponseStatus::TOO_MANY_REQUESTS,
+                kind: "rate_limit_exceeded",
+                title: "Rate limit exceeded",
+                is_notify_sentry: false,
+            },
+            Self::RecordingNotFound => ErrorKindProperties {
+                status: ResponseStatus::NOT_FOUND,
+                kind: "recording_not_found",
+                title: "Recording not found",
+                is_notify_sentry: false,
+            },
             Self::ResubscriptionFailed => ErrorKindProperties {
                 status: ResponseStatus::INTERNAL_SERVER_ERROR,
                 kind: "resubscription_failed",
@@ -214,12 +260,24 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Room closed",
                 is_notify_sentry: false,
             },
+            Self::RoomFull => ErrorKindProperties {
+                status: ResponseStatus::UNPROCESSABLE_ENTITY,
+                kind: "room_full",
+                title: "Room full",
+                is_notify_sentry: false,
+            },
             Self::RoomNotFound => ErrorKindProperties {
                 status: ResponseStatus::NOT_FOUND,
                 kind: "room_not_found",
                 title: "Room not found",
                 is_notify_sentry: false,
             },
+            Self::RoomNotRelated => ErrorKindProperties {
+                status: ResponseStatus::UNPROCESSABLE_ENTITY,
+                kind: "room_not_related",
+                title: "Rooms are not related",
+                is_notify_sentry: false,
+            },
             Self::RtcNotFound => ErrorKindProperties {
                 status: ResponseStatus::NOT_FOUND,
                 kind: "rtc_not_found",
@@ -232,6 +290,18 @@ impl Into<ErrorKindProperties> for ErrorKind {
                 title: "Stats collection failed",
                 is_notify_sentry: true,
             },
+            Self::UnicastTimedOut => ErrorKindProperties {
+                status: ResponseStatus::GATEWAY_TIMEOUT,
+                kind: "unicast_timed_out",
+                title: "Unicast message timed out",
+                is_notify_sentry: false,
+            },
+            Self::UploadNotFinished => ErrorKindProperties {
+                status: ResponseStatus::UNPROCESSABLE_ENTITY,
+                kind: "upload_not_finished",
+                title: "Upload not finished",
+                is_notify_sentry: false,
+            },
         }
     }
 }
@@ -1,9 +1,31 @@
 use std::fmt;
 use std::str::FromStr;
 
+use anyhow::Context;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use openssl::{hash::MessageDigest, memcmp, pkey::PKey, sign::Signer};
 use svc_agent::AgentId;
 use uuid::Uuid;
 
+use crate::app::error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind};
+use crate::config::HandleIdConfig;
+
+////////////////////////////////////////////////////////////////////////////////
+
+fn sign(key: &str, data: &str) -> anyhow::Result<Vec<u8>> {
+    let pkey = PKey::hmac(key.as_bytes())?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
+    signer.update(data.as_bytes())?;
+    Ok(signer.sign_to_vec()?)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A handle id which signature and expiration have been checked.
+///
+/// It's either built by the service itself or obtained from
+/// [UnverifiedHandleId::verify](struct.UnverifiedHandleId.html#method.verify) so it can't be
+/// parsed from a client's input directly.
 #[derive(Debug)]
 pub(crate) struct HandleId {
     rtc_stream_id: Uuid,
@@ -11,6 +33,8 @@ pub(crate) struct HandleId {
     janus_handle_id: i64,
     janus_session_id: i64,
     backend_id: AgentId,
+    expires_at: DateTime<Utc>,
+    signature: Vec<u8>,
 }
 
 impl HandleId {
@@ -42,14 +66,52 @@ impl HandleId {
         janus_handle_id: i64,
         janus_session_id: i64,
         backend_id: AgentId,
-    ) -> Self {
-        Self {
+        config: &HandleIdConfig,
+    ) -> Result<Self, AppError> {
+        let mut handle_id = Self {
             rtc_stream_id,
             rtc_id,
             janus_handle_id,
             janus_session_id,
             backend_id,
+            expires_at: Utc::now() + Duration::seconds(config.ttl as i64),
+            signature: vec![],
+        };
+
+        handle_id.signature = sign(&config.key, &handle_id.signed_data())
+            .context("Failed to sign handle id")
+            .error(AppErrorKind::MessageBuildingFailed)?;
+
+        Ok(handle_id)
+    }
+
+    // The signature covers every field so none of them may be altered by the client.
+    fn signed_data(&self) -> String {
+        format!(
+            "{}.{}.{}.{}.{}.{}",
+            self.rtc_stream_id,
+            self.rtc_id,
+            self.janus_handle_id,
+            self.janus_session_id,
+            self.expires_at.timestamp(),
+            self.backend_id
+        )
+    }
+
+    fn verify_signature(&self, config: &HandleIdConfig) -> anyhow::Result<()> {
+        let expected_signature = sign(&config.key, &self.signed_data())?;
+
+        if self.signature.len() != expected_signature.len()
+            || !memcmp::eq(&self.signature, &expected_signature)
+        {
+            return Err(anyhow!("Signature mismatch"));
+        }
+
+        if self.expires_at <= Utc::now() {
+            return Err(anyhow!("Handle id expired at {}", self.expires_at));
         }
+
+        Ok(())
     }
 }
 
@@ -57,33 +119,74 @@ impl fmt::Display for HandleId {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         write!(
             fmt,
-            "{}.{}.{}.{}.{}",
+            "{}.{}.{}.{}.{}.{}.{}",
             self.rtc_stream_id,
             self.rtc_id,
             self.janus_handle_id,
             self.janus_session_id,
+            self.expires_at.timestamp(),
+            base64::encode_config(&self.signature, base64::URL_SAFE_NO_PAD),
             self.backend_id
         )
     }
 }
 
-impl FromStr for HandleId {
-    type Err = anyhow::Error;
+////////////////////////////////////////////////////////////////////////////////
+
+/// A handle id as it comes from a client.
+///
+/// Parsing doesn't check the signature since the key isn't available to the deserializer.
+/// Call [verify](#method.verify) to get a [HandleId](struct.HandleId.html) out of it.
+#[derive(Debug)]
+pub(crate) struct UnverifiedHandleId(HandleId);
+
+impl UnverifiedHandleId {
+    /// Checks the signature and expiration.
+    pub(crate) fn verify(self, config: &HandleIdConfig) -> Result<HandleId, AppError> {
+        self.0
+            .verify_signature(config)
+            .error(AppErrorKind::InvalidHandleId)?;
+
+        Ok(self.0)
+    }
+}
+
+impl From<HandleId> for UnverifiedHandleId {
+    fn from(handle_id: HandleId) -> Self {
+        Self(handle_id)
+    }
+}
+
+impl FromStr for UnverifiedHandleId {
+    type Err = AppError;
 
     fn from_str(val: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = val.splitn(5, '.').collect();
-        match parts[..] {
-            [ref rtc_stream_id, ref rtc_id, ref janus_handle_id, ref janus_session_id, ref rest] => {
-                Ok(Self::new(
-                    Uuid::from_str(rtc_stream_id)?,
-                    Uuid::from_str(rtc_id)?,
-                    janus_handle_id.parse::<i64>()?,
-                    janus_session_id.parse::<i64>()?,
-                    rest.parse::<AgentId>()?,
-                ))
-            }
-            _ => Err(anyhow!("Invalid handle id: {}", val)),
+        parse(val).map(Self).error(AppErrorKind::InvalidHandleId)
+    }
+}
+
+fn parse(val: &str) -> anyhow::Result<HandleId> {
+    let parts: Vec<&str> = val.splitn(7, '.').collect();
+
+    match parts[..] {
+        [ref rtc_stream_id, ref rtc_id, ref janus_handle_id, ref janus_session_id, ref expires_at, ref signature, ref rest] =>
+        {
+            let expires_at = Utc
+                .timestamp_opt(expires_at.parse::<i64>()?, 0)
+                .single()
+                .ok_or_else(|| anyhow!("Invalid expiration time: {}", expires_at))?;
+
+            Ok(HandleId {
+                rtc_stream_id: Uuid::from_str(rtc_stream_id)?,
+                rtc_id: Uuid::from_str(rtc_id)?,
+                janus_handle_id: janus_handle_id.parse::<i64>()?,
+                janus_session_id: janus_session_id.parse::<i64>()?,
+                backend_id: rest.parse::<AgentId>()?,
+                expires_at,
+                signature: base64::decode_config(signature, base64::URL_SAFE_NO_PAD)?,
+            })
         }
+        _ => Err(anyhow!("Invalid handle id: {}", val)),
     }
 }
 
@@ -93,7 +196,7 @@ mod serde {
     use serde::{de, ser};
     use std::fmt;
 
-    use super::HandleId;
+    use super::{HandleId, UnverifiedHandleId};
 
     impl ser::Serialize for HandleId {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -104,7 +207,7 @@ mod serde {
         }
     }
 
-    impl<'de> de::Deserialize<'de> for HandleId {
+    impl<'de> de::Deserialize<'de> for UnverifiedHandleId {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
             D: de::Deserializer<'de>,
@@ -112,7 +215,7 @@ mod serde {
             struct AgentIdVisitor;
 
             impl<'de> de::Visitor<'de> for AgentIdVisitor {
-                type Value = HandleId;
+                type Value = UnverifiedHandleId;
 
                 fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
                     formatter.write_str("struct HandleId")
@@ -124,8 +227,7 @@ mod serde {
                 {
                     use std::str::FromStr;
 
-                    HandleId::from_str(v)
-                        .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(v), &self))
+                    UnverifiedHandleId::from_str(v).map_err(de::Error::custom)
                 }
             }
 
@@ -133,3 +235,113 @@ mod serde {
         }
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::test_helpers::{context::build_config, prelude::*};
+
+    use super::{sign, HandleId, UnverifiedHandleId};
+
+    fn build_handle_id() -> HandleId {
+        let backend = TestAgent::new("alpha", "janus", SVC_AUDIENCE);
+
+        HandleId::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            123,
+            456,
+            backend.agent_id().to_owned(),
+            &build_config().handle_id,
+        )
+        .expect("Failed to build handle id")
+    }
+
+    #[test]
+    fn parse_signed_handle_id() {
+        let handle_id = build_handle_id();
+        let parsed = UnverifiedHandleId::from_str(&handle_id.to_string())
+            .expect("Failed to parse handle id")
+            .verify(&build_config().handle_id)
+            .expect("Failed to verify handle id");
+
+        assert_eq!(parsed.rtc_stream_id(), handle_id.rtc_stream_id());
+        assert_eq!(parsed.rtc_id(), handle_id.rtc_id());
+        assert_eq!(parsed.janus_handle_id(), 123);
+        assert_eq!(parsed.janus_session_id(), 456);
+        assert_eq!(parsed.backend_id(), handle_id.backend_id());
+    }
+
+    #[test]
+    fn deserialize_signed_handle_id() {
+        let handle_id = build_handle_id();
+
+        let parsed = serde_json::from_value::<UnverifiedHandleId>(json!(handle_id))
+            .expect("Failed to deserialize handle id")
+            .verify(&build_config().handle_id)
+            .expect("Failed to verify handle id");
+
+        assert_eq!(parsed.rtc_stream_id(), handle_id.rtc_stream_id());
+    }
+
+    #[test]
+    fn reject_tampered_handle_id() {
+        let handle_id = build_handle_id();
+
+        let other_backend = TestAgent::new("alpha", "other-janus", SVC_AUDIENCE);
+        let tampered = handle_id.to_string().replace(".456.", ".789.").replace(
+            &handle_id.backend_id().to_string(),
+            &other_backend.agent_id().to_string(),
+        );
+
+        let parsed = UnverifiedHandleId::from_str(&tampered).expect("Failed to parse handle id");
+
+        let err = parsed
+            .verify(&build_config().handle_id)
+            .expect_err("Unexpected success on verifying");
+
+        assert_eq!(err.kind(), "invalid_handle_id");
+    }
+
+    #[test]
+    fn reject_handle_id_signed_with_other_key() {
+        let handle_id = build_handle_id();
+        let mut config = build_config().handle_id;
+        config.key = String::from("other-key");
+
+        let err = UnverifiedHandleId::from(handle_id)
+            .verify(&config)
+            .expect_err("Unexpected success on verifying");
+
+        assert_eq!(err.kind(), "invalid_handle_id");
+    }
+
+    #[test]
+    fn reject_expired_handle_id() {
+        let mut handle_id = build_handle_id();
+        handle_id.expires_at = Utc::now() - Duration::seconds(1);
+
+        handle_id.signature = sign(&build_config().handle_id.key, &handle_id.signed_data())
+            .expect("Failed to sign handle id");
+
+        let err = UnverifiedHandleId::from(handle_id)
+            .verify(&build_config().handle_id)
+            .expect_err("Unexpected success on verifying");
+
+        assert_eq!(err.kind(), "invalid_handle_id");
+    }
+
+    #[test]
+    fn reject_malformed_handle_id() {
+        let err =
+            UnverifiedHandleId::from_str("garbage").expect_err("Unexpected success on parsing");
+        assert_eq!(err.kind(), "invalid_handle_id");
+    }
+}
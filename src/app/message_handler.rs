@@ -17,7 +17,9 @@ use svc_error::Error as SvcError;
 
 use crate::app::context::{AppMessageContext, Context, GlobalContext, MessageContext};
 use crate::app::error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind};
-use crate::app::{endpoint, API_VERSION};
+use crate::app::{endpoint, webhook, API_VERSION};
+use crate::backend;
+use crate::config::KeepaliveConfig;
 
 pub(crate) type MessageStream =
     Box<dyn Stream<Item = Box<dyn IntoPublishableMessage + Send>> + Send + Unpin>;
@@ -62,6 +64,51 @@ impl<C: GlobalContext + Sync> MessageHandler<C> {
         }
     }
 
+    pub(crate) async fn handle_janus_keepalive(&self, config: &KeepaliveConfig) {
+        let mut msg_context = AppMessageContext::new(&self.global_context, Utc::now());
+
+        let result = match backend::janus::handle_keepalive(&mut msg_context, config) {
+            Ok(outgoing_message_stream) => {
+                self.publish_outgoing_messages(outgoing_message_stream)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            error!(
+                msg_context.logger(),
+                "Failed to send janus keepalives: {}", err
+            );
+
+            err.notify_sentry(msg_context.logger());
+        }
+    }
+
+    pub(crate) async fn handle_vacuum(&self) {
+        let msg_context = AppMessageContext::new(&self.global_context, Utc::now());
+
+        let result = match endpoint::system::vacuum(&msg_context, None, None) {
+            Ok(messages) => {
+                self.publish_outgoing_messages(Box::new(stream::from_iter(messages)))
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            error!(msg_context.logger(), "Failed to vacuum: {}", err);
+            err.notify_sentry(msg_context.logger());
+        }
+    }
+
+    pub(crate) fn handle_webhook_delivery(&self) {
+        if let Err(err) = webhook::deliver(&self.global_context) {
+            error!(crate::LOG, "Failed to deliver webhooks: {}", err);
+            err.notify_sentry(&crate::LOG);
+        }
+    }
+
     async fn report_error(
         msg_context: &mut AppMessageContext<'_, C>,
         message: &Result<IncomingMessage<String>, String>,
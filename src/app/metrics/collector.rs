@@ -22,6 +22,7 @@ impl<'a, C: GlobalContext> Collector<'a, C> {
         append_dynamic_stats(&mut metrics, self.context, now)?;
 
         append_janus_stats(&mut metrics, self.context, now)?;
+        append_webhook_stats(&mut metrics, self.context, now)?;
 
         Ok(metrics)
     }
@@ -185,6 +186,17 @@ fn append_janus_stats(
         tags.clone(),
     ));
 
+    // The number of janus backends being taken out of rotation.
+    let draining_backends_count = crate::db::janus_backend::draining_count(&conn)
+        .context("Failed to get draining janus backends count")?;
+
+    metrics.push(Metric::new(
+        MetricKey::DrainingJanusBackendsCount,
+        draining_backends_count,
+        now,
+        tags.clone(),
+    ));
+
     // Total capacity of online janus backends.
     let total_capacity = crate::db::janus_backend::total_capacity(&conn)
         .context("Failed to get janus backends total capacity")?;
@@ -236,5 +248,51 @@ fn append_janus_stats(
 
     metrics.extend(backend_load);
 
+    // Milliseconds of the last acknowledged keepalive round trip for each backend.
+    let backends = crate::db::janus_backend::ListQuery::new()
+        .execute(&conn)
+        .context("Failed to get janus backends")?;
+
+    for backend in backends {
+        if let Some(latency) = backend.keepalive_latency() {
+            let tags = Tags::build_janus_tags(crate::APP_VERSION, context.agent_id(), backend.id());
+
+            metrics.push(Metric::new(
+                MetricKey::JanusBackendKeepaliveLatency,
+                latency,
+                now,
+                tags,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn append_webhook_stats(
+    metrics: &mut Vec<Metric>,
+    context: &impl GlobalContext,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    if context.config().webhook.is_none() {
+        return Ok(());
+    }
+
+    let conn = context
+        .get_conn()
+        .map_err(|err| anyhow!("Failed to get DB connection: {}", err))?;
+
+    // Seconds the oldest undelivered webhook has been waiting in the outbox.
+    let oldest_created_at = crate::db::webhook::oldest_created_at(&conn)
+        .context("Failed to get the oldest webhook enqueue time")?;
+
+    let lag = oldest_created_at
+        .map(|created_at| (now - created_at).num_seconds().max(0))
+        .unwrap_or(0);
+
+    let tags = Tags::build_internal_tags(crate::APP_VERSION, context.agent_id());
+    metrics.push(Metric::new(MetricKey::WebhookDeliveryLag, lag, now, tags));
     Ok(())
 }
@@ -135,6 +135,8 @@ pub(crate) enum MetricKey {
     IdleRedisConnections,
     #[serde(rename(serialize = "apps.conference.online_janus_backends_total"))]
     OnlineJanusBackendsCount,
+    #[serde(rename(serialize = "apps.conference.draining_janus_backends_total"))]
+    DrainingJanusBackendsCount,
     #[serde(rename(serialize = "apps.conference.janus_backends_capacity_total"))]
     JanusBackendTotalCapacity,
     #[serde(rename(serialize = "apps.conference.connected_agents_total"))]
@@ -143,6 +145,10 @@ pub(crate) enum MetricKey {
     JanusBackendReserveLoad,
     #[serde(rename(serialize = "apps.conference.janus_backend_agent_load_total"))]
     JanusBackendAgentLoad,
+    #[serde(rename(serialize = "apps.conference.janus_backend_keepalive_latency_total"))]
+    JanusBackendKeepaliveLatency,
+    #[serde(rename(serialize = "apps.conference.webhook_delivery_lag_total"))]
+    WebhookDeliveryLag,
     #[serde(serialize_with = "serialize_dynamic_metric")]
     Dynamic(String),
 }
@@ -172,6 +178,8 @@ pub(crate) enum MetricKey2 {
     IdleRedisConnections,
     #[serde(rename(serialize = "online_janus_backends_total"))]
     OnlineJanusBackendsCount,
+    #[serde(rename(serialize = "draining_janus_backends_total"))]
+    DrainingJanusBackendsCount,
     #[serde(rename(serialize = "janus_backends_capacity_total"))]
     JanusBackendTotalCapacity,
     #[serde(rename(serialize = "connected_agents_total"))]
@@ -180,6 +188,10 @@ pub(crate) enum MetricKey2 {
     JanusBackendReserveLoad,
     #[serde(rename(serialize = "janus_backend_agent_load_total"))]
     JanusBackendAgentLoad,
+    #[serde(rename(serialize = "janus_backend_keepalive_latency_total"))]
+    JanusBackendKeepaliveLatency,
+    #[serde(rename(serialize = "webhook_delivery_lag_total"))]
+    WebhookDeliveryLag,
     #[serde(serialize_with = "serialize_dynamic_metric2")]
     Dynamic(String),
 }
@@ -198,11 +210,14 @@ impl From<MetricKey> for MetricKey2 {
             MetricKey::RedisConnections => MetricKey2::RedisConnections,
             MetricKey::IdleRedisConnections => MetricKey2::IdleRedisConnections,
             MetricKey::OnlineJanusBackendsCount => MetricKey2::OnlineJanusBackendsCount,
+            MetricKey::DrainingJanusBackendsCount => MetricKey2::DrainingJanusBackendsCount,
             MetricKey::JanusBackendTotalCapacity => MetricKey2::JanusBackendTotalCapacity,
             MetricKey::ConnectedAgentsCount => MetricKey2::ConnectedAgentsCount,
             MetricKey::Dynamic(key) => MetricKey2::Dynamic(key),
             MetricKey::JanusBackendReserveLoad => MetricKey2::JanusBackendReserveLoad,
             MetricKey::JanusBackendAgentLoad => MetricKey2::JanusBackendAgentLoad,
+            MetricKey::JanusBackendKeepaliveLatency => MetricKey2::JanusBackendKeepaliveLatency,
+            MetricKey::WebhookDeliveryLag => MetricKey2::WebhookDeliveryLag,
         }
     }
 }
@@ -221,10 +236,15 @@ impl std::fmt::Display for MetricKey2 {
             MetricKey2::RedisConnections => write!(f, "redis_connections_total"),
             MetricKey2::IdleRedisConnections => write!(f, "idle_redis_connections_total"),
             MetricKey2::OnlineJanusBackendsCount => write!(f, "online_janus_backends_total"),
+            MetricKey2::DrainingJanusBackendsCount => write!(f, "draining_janus_backends_total"),
             MetricKey2::JanusBackendTotalCapacity => write!(f, "janus_backends_capacity_total"),
             MetricKey2::ConnectedAgentsCount => write!(f, "connected_agents_total"),
             MetricKey2::JanusBackendReserveLoad => write!(f, "janus_backend_reserve_load_total"),
             MetricKey2::JanusBackendAgentLoad => write!(f, "janus_backend_agent_load_total"),
+            MetricKey2::JanusBackendKeepaliveLatency => {
+                write!(f, "janus_backend_keepalive_latency_total")
+            }
+            MetricKey2::WebhookDeliveryLag => write!(f, "webhook_delivery_lag_total"),
             MetricKey2::Dynamic(key) => write!(f, "{}_total", key),
         }
     }
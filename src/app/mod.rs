@@ -22,7 +22,7 @@ use crate::app::context::GlobalContext;
 use crate::app::error::{Error as AppError, ErrorKind as AppErrorKind};
 use crate::app::metrics::StatsRoute;
 use crate::backend::janus::Client as JanusClient;
-use crate::config::{self, Config, KruonisConfig};
+use crate::config::{self, Config, KeepaliveConfig, KruonisConfig, VacuumConfig, WebhookConfig};
 use crate::db::ConnectionPool;
 use context::{AppContext, JanusTopics};
 use message_handler::MessageHandler;
@@ -90,10 +90,11 @@ pub(crate) async fn run(
         config.clone(),
         authz,
         db.clone(),
-        JanusClient::start(&config.backend, agent_id)?,
+        JanusClient::start(&config.backend, agent_id, Some(agent.clone()))?,
         janus_topics,
     )
-    .add_queue_counter(agent.get_queue_counter());
+    .add_queue_counter(agent.get_queue_counter())
+    .add_unicast_watchdog(agent.clone());
 
     let context = match redis_pool {
         Some(pool) => context.add_redis_pool(pool),
@@ -102,6 +103,23 @@ pub(crate) async fn run(
 
     // Message handler
     let message_handler = Arc::new(MessageHandler::new(agent.clone(), context));
+
+    // Janus keepalive
+    if let Some(keepalive_config) = config.backend.keepalive.clone() {
+        start_janus_keepalive(keepalive_config, message_handler.clone());
+    }
+
+    // Vacuum scheduler
+    if let Some(vacuum_config) = config.vacuum.clone() {
+        start_vacuum(vacuum_config, message_handler.clone());
+    }
+
+    // Webhook delivery
+    if let Some(webhook_config) = config.webhook.clone() {
+        start_webhook_delivery(webhook_config, message_handler.clone());
+    }
+
+    // Metrics
     StatsRoute::start(config, message_handler.clone());
 
     // Message loop
@@ -145,6 +163,45 @@ pub(crate) async fn run(
     Ok(())
 }
 
+fn start_janus_keepalive(
+    config: KeepaliveConfig,
+    message_handler: Arc<MessageHandler<AppContext>>,
+) {
+    let period = Duration::from_secs(config.period);
+
+    thread::Builder::new()
+        .name("conference-janus-keepalive".to_owned())
+        .spawn(move || loop {
+            thread::sleep(period);
+            task::block_on(message_handler.handle_janus_keepalive(&config));
+        })
+        .expect("Failed to start janus keepalive loop");
+}
+
+fn start_vacuum(config: VacuumConfig, message_handler: Arc<MessageHandler<AppContext>>) {
+    let interval = Duration::from_secs(config.interval);
+
+    thread::Builder::new()
+        .name("conference-vacuum".to_owned())
+        .spawn(move || loop {
+            thread::sleep(interval);
+            task::block_on(message_handler.handle_vacuum());
+        })
+        .expect("Failed to start vacuum loop");
+}
+
+fn start_webhook_delivery(config: WebhookConfig, message_handler: Arc<MessageHandler<AppContext>>) {
+    let interval = Duration::from_secs(config.interval);
+
+    thread::Builder::new()
+        .name("conference-webhook-delivery".to_owned())
+        .spawn(move || loop {
+            thread::sleep(interval);
+            message_handler.handle_webhook_delivery();
+        })
+        .expect("Failed to start webhook delivery loop");
+}
+
 fn subscribe(agent: &mut Agent, agent_id: &AgentId, config: &Config) -> Result<JanusTopics> {
     let group = SharedGroup::new("loadbalancer", agent_id.as_account_id().clone());
 
@@ -242,3 +299,6 @@ pub(crate) mod error;
 pub(crate) mod handle_id;
 pub(crate) mod message_handler;
 pub(crate) mod metrics;
+pub(crate) mod rate_limiter;
+pub(crate) mod unicast_watchdog;
+pub(crate) mod webhook;
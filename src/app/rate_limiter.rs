@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::{Context as AnyhowContext, Result};
+use chrono::{DateTime, Utc};
+use r2d2_redis::redis;
+use svc_authz::cache::ConnectionPool as RedisConnectionPool;
+
+use crate::config::TokenBucketConfig;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Full buckets are indistinguishable from missing ones so they get evicted
+// when there are too many of them.
+const MAX_LOCAL_BUCKETS: usize = 10_000;
+const REDIS_KEY_PREFIX: &str = "conference.rate_limit";
+
+// Refills the bucket stored in a hash and takes a token atomically on the Redis side.
+// KEYS[1] – bucket key, ARGV – capacity, refill rate per second, now in ms, ttl in ms.
+const REDIS_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local refill_rate = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated_at')
+local tokens = tonumber(bucket[1]) or capacity
+local updated_at = tonumber(bucket[2]) or now
+local elapsed = math.max(0, now - updated_at)
+tokens = math.min(capacity, tokens + elapsed * refill_rate / 1000)
+local allowed = 0
+
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+end
+
+redis.call('HMSET', KEYS[1], 'tokens', tostring(tokens), 'updated_at', now)
+redis.call('PEXPIRE', KEYS[1], ARGV[4])
+return allowed
+"#;
+
+lazy_static! {
+    static ref TAKE_TOKEN_SCRIPT: redis::Script = redis::Script::new(REDIS_SCRIPT);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+struct TokenBucket {
+    config: TokenBucketConfig,
+    tokens: f64,
+    updated_at: DateTime<Utc>,
+}
+
+impl TokenBucket {
+    fn new(config: &TokenBucketConfig, now: DateTime<Utc>) -> Self {
+        Self {
+            config: config.to_owned(),
+            tokens: config.capacity as f64,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: DateTime<Utc>) {
+        let elapsed = (now - self.updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+        let tokens = self.tokens + elapsed * self.config.refill_rate;
+        self.tokens = tokens.min(self.config.capacity as f64);
+        self.updated_at = now;
+    }
+
+    fn take(&mut self, now: DateTime<Utc>) -> bool {
+        self.refill(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= self.config.capacity as f64
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Token bucket rate limiter for agents' messages.
+///
+/// Buckets are held in Redis when the pool is available so the limits are shared between
+/// the service instances. Otherwise or when Redis fails they're held in-process.
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter {
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes a token from the bucket with the `key` and returns whether it was available.
+    pub(crate) fn check(
+        &self,
+        redis_pool: Option<&RedisConnectionPool>,
+        key: &str,
+        config: &TokenBucketConfig,
+    ) -> bool {
+        let now = Utc::now();
+
+        if let Some(pool) = redis_pool {
+            match take_redis(pool, key, config, now) {
+                Ok(allowed) => return allowed,
+                Err(err) => error!(
+                    crate::LOG,
+                    "Failed to check rate limit in Redis, falling back to in-process bucket: {:?}",
+                    err
+                ),
+            }
+        }
+
+        self.take_local(key, config, now)
+    }
+
+    fn take_local(&self, key: &str, config: &TokenBucketConfig, now: DateTime<Utc>) -> bool {
+        let mut buckets = match self.buckets.lock() {
+            Ok(buckets) => buckets,
+            Err(err) => {
+                error!(crate::LOG, "Failed to lock rate limiter buckets: {}", err);
+                return true;
+            }
+        };
+
+        if buckets.len() >= MAX_LOCAL_BUCKETS {
+            buckets.retain(|_, bucket| {
+                bucket.refill(now);
+                !bucket.is_full()
+            });
+        }
+
+        buckets
+            .entry(key.to_owned())
+            .or_insert_with(|| TokenBucket::new(config, now))
+            .take(now)
+    }
+}
+
+fn take_redis(
+    pool: &RedisConnectionPool,
+    key: &str,
+    config: &TokenBucketConfig,
+    now: DateTime<Utc>,
+) -> Result<bool> {
+    let mut conn = pool.get().context("Failed to acquire Redis connection")?;
+
+    // Keep the bucket until it gets full again, after that it's the same as a missing one.
+    let ttl = (config.capacity as f64 / config.refill_rate * 1000.0).ceil();
+    let ttl = ttl.max(1000.0).min(86_400_000.0) as u64;
+
+    let allowed: i32 = TAKE_TOKEN_SCRIPT
+        .key(format!("{}.{}", REDIS_KEY_PREFIX, key))
+        .arg(config.capacity)
+        .arg(config.refill_rate)
+        .arg(now.timestamp_millis())
+        .arg(ttl)
+        .invoke(&mut *conn)
+        .context("Failed to invoke rate limit script")?;
+
+    Ok(allowed == 1)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn take_tokens() {
+        let config = TokenBucketConfig {
+            capacity: 2,
+            refill_rate: 1.0,
+        };
+
+        let now = Utc::now();
+        let mut bucket = TokenBucket::new(&config, now);
+
+        // Burst up to capacity.
+        assert!(bucket.take(now));
+        assert!(bucket.take(now));
+        assert!(!bucket.take(now));
+
+        // One token gets refilled in a second.
+        let now = now + Duration::seconds(1);
+        assert!(bucket.take(now));
+        assert!(!bucket.take(now));
+
+        // Refill doesn't exceed capacity.
+        let now = now + Duration::seconds(60);
+        bucket.refill(now);
+        assert!(bucket.is_full());
+    }
+
+    #[test]
+    fn separate_local_buckets() {
+        let config = TokenBucketConfig {
+            capacity: 1,
+            refill_rate: 0.001,
+        };
+
+        let limiter = RateLimiter::new();
+        assert!(limiter.check(None, "first", &config));
+        assert!(!limiter.check(None, "first", &config));
+        assert!(limiter.check(None, "second", &config));
+    }
+}
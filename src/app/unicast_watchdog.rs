@@ -0,0 +1,241 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration as StdDuration;
+
+use anyhow::Context as AnyhowContext;
+use chrono::{DateTime, Duration, Utc};
+use diesel::pg::PgConnection;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use svc_agent::mqtt::{
+    Agent, IncomingRequestProperties, IntoPublishableMessage, OutgoingResponse,
+    ShortTermTimingProperties,
+};
+
+use crate::app::error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind};
+use crate::app::message_handler::publish_message;
+use crate::app::metrics::DynamicStatsCollector;
+use crate::app::API_VERSION;
+use crate::config::MessageConfig;
+use crate::db::{self, ConnectionPool as Db};
+
+////////////////////////////////////////////////////////////////////////////////
+
+const CHECK_PERIOD: StdDuration = StdDuration::from_secs(1);
+
+/// Tracks `message.unicast` requests waiting for a callback and replies with
+/// a timeout error to the callers whose calls haven't been answered in time.
+///
+/// The calls are stored in the DB since the callback may land on a replica other than
+/// the one that has sent the request. Finishing a call and timing it out both delete
+/// its row so only one of them takes effect.
+pub(crate) struct UnicastWatchdog {
+    db: Db,
+    timeout: Duration,
+    halt_tx: Option<crossbeam_channel::Sender<()>>,
+}
+
+impl UnicastWatchdog {
+    pub(crate) fn new(config: &MessageConfig, db: Db) -> Self {
+        Self {
+            db,
+            timeout: Duration::seconds(config.unicast_timeout as i64),
+            halt_tx: None,
+        }
+    }
+
+    /// Starts a thread replying to the callers of timed out calls.
+    pub(crate) fn start(
+        mut self,
+        agent: Agent,
+        dynamic_stats: Option<Arc<DynamicStatsCollector>>,
+    ) -> Self {
+        let (halt_tx, halt_rx) = crossbeam_channel::bounded::<()>(1);
+        let db = self.db.clone();
+
+        thread::spawn(move || {
+            let mut agent = agent;
+
+            while let Err(crossbeam_channel::RecvTimeoutError::Timeout) =
+                halt_rx.recv_timeout(CHECK_PERIOD)
+            {
+                let calls = match take_expired(&db, Utc::now()) {
+                    Ok(calls) => calls,
+                    Err(err) => {
+                        error!(crate::LOG, "Failed to check unicast calls: {}", err);
+                        continue;
+                    }
+                };
+
+                for call in calls {
+                    warn!(
+                        crate::LOG,
+                        "Unicast call timed out at {} ({}): {}",
+                        call.deadline(),
+                        call.correlation_data(),
+                        call.reqp()
+                    );
+
+                    if let Some(ref stats) = dynamic_stats {
+                        stats.collect("message_unicast_timeout", 1);
+                    }
+
+                    // Let the caller know that the callee hasn't answered
+                    // instead of leaving it waiting for a response forever.
+                    match timeout_response(&call) {
+                        Ok(resp) => publish_message(&mut agent, resp).unwrap_or_else(|err| {
+                            error!(
+                                crate::LOG,
+                                "Failed to publish unicast call timeout response: {}", err
+                            );
+                        }),
+                        Err(err) => error!(
+                            crate::LOG,
+                            "Failed to build unicast call timeout response: {}", err
+                        ),
+                    }
+                }
+            }
+        });
+
+        self.halt_tx = Some(halt_tx);
+        self
+    }
+
+    pub(crate) fn register(
+        &self,
+        corr_data: &str,
+        reqp: &IncomingRequestProperties,
+        start_timestamp: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let reqp = serde_json::to_value(reqp)
+            .context("Failed to serialize request properties")
+            .error(AppErrorKind::MessageBuildingFailed)?;
+
+        let conn = self.get_conn()?;
+
+        db::unicast_call::InsertQuery::new(
+            corr_data,
+            reqp,
+            start_timestamp,
+            start_timestamp + self.timeout,
+        )
+        .execute(&conn)?;
+
+        Ok(())
+    }
+
+    /// Removes the call and returns `false` if it's unknown so that the callback gets dropped.
+    /// This is the case when the caller has already got a timeout response or another
+    /// callback to the same call has already been relayed.
+    pub(crate) fn finish(&self, corr_data: &str) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let maybe_call = db::unicast_call::DeleteQuery::new(corr_data).execute(&conn)?;
+        Ok(maybe_call.is_some())
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, AppError> {
+        self.db
+            .get()
+            .map_err(|err| anyhow::Error::from(err).context("Failed to acquire DB connection"))
+            .error(AppErrorKind::DbConnAcquisitionFailed)
+    }
+}
+
+impl Drop for UnicastWatchdog {
+    fn drop(&mut self) {
+        if let Some(ref halt_tx) = self.halt_tx {
+            halt_tx.send(()).unwrap_or_else(|err| {
+                error!(crate::LOG, "Failed to stop unicast watchdog: {}", err);
+            });
+        }
+    }
+}
+
+fn take_expired(db: &Db, now: DateTime<Utc>) -> anyhow::Result<Vec<db::unicast_call::Object>> {
+    let conn = db.get().context("Failed to acquire DB connection")?;
+    let calls = db::unicast_call::delete_expired(now, &conn)?;
+    Ok(calls)
+}
+
+fn timeout_response(
+    call: &db::unicast_call::Object,
+) -> anyhow::Result<Box<dyn IntoPublishableMessage + Send>> {
+    let reqp = serde_json::from_value::<IncomingRequestProperties>(call.reqp().to_owned())
+        .context("Failed to parse request properties")?;
+
+    let err = anyhow!("The callee hasn't answered in time");
+    let app_error = AppError::new(AppErrorKind::UnicastTimedOut, err);
+    let svc_error = app_error.to_svc_error();
+    let timing = ShortTermTimingProperties::until_now(call.started_at());
+    let respp = reqp.to_response(svc_error.status_code(), timing);
+    let resp = OutgoingResponse::unicast(svc_error, respp, &reqp, API_VERSION);
+    Ok(Box::new(resp) as Box<dyn IntoPublishableMessage + Send>)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::prelude::*;
+
+    use super::*;
+
+    fn build_watchdog(db: &TestDb) -> UnicastWatchdog {
+        UnicastWatchdog::new(&MessageConfig::default(), db.connection_pool().to_owned())
+    }
+
+    #[test]
+    fn finish_pending_call() {
+        let db = TestDb::new();
+        let watchdog = build_watchdog(&db);
+        let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+        let reqp = build_reqp(agent.agent_id(), "message.unicast");
+
+        watchdog
+            .register("corr", &reqp, Utc::now())
+            .expect("Failed to register call");
+
+        assert!(watchdog.finish("corr").expect("Failed to finish call"));
+
+        // The second callback to the same call must be dropped.
+        assert!(!watchdog.finish("corr").expect("Failed to finish call"));
+    }
+
+    #[test]
+    fn finish_unknown_call() {
+        let db = TestDb::new();
+        let watchdog = build_watchdog(&db);
+        assert!(!watchdog.finish("unknown").expect("Failed to finish call"));
+    }
+
+    #[test]
+    fn expire_pending_call() {
+        let db = TestDb::new();
+        let config = MessageConfig::default();
+        let watchdog = build_watchdog(&db);
+        let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+        let reqp = build_reqp(agent.agent_id(), "message.unicast");
+        let start_timestamp = Utc::now() - Duration::seconds(config.unicast_timeout as i64 + 1);
+
+        watchdog
+            .register("expired", &reqp, start_timestamp)
+            .expect("Failed to register call");
+
+        watchdog
+            .register("pending", &reqp, Utc::now())
+            .expect("Failed to register call");
+
+        let expired = take_expired(db.connection_pool(), Utc::now()).expect("Failed to expire");
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].correlation_data(), "expired");
+        timeout_response(&expired[0]).expect("Failed to build timeout response");
+
+        // Timed out calls are taken only once.
+        let expired = take_expired(db.connection_pool(), Utc::now()).expect("Failed to expire");
+        assert!(expired.is_empty());
+
+        // The caller has got a timeout response so the late callback must be dropped.
+        assert!(!watchdog.finish("expired").expect("Failed to finish call"));
+        assert!(watchdog.finish("pending").expect("Failed to finish call"));
+    }
+}
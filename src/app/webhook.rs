@@ -0,0 +1,428 @@
+use std::result::Result as StdResult;
+use std::time::Duration as StdDuration;
+
+use anyhow::{Context as AnyhowContext, Result};
+use chrono::{Duration, Utc};
+use diesel::{pg::PgConnection, Connection};
+use isahc::prelude::*;
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+use serde::Serialize;
+
+use crate::app::context::GlobalContext;
+use crate::app::error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind};
+use crate::config::WebhookAudienceConfig;
+use crate::db;
+use crate::db::webhook::Object as Webhook;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Webhooks of a batch are leased for the total of their timeouts so keep the batch small.
+const BATCH_SIZE: i64 = 20;
+// A webhook gets dropped after this number of unsuccessful deliveries.
+const MAX_ATTEMPTS: i32 = 10;
+// Seconds to wait before the first retry, doubled on each next one.
+const RETRY_BASE_DELAY: i64 = 10;
+const RETRY_MAX_DELAY: i64 = 3600;
+
+const EVENT_HEADER: &str = "X-Conference-Event";
+const ID_HEADER: &str = "X-Conference-Webhook-Id";
+const SIGNATURE_HEADER: &str = "X-Conference-Signature";
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Puts the event into the webhook outbox when there's a webhook configured for the audience.
+///
+/// Pass the same connection as for the change the event is about so they get committed together.
+pub(crate) fn enqueue<C, P>(
+    context: &C,
+    audience: &str,
+    label: &str,
+    payload: &P,
+    conn: &PgConnection,
+) -> StdResult<(), AppError>
+where
+    C: GlobalContext,
+    P: Serialize,
+{
+    let is_configured = context
+        .config()
+        .webhook
+        .as_ref()
+        .map(|config| config.audiences.contains_key(audience))
+        .unwrap_or(false);
+
+    if !is_configured {
+        return Ok(());
+    }
+
+    let payload = serde_json::to_value(payload)
+        .map_err(|err| anyhow!("Failed to serialize webhook payload: {}", err))
+        .error(AppErrorKind::MessageBuildingFailed)?;
+
+    db::webhook::InsertQuery::new(audience, label, payload).execute(conn)?;
+    Ok(())
+}
+
+/// Posts the webhooks due for delivery and reschedules the failed ones with a backoff.
+/// Returns the number of delivered webhooks.
+///
+/// The batch gets leased first so that other replicas skip it while it's being posted
+/// without keeping the rows locked and the connection taken during the requests.
+pub(crate) fn deliver<C: GlobalContext>(context: &C) -> StdResult<usize, AppError> {
+    let config = match context.config().webhook {
+        Some(ref config) => config,
+        None => return Ok(0),
+    };
+
+    let webhooks = {
+        let conn = context.get_conn()?;
+
+        conn.transaction::<_, AppError, _>(|| {
+            let webhooks = db::webhook::DueListQuery::new(BATCH_SIZE).execute(&conn)?;
+
+            // Webhooks are being posted one by one so the last one may wait
+            // for all the previous ones to time out.
+            let lease_seconds = webhooks
+                .iter()
+                .filter_map(|webhook| config.audiences.get(webhook.audience()))
+                .map(|audience_config| audience_config.timeout as i64)
+                .sum::<i64>();
+
+            let leased_until = Utc::now() + Duration::seconds(lease_seconds);
+
+            for webhook in webhooks.iter() {
+                db::webhook::UpdateQuery::new(webhook.id())
+                    .next_attempt_at(leased_until)
+                    .execute(&conn)?;
+            }
+
+            Ok(webhooks)
+        })?
+    };
+
+    let mut delivered = 0;
+
+    for webhook in webhooks {
+        let result = match config.audiences.get(webhook.audience()) {
+            Some(audience_config) => post(audience_config, &webhook),
+            None => Err(anyhow!("Missing webhook configuration for the audience")),
+        };
+
+        let is_delivered = result.is_ok();
+        let conn = context.get_conn()?;
+        conn.transaction::<_, AppError, _>(|| record_result(context, &webhook, result, &conn))?;
+
+        if is_delivered {
+            delivered += 1;
+        }
+    }
+
+    Ok(delivered)
+}
+
+fn record_result<C: GlobalContext>(
+    context: &C,
+    webhook: &Webhook,
+    result: Result<()>,
+    conn: &PgConnection,
+) -> StdResult<(), AppError> {
+    match result {
+        Ok(()) => {
+            db::webhook::DeleteQuery::new(webhook.id()).execute(conn)?;
+            collect_stats(context, "webhook_delivered");
+        }
+        Err(err) if webhook.attempts() + 1 >= MAX_ATTEMPTS => {
+            error!(
+                crate::LOG,
+                "Giving up delivering webhook after {} attempts, id = '{}', label = '{}', enqueued at {}: {:?}",
+                webhook.attempts() + 1,
+                webhook.id(),
+                webhook.label(),
+                webhook.created_at(),
+                err,
+            );
+
+            db::webhook::DeleteQuery::new(webhook.id()).execute(conn)?;
+            collect_stats(context, "webhook_failed");
+        }
+        Err(err) => {
+            let attempts = webhook.attempts() + 1;
+
+            warn!(
+                crate::LOG,
+                "Failed to deliver webhook, id = '{}', label = '{}', attempt = {}: {:?}",
+                webhook.id(),
+                webhook.label(),
+                attempts,
+                err,
+            );
+
+            db::webhook::UpdateQuery::new(webhook.id())
+                .attempts(attempts)
+                .next_attempt_at(Utc::now() + retry_delay(attempts))
+                .execute(conn)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn post(config: &WebhookAudienceConfig, webhook: &Webhook) -> Result<()> {
+    let body = serde_json::to_vec(webhook.payload()).context("Failed to serialize payload")?;
+    let signature = sign(&config.secret, &body)?;
+
+    let response = Request::post(&config.url)
+        .header("Content-Type", "application/json")
+        .header(EVENT_HEADER, webhook.label())
+        .header(ID_HEADER, webhook.id().to_string())
+        .header(SIGNATURE_HEADER, signature)
+        .timeout(StdDuration::from_secs(config.timeout))
+        .body(body)
+        .context("Failed to build request")?
+        .send()
+        .context("Failed to send request")?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Endpoint responded with {}", response.status()));
+    }
+
+    Ok(())
+}
+
+/// Returns the `X-Conference-Signature` header value so that the receiver could verify
+/// the body has been sent by the service.
+fn sign(secret: &str, body: &[u8]) -> Result<String> {
+    let pkey = PKey::hmac(secret.as_bytes()).context("Invalid webhook secret")?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
+    signer.update(body)?;
+
+    let hex = signer
+        .sign_to_vec()?
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    Ok(format!("sha256={}", hex))
+}
+
+fn retry_delay(attempts: i32) -> Duration {
+    let exponent = (attempts.max(1) - 1).min(16) as u32;
+    let seconds = RETRY_BASE_DELAY * 2_i64.pow(exponent);
+    Duration::seconds(seconds.min(RETRY_MAX_DELAY))
+}
+
+fn collect_stats<C: GlobalContext>(context: &C, key: &str) {
+    if let Some(stats) = context.dynamic_stats() {
+        stats.collect(key, 1);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc;
+    use std::thread;
+
+    use serde_json::json;
+
+    use crate::config::WebhookConfig;
+    use crate::schema::webhook;
+    use crate::test_helpers::prelude::*;
+
+    use super::*;
+
+    const SECRET: &str = "webhook_secret";
+
+    #[derive(Debug)]
+    struct CapturedRequest {
+        headers: HashMap<String, String>,
+        body: Vec<u8>,
+    }
+
+    // Accepts connections on a random local port replying with the `status` to each request.
+    fn start_stand_in(status: &'static str) -> (String, mpsc::Receiver<CapturedRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind stand-in");
+        let url = format!("http://{}/webhook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => break,
+                };
+
+                let request = read_request(&mut stream);
+
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+
+                stream.write_all(response.as_bytes()).ok();
+
+                if tx.send(request).is_err() {
+                    break;
+                }
+            }
+        });
+
+        (url, rx)
+    }
+
+    fn read_request(stream: &mut TcpStream) -> CapturedRequest {
+        let mut reader = BufReader::new(stream);
+        let mut headers = HashMap::new();
+        let mut line = String::new();
+
+        // Skip the request line.
+        reader
+            .read_line(&mut line)
+            .expect("Failed to read request line");
+
+        loop {
+            line.clear();
+            reader.read_line(&mut line).expect("Failed to read header");
+
+            let mut parts = line.trim_end().splitn(2, ':');
+
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) => {
+                    headers.insert(name.to_lowercase(), value.trim().to_owned());
+                }
+                _ => break,
+            }
+        }
+
+        let length = headers
+            .get("content-length")
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).expect("Failed to read body");
+        CapturedRequest { headers, body }
+    }
+
+    fn build_context(db: TestDb, url: &str) -> TestContext {
+        let config = json!({
+            "interval": 1,
+            "audiences": {
+                USR_AUDIENCE: {
+                    "url": url,
+                    "secret": SECRET,
+                }
+            }
+        });
+
+        let mut context = TestContext::new(db, TestAuthz::new());
+
+        context.config_mut().webhook =
+            Some(serde_json::from_value::<WebhookConfig>(config).expect("Failed to parse config"));
+
+        context
+    }
+
+    fn load_webhooks(context: &TestContext) -> Vec<Webhook> {
+        use diesel::prelude::*;
+
+        let conn = context.get_conn().expect("Failed to get DB connection");
+
+        webhook::table
+            .get_results::<Webhook>(&conn)
+            .expect("Failed to load webhooks")
+    }
+
+    #[test]
+    fn sign_body() {
+        let signature = sign("key", b"The quick brown fox jumps over the lazy dog")
+            .expect("Failed to sign body");
+
+        assert_eq!(
+            signature,
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn deliver_webhook() {
+        let (url, rx) = start_stand_in("200 OK");
+        let context = build_context(TestDb::new(), &url);
+        let payload = json!({ "id": "some-room" });
+
+        {
+            let conn = context.get_conn().expect("Failed to get DB connection");
+            enqueue(&context, USR_AUDIENCE, "room.create", &payload, &conn)
+                .expect("Failed to enqueue webhook");
+        }
+
+        let webhook_id = load_webhooks(&context)[0].id();
+        assert_eq!(deliver(&context).expect("Failed to deliver webhooks"), 1);
+
+        // Assert the request received by the stand-in.
+        let request = rx.recv().expect("Failed to receive request");
+        let body = serde_json::to_vec(&payload).unwrap();
+        assert_eq!(request.body, body);
+        assert_eq!(request.headers["x-conference-event"], "room.create");
+        assert_eq!(
+            request.headers["x-conference-webhook-id"],
+            webhook_id.to_string()
+        );
+        assert_eq!(
+            request.headers["x-conference-signature"],
+            sign(SECRET, &body).unwrap()
+        );
+
+        // Assert the webhook is removed from the outbox.
+        assert!(load_webhooks(&context).is_empty());
+    }
+
+    #[test]
+    fn retry_failed_webhook() {
+        let (url, rx) = start_stand_in("500 Internal Server Error");
+        let context = build_context(TestDb::new(), &url);
+
+        {
+            let conn = context.get_conn().expect("Failed to get DB connection");
+            enqueue(&context, USR_AUDIENCE, "room.close", &json!({}), &conn)
+                .expect("Failed to enqueue webhook");
+        }
+
+        assert_eq!(deliver(&context).expect("Failed to deliver webhooks"), 0);
+        rx.recv().expect("Failed to receive request");
+
+        // Assert the webhook stays in the outbox until the backoff expires.
+        let webhooks = load_webhooks(&context);
+        assert_eq!(webhooks.len(), 1);
+        assert_eq!(webhooks[0].attempts(), 1);
+        assert!(webhooks[0].next_attempt_at() > Utc::now());
+
+        assert_eq!(deliver(&context).expect("Failed to deliver webhooks"), 0);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn skip_unconfigured_audience() {
+        let (url, _rx) = start_stand_in("200 OK");
+        let context = build_context(TestDb::new(), &url);
+
+        {
+            let conn = context.get_conn().expect("Failed to get DB connection");
+            enqueue(&context, SVC_AUDIENCE, "room.create", &json!({}), &conn)
+                .expect("Failed to enqueue webhook");
+        }
+
+        assert!(load_webhooks(&context).is_empty());
+    }
+
+    #[test]
+    fn backoff() {
+        assert_eq!(retry_delay(1), Duration::seconds(10));
+        assert_eq!(retry_delay(2), Duration::seconds(20));
+        assert_eq!(retry_delay(100), Duration::seconds(3600));
+    }
+}
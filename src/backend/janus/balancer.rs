@@ -0,0 +1,418 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use uuid::Uuid;
+
+use crate::config::BalancerStrategy;
+use crate::db::janus_backend::{self, Object as JanusBackend};
+use crate::db::room::Object as Room;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Chooses a backend to host a new recording of the room.
+// Draining backends are never chosen.
+pub(crate) struct Balancer {
+    strategy: BalancerStrategy,
+    counter: AtomicUsize,
+}
+
+impl Balancer {
+    pub(crate) fn new(strategy: BalancerStrategy) -> Self {
+        Self {
+            strategy,
+            counter: AtomicUsize::new(0),
+        }
+    }
+
+    // Backends of the room's preferred group are tried first. If there are none available
+    // then fall back to backends of any group and send a warning to Sentry.
+    pub(crate) fn select(
+        &self,
+        room: &Room,
+        rtc_id: Uuid,
+        conn: &PgConnection,
+    ) -> Result<Option<JanusBackend>, Error> {
+        let group = match room.backend_group() {
+            Some(group) => group,
+            None => return self.select_in_group(room, rtc_id, None, conn),
+        };
+
+        if let Some(backend) = self.select_in_group(room, rtc_id, Some(group), conn)? {
+            return Ok(Some(backend));
+        }
+
+        let maybe_backend = self.select_in_group(room, rtc_id, None, conn)?;
+
+        if let Some(ref backend) = maybe_backend {
+            notify_fallback(
+                "No available backends in the preferred group; falling back to other groups",
+                room,
+                rtc_id,
+                backend,
+            );
+        }
+
+        Ok(maybe_backend)
+    }
+
+    fn select_in_group(
+        &self,
+        room: &Room,
+        rtc_id: Uuid,
+        group: Option<&str>,
+        conn: &PgConnection,
+    ) -> Result<Option<JanusBackend>, Error> {
+        match self.strategy {
+            BalancerStrategy::BinPacking => bin_packing(room, rtc_id, group, conn),
+            BalancerStrategy::LeastLoaded => janus_backend::least_loaded(room.id(), group, conn),
+            BalancerStrategy::RoundRobin => self.round_robin(group, conn),
+            BalancerStrategy::Weighted => janus_backend::weighted(room.id(), group, conn),
+        }
+    }
+
+    fn round_robin(
+        &self,
+        group: Option<&str>,
+        conn: &PgConnection,
+    ) -> Result<Option<JanusBackend>, Error> {
+        let mut query = janus_backend::ListQuery::new().draining(false);
+
+        if let Some(group) = group {
+            query = query.group(group);
+        }
+
+        let backends = query.execute(conn)?;
+
+        if backends.is_empty() {
+            return Ok(None);
+        }
+
+        let idx = self.counter.fetch_add(1, Ordering::Relaxed) % backends.len();
+        Ok(backends.into_iter().nth(idx))
+    }
+}
+
+// Select the most loaded backend that is capable to host the room's reservation.
+// If there's no capable backend then select the least loaded and send a warning to Sentry.
+fn bin_packing(
+    room: &Room,
+    rtc_id: Uuid,
+    group: Option<&str>,
+    conn: &PgConnection,
+) -> Result<Option<JanusBackend>, Error> {
+    if let Some(backend) = janus_backend::most_loaded(room.id(), group, conn)? {
+        return Ok(Some(backend));
+    }
+
+    let maybe_backend = janus_backend::least_loaded(room.id(), group, conn)?;
+
+    if let Some(ref backend) = maybe_backend {
+        notify_fallback(
+            "No capable backends to host the reserve; falling back to the least loaded backend",
+            room,
+            rtc_id,
+            backend,
+        );
+    }
+
+    Ok(maybe_backend)
+}
+
+fn notify_fallback(message: &str, room: &Room, rtc_id: Uuid, backend: &JanusBackend) {
+    use sentry::protocol::{value::Value, Event, Level};
+
+    let room_id = room.id().to_string();
+    let rtc_id = rtc_id.to_string();
+    let backend_id = backend.id().to_string();
+
+    warn!(
+        crate::LOG,
+        "{}: room_id = {}, rtc_id = {}, backend_id = {}", message, room_id, rtc_id, backend_id
+    );
+
+    let mut extra = std::collections::BTreeMap::new();
+    extra.insert(String::from("room_id"), Value::from(room_id));
+    extra.insert(String::from("rtc_id"), Value::from(rtc_id));
+    extra.insert(String::from("backend_id"), Value::from(backend_id));
+
+    if let Some(reserve) = room.reserve() {
+        extra.insert(String::from("reserve"), Value::from(reserve));
+    }
+
+    if let Some(group) = room.backend_group() {
+        extra.insert(String::from("backend_group"), Value::from(group));
+    }
+
+    sentry::capture_event(Event {
+        message: Some(message.to_owned()),
+        level: Level::Warning,
+        extra,
+        ..Default::default()
+    });
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    use std::ops::Bound;
+
+    use chrono::{Duration, Utc};
+    use diesel::pg::PgConnection;
+    use svc_agent::AgentId;
+
+    use crate::config::BalancerStrategy;
+    use crate::db::janus_backend::Object as JanusBackend;
+    use crate::db::room::{Object as Room, RoomBackend};
+    use crate::test_helpers::prelude::*;
+
+    use super::Balancer;
+
+    fn insert_backend(conn: &PgConnection, label: &str, capacity: i32) -> JanusBackend {
+        let agent = TestAgent::new("alpha", label, SVC_AUDIENCE);
+
+        factory::JanusBackend::new(agent.agent_id().to_owned(), 1, 1)
+            .capacity(capacity)
+            .insert(conn)
+    }
+
+    fn insert_room(conn: &PgConnection, reserve: Option<i32>) -> Room {
+        let now = Utc::now();
+
+        let mut room = factory::Room::new()
+            .audience(USR_AUDIENCE)
+            .time((
+                Bound::Included(now),
+                Bound::Excluded(now + Duration::hours(1)),
+            ))
+            .backend(RoomBackend::Janus);
+
+        if let Some(reserve) = reserve {
+            room = room.reserve(reserve);
+        }
+
+        room.insert(conn)
+    }
+
+    // Puts a recording on the backend with the given number of connected agents.
+    fn load_backend(conn: &PgConnection, backend: &JanusBackend, agents: usize) {
+        let room = insert_room(conn, None);
+        let rtc = factory::Rtc::new(room.id()).insert(conn);
+
+        factory::Recording::new()
+            .rtc(&rtc)
+            .backend(backend)
+            .insert(conn);
+
+        for idx in 0..agents {
+            let agent = TestAgent::new("web", &format!("user{}", idx), USR_AUDIENCE);
+            shared_helpers::insert_agent(conn, agent.agent_id(), room.id());
+        }
+    }
+
+    fn select(balancer: &Balancer, room: &Room, conn: &PgConnection) -> Option<AgentId> {
+        let rtc = factory::Rtc::new(room.id()).insert(conn);
+
+        balancer
+            .select(room, rtc.id(), conn)
+            .expect("Failed to select backend")
+            .map(|backend| backend.id().to_owned())
+    }
+
+    #[test]
+    fn bin_packing() {
+        let db = TestDb::new();
+        let conn = db.connection_pool().get().expect("Failed to get db conn");
+
+        let backend1 = insert_backend(&conn, "janus1", 10);
+        let backend2 = insert_backend(&conn, "janus2", 10);
+        load_backend(&conn, &backend1, 3);
+        load_backend(&conn, &backend2, 6);
+
+        let balancer = Balancer::new(BalancerStrategy::BinPacking);
+
+        // The most loaded backend fits the reserve.
+        let room = insert_room(&conn, Some(4));
+        assert_eq!(
+            select(&balancer, &room, &conn).as_ref(),
+            Some(backend2.id())
+        );
+
+        // Only the less loaded backend fits the reserve.
+        let room = insert_room(&conn, Some(5));
+        assert_eq!(
+            select(&balancer, &room, &conn).as_ref(),
+            Some(backend1.id())
+        );
+
+        // No backend fits the reserve so falling back to the least loaded one.
+        let room = insert_room(&conn, Some(100));
+        assert_eq!(
+            select(&balancer, &room, &conn).as_ref(),
+            Some(backend1.id())
+        );
+    }
+
+    #[test]
+    fn least_loaded() {
+        let db = TestDb::new();
+        let conn = db.connection_pool().get().expect("Failed to get db conn");
+
+        let backend1 = insert_backend(&conn, "janus1", 10);
+        let backend2 = insert_backend(&conn, "janus2", 10);
+        load_backend(&conn, &backend1, 6);
+        load_backend(&conn, &backend2, 3);
+
+        let balancer = Balancer::new(BalancerStrategy::LeastLoaded);
+        let room = insert_room(&conn, None);
+        assert_eq!(
+            select(&balancer, &room, &conn).as_ref(),
+            Some(backend2.id())
+        );
+    }
+
+    #[test]
+    fn round_robin() {
+        let db = TestDb::new();
+        let conn = db.connection_pool().get().expect("Failed to get db conn");
+
+        let backend1 = insert_backend(&conn, "janus1", 10);
+        let backend2 = insert_backend(&conn, "janus2", 10);
+        let backend3 = insert_backend(&conn, "janus3", 10);
+        load_backend(&conn, &backend1, 6);
+
+        crate::db::janus_backend::UpdateQuery::new(backend3.id())
+            .draining(true)
+            .execute(&conn)
+            .expect("Failed to drain backend");
+
+        let balancer = Balancer::new(BalancerStrategy::RoundRobin);
+        let room = insert_room(&conn, None);
+
+        let selected = (0..4)
+            .map(|_| select(&balancer, &room, &conn).expect("No backend selected"))
+            .collect::<Vec<AgentId>>();
+
+        // Backends are taken in turn regardless of their load and the draining one is skipped.
+        assert_ne!(selected[0], selected[1]);
+        assert_eq!(selected[0], selected[2]);
+        assert_eq!(selected[1], selected[3]);
+        assert!(selected.iter().all(|id| id != backend3.id()));
+        assert!(selected.contains(backend2.id()));
+    }
+
+    #[test]
+    fn weighted() {
+        let db = TestDb::new();
+        let conn = db.connection_pool().get().expect("Failed to get db conn");
+
+        let backend1 = insert_backend(&conn, "janus1", 10);
+        let backend2 = insert_backend(&conn, "janus2", 100);
+        load_backend(&conn, &backend1, 3);
+        load_backend(&conn, &backend2, 20);
+
+        let balancer = Balancer::new(BalancerStrategy::Weighted);
+        let room = insert_room(&conn, None);
+
+        // The second backend has more agents but it's less loaded relative to its capacity.
+        assert_eq!(
+            select(&balancer, &room, &conn).as_ref(),
+            Some(backend2.id())
+        );
+
+        // The balancer capacity takes precedence over the capacity.
+        let backend3 = factory::JanusBackend::new(
+            TestAgent::new("alpha", "janus3", SVC_AUDIENCE)
+                .agent_id()
+                .to_owned(),
+            1,
+            1,
+        )
+        .capacity(10)
+        .balancer_capacity(1000)
+        .insert(&conn);
+
+        load_backend(&conn, &backend3, 30);
+        assert_eq!(
+            select(&balancer, &room, &conn).as_ref(),
+            Some(backend3.id())
+        );
+    }
+
+    #[test]
+    fn preferred_group() {
+        let db = TestDb::new();
+        let conn = db.connection_pool().get().expect("Failed to get db conn");
+
+        let agent1 = TestAgent::new("alpha", "janus1", SVC_AUDIENCE);
+        let agent2 = TestAgent::new("alpha", "janus2", SVC_AUDIENCE);
+
+        let backend1 = factory::JanusBackend::new(agent1.agent_id().to_owned(), 1, 1)
+            .capacity(10)
+            .group("eu")
+            .insert(&conn);
+
+        let backend2 = factory::JanusBackend::new(agent2.agent_id().to_owned(), 1, 1)
+            .capacity(10)
+            .group("us")
+            .insert(&conn);
+
+        // The backend out of the preferred group is more loaded.
+        load_backend(&conn, &backend2, 3);
+
+        let balancer = Balancer::new(BalancerStrategy::BinPacking);
+        let now = Utc::now();
+
+        let room = factory::Room::new()
+            .audience(USR_AUDIENCE)
+            .time((
+                Bound::Included(now),
+                Bound::Excluded(now + Duration::hours(1)),
+            ))
+            .backend(RoomBackend::Janus)
+            .backend_group("eu")
+            .insert(&conn);
+
+        assert_eq!(
+            select(&balancer, &room, &conn).as_ref(),
+            Some(backend1.id())
+        );
+
+        // Fall back to other groups when the preferred one has no available backends.
+        crate::db::janus_backend::UpdateQuery::new(backend1.id())
+            .draining(true)
+            .execute(&conn)
+            .expect("Failed to drain backend");
+
+        assert_eq!(
+            select(&balancer, &room, &conn).as_ref(),
+            Some(backend2.id())
+        );
+    }
+
+    #[test]
+    fn skip_draining() {
+        let db = TestDb::new();
+        let conn = db.connection_pool().get().expect("Failed to get db conn");
+
+        let backend = insert_backend(&conn, "janus1", 10);
+
+        crate::db::janus_backend::UpdateQuery::new(backend.id())
+            .draining(true)
+            .execute(&conn)
+            .expect("Failed to drain backend");
+
+        let room = insert_room(&conn, None);
+
+        for &strategy in &[
+            BalancerStrategy::BinPacking,
+            BalancerStrategy::LeastLoaded,
+            BalancerStrategy::RoundRobin,
+            BalancerStrategy::Weighted,
+        ] {
+            let balancer = Balancer::new(strategy);
+            assert_eq!(select(&balancer, &room, &conn), None);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration as StdDuration;
 
@@ -6,13 +7,19 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Utc};
 use serde_json::{json, Value as JsonValue};
 use svc_agent::{
-    mqtt::{IncomingResponseProperties, OutgoingRequestProperties, SubscriptionTopic},
+    mqtt::{
+        Agent, IncomingRequestProperties, IncomingResponseProperties, IntoPublishableMessage,
+        OutgoingRequestProperties, OutgoingResponse, ShortTermTimingProperties, SubscriptionTopic,
+    },
     AgentId, Subscription,
 };
 
 use crate::app::error::{Error as AppError, ErrorKind as AppErrorKind};
+use crate::app::message_handler::publish_message;
+use crate::app::API_VERSION;
 use crate::config::BackendConfig;
 
+use super::transactions::Transaction;
 use super::{JANUS_API_VERSION, STREAM_UPLOAD_METHOD};
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -23,66 +30,65 @@ struct RequestInfo {
     to: AgentId,
     start_timestamp: DateTime<Utc>,
     payload: JsonValue,
+    reqp: Option<IncomingRequestProperties>,
 }
 
-enum TransactionWatchdogMessage {
-    Halt,
-    Insert(String, RequestInfo),
-    Remove(String),
+#[derive(Debug, Default)]
+struct Transactions {
+    pending: HashMap<String, RequestInfo>,
+    // Transactions which requesters have already got a timeout response
+    // along with the time until their late responses get dropped.
+    timed_out: HashMap<String, DateTime<Utc>>,
 }
 
 pub(crate) struct Client {
     me: AgentId,
-    transaction_watchdog_tx: crossbeam_channel::Sender<TransactionWatchdogMessage>,
+    transactions: Arc<Mutex<Transactions>>,
+    halt_tx: crossbeam_channel::Sender<()>,
     default_timeout: Duration,
     stream_upload_timeout: Duration,
 }
 
 impl Client {
-    pub(crate) fn start(config: &BackendConfig, me: AgentId) -> Result<Self> {
+    pub(crate) fn start(config: &BackendConfig, me: AgentId, agent: Option<Agent>) -> Result<Self> {
         let period = StdDuration::from_secs(config.transaction_watchdog_check_period);
-        let (tx, rx) = crossbeam_channel::unbounded();
+        let transactions = Arc::new(Mutex::new(Transactions::default()));
+        let (halt_tx, halt_rx) = crossbeam_channel::bounded::<()>(1);
+        let thread_transactions = transactions.clone();
 
         thread::spawn(move || {
-            let mut state: HashMap<String, RequestInfo> = HashMap::new();
-
-            loop {
-                if let Ok(message) = rx.recv_timeout(period) {
-                    match message {
-                        TransactionWatchdogMessage::Halt => break,
-                        TransactionWatchdogMessage::Insert(corr_data, info) => {
-                            state.insert(corr_data, info);
-                        }
-                        TransactionWatchdogMessage::Remove(ref corr_data) => {
-                            state.remove(corr_data);
-                        }
-                    }
-                }
+            let mut agent = agent;
 
-                state = state
-                    .into_iter()
-                    .filter(|(corr_data, info)| {
-                        if info.start_timestamp + info.timeout < Utc::now() {
-                            let err =
-                                anyhow!("Janus request timed out ({}): {:?}", corr_data, info);
+            while let Err(crossbeam_channel::RecvTimeoutError::Timeout) =
+                halt_rx.recv_timeout(period)
+            {
+                for (corr_data, info) in take_expired(&thread_transactions, Utc::now()) {
+                    let err = anyhow!("Janus request timed out ({}): {:?}", corr_data, info);
+                    error!(crate::LOG, "{}", err);
 
-                            error!(crate::LOG, "{}", err);
+                    let app_error = AppError::new(AppErrorKind::BackendRequestTimedOut, err);
+                    app_error.notify_sentry(&crate::LOG);
 
-                            AppError::new(AppErrorKind::BackendRequestTimedOut, err)
-                                .notify_sentry(&crate::LOG);
+                    // Let the client know that its request has failed instead of
+                    // leaving it waiting for a response forever.
+                    if let (Some(agent), Some(reqp)) = (agent.as_mut(), info.reqp.as_ref()) {
+                        let resp = timeout_response(reqp, info.start_timestamp);
 
-                            false
-                        } else {
-                            true
-                        }
-                    })
-                    .collect();
+                        publish_message(agent, resp).unwrap_or_else(|err| {
+                            error!(
+                                crate::LOG,
+                                "Failed to publish janus request timeout response: {}", err
+                            );
+                        });
+                    }
+                }
             }
         });
 
         Ok(Self {
             me,
-            transaction_watchdog_tx: tx,
+            transactions,
+            halt_tx,
             default_timeout: Duration::seconds(config.default_timeout as i64),
             stream_upload_timeout: Duration::seconds(config.stream_upload_timeout as i64),
         })
@@ -93,6 +99,7 @@ impl Client {
         to: &AgentId,
         start_timestamp: DateTime<Utc>,
         reqp: &OutgoingRequestProperties,
+        transaction: &Transaction,
         payload: &P,
         timeout: Duration,
     ) {
@@ -101,32 +108,47 @@ impl Client {
             to: to.to_owned(),
             start_timestamp,
             payload: json!(payload),
+            reqp: transaction.reqp().map(|reqp| reqp.to_owned()),
         };
 
-        self.transaction_watchdog_tx
-            .send(TransactionWatchdogMessage::Insert(
-                reqp.correlation_data().to_owned(),
-                request_info,
-            ))
-            .unwrap_or_else(|err| {
-                error!(
-                    crate::LOG,
-                    "Failed to register janus client transaction: {}", err
-                );
-            });
+        self.insert_transaction(reqp.correlation_data(), request_info);
+    }
+
+    fn insert_transaction(&self, corr_data: &str, request_info: RequestInfo) {
+        match self.transactions.lock() {
+            Ok(mut transactions) => {
+                transactions
+                    .pending
+                    .insert(corr_data.to_owned(), request_info);
+            }
+            Err(err) => error!(
+                crate::LOG,
+                "Failed to register janus client transaction: {}", err
+            ),
+        }
     }
 
-    pub(super) fn finish_transaction(&self, respp: &IncomingResponseProperties) {
-        let corr_data = respp.correlation_data().to_owned();
+    /// Removes the transaction and returns `false` if its requester has already got
+    /// a timeout response so that the late response gets dropped.
+    pub(super) fn finish_transaction(&self, respp: &IncomingResponseProperties) -> bool {
+        self.finish(respp.correlation_data())
+    }
 
-        self.transaction_watchdog_tx
-            .send(TransactionWatchdogMessage::Remove(corr_data))
-            .unwrap_or_else(|err| {
+    fn finish(&self, corr_data: &str) -> bool {
+        match self.transactions.lock() {
+            Ok(mut transactions) => {
+                transactions.pending.remove(corr_data);
+                transactions.timed_out.remove(corr_data).is_none()
+            }
+            Err(err) => {
                 error!(
                     crate::LOG,
                     "Failed to remove janus client transaction: {}", err
                 );
-            });
+
+                true
+            }
+        }
     }
 
     pub(super) fn timeout(&self, method: &str) -> Duration {
@@ -143,15 +165,155 @@ impl Client {
     }
 }
 
+fn take_expired(
+    transactions: &Mutex<Transactions>,
+    now: DateTime<Utc>,
+) -> Vec<(String, RequestInfo)> {
+    let mut transactions = match transactions.lock() {
+        Ok(transactions) => transactions,
+        Err(err) => {
+            error!(
+                crate::LOG,
+                "Failed to check janus client transactions: {}", err
+            );
+            return vec![];
+        }
+    };
+
+    transactions
+        .timed_out
+        .retain(|_, drop_until| *drop_until >= now);
+
+    let expired_keys = transactions
+        .pending
+        .iter()
+        .filter(|(_, info)| info.start_timestamp + info.timeout < now)
+        .map(|(corr_data, _)| corr_data.to_owned())
+        .collect::<Vec<String>>();
+
+    let mut expired = Vec::with_capacity(expired_keys.len());
+
+    for corr_data in expired_keys {
+        if let Some(info) = transactions.pending.remove(&corr_data) {
+            // Only the requester gets notified on timeout so there's no point in dropping
+            // late responses to system transactions like `stream.upload`.
+            if info.reqp.is_some() {
+                let drop_until = now + info.timeout;
+                transactions.timed_out.insert(corr_data.clone(), drop_until);
+            }
+
+            expired.push((corr_data, info));
+        }
+    }
+
+    expired
+}
+
+fn timeout_response(
+    reqp: &IncomingRequestProperties,
+    start_timestamp: DateTime<Utc>,
+) -> Box<dyn IntoPublishableMessage + Send> {
+    let err = anyhow!("Janus hasn't responded in time");
+    let app_error = AppError::new(AppErrorKind::BackendRequestTimedOut, err);
+    let svc_error = app_error.to_svc_error();
+    let timing = ShortTermTimingProperties::until_now(start_timestamp);
+    let respp = reqp.to_response(svc_error.status_code(), timing);
+    let resp = OutgoingResponse::unicast(svc_error, respp, reqp, API_VERSION);
+    Box::new(resp) as Box<dyn IntoPublishableMessage + Send>
+}
+
 impl Drop for Client {
     fn drop(&mut self) {
-        self.transaction_watchdog_tx
-            .send(TransactionWatchdogMessage::Halt)
-            .unwrap_or_else(|err| {
-                error!(
-                    crate::LOG,
-                    "Failed to stop janus client transaction watchdog: {}", err
-                );
-            });
+        self.halt_tx.send(()).unwrap_or_else(|err| {
+            error!(
+                crate::LOG,
+                "Failed to stop janus client transaction watchdog: {}", err
+            );
+        });
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use async_std::stream;
+    use serde_json::Value as JsonValue;
+    use svc_agent::mqtt::ResponseStatus;
+
+    use crate::test_helpers::context::build_config;
+    use crate::test_helpers::prelude::*;
+
+    use super::*;
+
+    fn start_client() -> Client {
+        let config = build_config();
+        let agent_id = AgentId::new(&config.agent_label, config.id.clone());
+        Client::start(&config.backend, agent_id, None).expect("Failed to start janus client")
+    }
+
+    fn request_info(
+        reqp: Option<IncomingRequestProperties>,
+        start_timestamp: DateTime<Utc>,
+    ) -> RequestInfo {
+        let backend = TestAgent::new("alpha", "janus", SVC_AUDIENCE);
+
+        RequestInfo {
+            timeout: Duration::seconds(5),
+            to: backend.agent_id().to_owned(),
+            start_timestamp,
+            payload: json!({}),
+            reqp,
+        }
+    }
+
+    #[test]
+    fn finish_pending_transaction() {
+        let client = start_client();
+        let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+        let reqp = build_reqp(agent.agent_id(), "rtc.connect");
+
+        client.insert_transaction("pending", request_info(Some(reqp), Utc::now()));
+        assert!(client.finish("pending"));
+        assert!(take_expired(&client.transactions, Utc::now()).is_empty());
+    }
+
+    #[test]
+    fn drop_response_to_timed_out_transaction() {
+        let client = start_client();
+        let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+        let reqp = build_reqp(agent.agent_id(), "rtc.connect");
+        let start_timestamp = Utc::now() - Duration::seconds(10);
+
+        client.insert_transaction("expired", request_info(Some(reqp), start_timestamp));
+        client.insert_transaction("system", request_info(None, start_timestamp));
+        client.insert_transaction("pending", request_info(None, Utc::now()));
+
+        let mut expired = take_expired(&client.transactions, Utc::now())
+            .into_iter()
+            .map(|(corr_data, _)| corr_data)
+            .collect::<Vec<String>>();
+
+        expired.sort();
+        assert_eq!(expired, vec!["expired", "system"]);
+
+        // The requester has got a timeout response so the late one must be dropped.
+        assert!(!client.finish("expired"));
+        assert!(client.finish("system"));
+        assert!(client.finish("pending"));
+    }
+
+    #[test]
+    fn respond_to_timed_out_request() {
+        async_std::task::block_on(async {
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let reqp = build_reqp(agent.agent_id(), "rtc.connect");
+            let resp = timeout_response(&reqp, Utc::now());
+
+            let messages = parse_messages(Box::new(stream::once(resp))).await;
+            let (payload, respp) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::GATEWAY_TIMEOUT);
+            assert_eq!(payload["type"], "backend_request_timed_out");
+        });
     }
 }
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use serde_derive::Deserialize;
 
 use super::OpaqueId;
@@ -51,16 +53,54 @@ pub(crate) struct MediaEvent {
     sender: i64,
     opaque_id: String,
     #[serde(rename = "type")]
-    kind: String,
+    kind: MediaKind,
     receiving: bool,
 }
 
+impl MediaEvent {
+    pub(crate) fn kind(&self) -> MediaKind {
+        self.kind
+    }
+
+    pub(crate) fn receiving(&self) -> bool {
+        self.receiving
+    }
+}
+
+impl OpaqueId for MediaEvent {
+    fn opaque_id(&self) -> &str {
+        &self.opaque_id
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MediaKind {
+    Audio,
+    Video,
+}
+
+impl fmt::Display for MediaKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Audio => write!(f, "audio"),
+            Self::Video => write!(f, "video"),
+        }
+    }
+}
+
 // A session was torn down by the server because of timeout: 60 seconds (by default).
 #[derive(Debug, Deserialize)]
 pub(crate) struct TimeoutEvent {
     session_id: i64,
 }
 
+impl TimeoutEvent {
+    pub(crate) fn session_id(&self) -> i64 {
+        self.session_id
+    }
+}
+
 // Janus reporting problems sending media to a user
 // (user sent many NACKs in the last second; uplink=true is from Janus' perspective).
 #[derive(Debug, Deserialize)]
@@ -71,6 +111,18 @@ pub(crate) struct SlowLinkEvent {
     uplink: bool,
 }
 
+impl SlowLinkEvent {
+    pub(crate) fn uplink(&self) -> bool {
+        self.uplink
+    }
+}
+
+impl OpaqueId for SlowLinkEvent {
+    fn opaque_id(&self) -> &str {
+        &self.opaque_id
+    }
+}
+
 // Janus handle detached.
 // This is being sent in case of abnormal shutdown or after `HangUpEvent` in Chrome.
 #[derive(Debug, Deserialize)]
@@ -92,6 +144,7 @@ pub(crate) struct StatusEvent {
     online: bool,
     capacity: Option<i32>,
     balancer_capacity: Option<i32>,
+    group: Option<String>,
 }
 
 impl StatusEvent {
@@ -106,4 +159,8 @@ impl StatusEvent {
     pub(crate) fn balancer_capacity(&self) -> Option<i32> {
         self.balancer_capacity
     }
+
+    pub(crate) fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
 }
@@ -7,9 +7,9 @@ use std::ops::Bound;
 use svc_agent::mqtt::{
     IncomingEvent as MQTTIncomingEvent, IncomingEventProperties, IncomingRequestProperties,
     IncomingResponse as MQTTIncomingResponse, IntoPublishableMessage, OutgoingResponse,
-    ResponseStatus, ShortTermTimingProperties,
+    ResponseStatus, ShortTermTimingProperties, TrackingProperties,
 };
-use svc_agent::Addressable;
+use svc_agent::{Addressable, AgentId};
 use svc_error::Error as SvcError;
 use uuid::Uuid;
 
@@ -19,11 +19,14 @@ use crate::app::error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind};
 use crate::app::handle_id::HandleId;
 use crate::app::message_handler::MessageStream;
 use crate::app::API_VERSION;
-use crate::db::{agent, janus_backend, janus_rtc_stream, recording, room, rtc};
+use crate::config::KeepaliveConfig;
+use crate::db::{agent, janus_backend, janus_rtc_stream, recording, room};
 use crate::diesel::Connection;
 use crate::util::from_base64;
 
-use self::events::{IncomingEvent, StatusEvent};
+use self::events::{
+    IncomingEvent, MediaEvent, MediaKind, SlowLinkEvent, StatusEvent, TimeoutEvent,
+};
 use self::responses::{ErrorResponse, IncomingResponse};
 use self::transactions::Transaction;
 
@@ -60,7 +63,17 @@ async fn handle_response_impl<C: Context>(
     resp: &MQTTIncomingResponse<String>,
 ) -> Result<MessageStream, AppError> {
     let respp = resp.properties();
-    context.janus_client().finish_transaction(respp);
+
+    // The requester has already got a timeout response.
+    if !context.janus_client().finish_transaction(respp) {
+        warn!(
+            context.logger(),
+            "Dropping janus response to a timed out transaction: {}",
+            respp.correlation_data(),
+        );
+
+        return Ok(Box::new(stream::empty()));
+    }
 
     let payload = MQTTIncomingResponse::convert_payload::<IncomingResponse>(&resp)
         .map_err(|err| anyhow!("Failed to parse response: {}", err))
@@ -83,6 +96,7 @@ async fn handle_response_impl<C: Context>(
                             inresp.data().id(),
                             tn.capacity(),
                             tn.balancer_capacity(),
+                            tn.group(),
                             context.start_timestamp(),
                         )
                         .error(AppErrorKind::MessageBuildingFailed)?;
@@ -107,6 +121,10 @@ async fn handle_response_impl<C: Context>(
                         q = q.balancer_capacity(balancer_capacity);
                     }
 
+                    if let Some(group) = tn.group() {
+                        q = q.group(group);
+                    }
+
                     q.execute(&conn)?;
                     Ok(Box::new(stream::empty()))
                 }
@@ -123,7 +141,8 @@ async fn handle_response_impl<C: Context>(
                             inresp.data().id(),
                             tn.session_id(),
                             agent_id.clone(),
-                        )),
+                            &context.config().handle_id,
+                        )?),
                         reqp.to_response(
                             ResponseStatus::OK,
                             ShortTermTimingProperties::until_now(context.start_timestamp()),
@@ -162,6 +181,20 @@ async fn handle_response_impl<C: Context>(
                     let boxed_resp = Box::new(resp) as Box<dyn IntoPublishableMessage + Send>;
                     Ok(Box::new(stream::once(boxed_resp)))
                 }
+                // Keepalive has been received by Janus Gateway
+                Transaction::Keepalive(tn) => {
+                    context.add_logger_tags(o!("session_id" => tn.session_id()));
+
+                    let latency = (Utc::now() - tn.sent_at()).num_milliseconds() as i32;
+                    let conn = context.get_conn()?;
+
+                    janus_backend::UpdateQuery::new(respp.as_agent_id())
+                        .keepalive_misses(0)
+                        .keepalive_latency(latency)
+                        .execute(&conn)?;
+
+                    Ok(Box::new(stream::empty()))
+                }
                 // An unsupported incoming Ack message has been received
                 _ => Ok(Box::new(stream::empty())),
             }
@@ -271,6 +304,19 @@ async fn handle_response_impl<C: Context>(
                     // TODO: improve error handling
                     let plugin_data = inresp.plugin().data();
 
+                    // Leave a failed upload for vacuum to retry or give up on it.
+                    match plugin_data.get("status") {
+                        Some(status) if status != "200" && status != "404" => {
+                            context.add_logger_tags(o!("status" => status.as_u64()));
+                            return handle_upload_stream_failure(
+                                context,
+                                tn.rtc_id(),
+                                respp.tracking(),
+                            );
+                        }
+                        _ => (),
+                    }
+
                     plugin_data
                         .get("status")
                         .ok_or_else(|| anyhow!("Missing 'status' in the response"))
@@ -338,7 +384,7 @@ async fn handle_response_impl<C: Context>(
                                         .collect())
                                 })?;
 
-                            let (room, rtcs, recs): (room::Object, Vec<rtc::Object>, Vec<recording::Object>) = {
+                            {
                                 let conn = context.get_conn()?;
 
                                 recording::UpdateQuery::new(rtc_id)
@@ -346,38 +392,32 @@ async fn handle_response_impl<C: Context>(
                                     .started_at(started_at)
                                     .segments(segments)
                                     .execute(&conn)?;
+                            }
+
+                            let room = endpoint::helpers::find_room_by_rtc_id(
+                                context,
+                                rtc_id,
+                                endpoint::helpers::RoomTimeRequirement::Any,
+                            )?;
+
+                            let conn = context.get_conn()?;
+
+                            let maybe_event = endpoint::system::room_upload_event(
+                                context,
+                                &room,
+                                Some(respp.tracking()),
+                                &conn,
+                            )?;
 
-                                let rtc = rtc::FindQuery::new()
-                                    .id(rtc_id)
-                                    .execute(&conn)?
-                                    .ok_or_else(|| anyhow!("RTC not found"))
-                                    .error(AppErrorKind::RtcNotFound)?;
-
-                                let room = endpoint::helpers::find_room_by_rtc_id(
-                                    context,
-                                    rtc.id(),
-                                    endpoint::helpers::RoomTimeRequirement::Any,
-                                )?;
-
-                                // TODO: move to db module
-                                use diesel::prelude::*;
-                                let rtcs = rtc::Object::belonging_to(&room).load(&conn)?;
-                                let recs = recording::Object::belonging_to(&rtcs).load(&conn)?;
-
-                                (room, rtcs, recs)
-                            };
-
-                            // Ensure that all rtcs have a recording.
-                            let rtc_ids_with_recs = recs
-                                .iter()
-                                .map(|rec| rec.rtc_id())
-                                .collect::<Vec<Uuid>>();
-
-                            for rtc in rtcs {
-                                if !rtc_ids_with_recs.contains(&rtc.id()) {
+                            match maybe_event {
+                                // Send room.upload event.
+                                Some(event) => {
+                                    let event_box = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
+                                    Ok(Box::new(stream::once(event_box)) as MessageStream)
+                                }
+                                None => {
                                     let mut logger = context.logger().new(o!(
                                         "room_id" => room.id().to_string(),
-                                        "rtc_id" => rtc.id().to_string(),
                                     ));
 
                                     if let Some(scope) = room.tags().get("scope") {
@@ -389,20 +429,9 @@ async fn handle_response_impl<C: Context>(
                                         "postpone 'room.upload' event because still waiting for rtcs being uploaded";
                                     );
 
-                                    return Ok(Box::new(stream::empty()) as MessageStream);
+                                    Ok(Box::new(stream::empty()) as MessageStream)
                                 }
                             }
-
-                            // Send room.upload event.
-                            let event = endpoint::system::upload_event(
-                                context,
-                                &room,
-                                recs.into_iter(),
-                                respp.tracking(),
-                            )?;
-
-                            let event_box = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
-                            Ok(Box::new(stream::once(event_box)) as MessageStream)
                         })
                 }
                 // An unsupported incoming Event message has been received
@@ -426,6 +455,59 @@ async fn handle_response_impl<C: Context>(
     }
 }
 
+// Postpones the next upload attempt by the backoff or marks the recording as failed
+// when it has run out of attempts so `room.upload` could be sent without it.
+fn handle_upload_stream_failure<C: Context>(
+    context: &mut C,
+    rtc_id: Uuid,
+    tracking: &TrackingProperties,
+) -> Result<MessageStream, AppError> {
+    use crate::db::room::FindQueryable;
+
+    let conn = context.get_conn()?;
+
+    let recording = recording::FindQuery::new(rtc_id)
+        .execute(&conn)?
+        .ok_or_else(|| anyhow!("Recording not found"))
+        .error(AppErrorKind::BackendRecordingMissing)?;
+
+    if recording.attempts() < endpoint::system::UPLOAD_MAX_ATTEMPTS {
+        let next_attempt_at =
+            Utc::now() + endpoint::system::upload_retry_delay(recording.attempts());
+
+        recording::UpdateQuery::new(rtc_id)
+            .next_attempt_at(Some(next_attempt_at))
+            .execute(&conn)?;
+
+        return Err(anyhow!("Received error status, the upload will be retried"))
+            .error(AppErrorKind::BackendRequestFailed);
+    }
+
+    warn!(
+        context.logger(),
+        "Giving up uploading recording after {} attempts",
+        recording.attempts(),
+    );
+
+    recording::UpdateQuery::new(rtc_id)
+        .status(recording::Status::Failed)
+        .next_attempt_at(None)
+        .execute(&conn)?;
+
+    let room = room::FindByRtcIdQuery::new(rtc_id)
+        .execute(&conn)?
+        .ok_or_else(|| anyhow!("Room not found"))
+        .error(AppErrorKind::RoomNotFound)?;
+
+    match endpoint::system::room_upload_event(context, &room, Some(tracking), &conn)? {
+        Some(event) => {
+            let event_box = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
+            Ok(Box::new(stream::once(event_box)) as MessageStream)
+        }
+        None => Ok(Box::new(stream::empty())),
+    }
+}
+
 fn handle_response_error<C: Context>(
     context: &mut C,
     reqp: &IncomingRequestProperties,
@@ -504,7 +586,7 @@ async fn handle_event_impl<C: Context>(
                     room.id(),
                     rtc_stream,
                     context.start_timestamp(),
-                    evp.tracking(),
+                    Some(evp.tracking()),
                 )?;
 
                 Ok(Box::new(stream::once(
@@ -516,11 +598,184 @@ async fn handle_event_impl<C: Context>(
         }
         IncomingEvent::HangUp(ref inev) => handle_hangup_detach(context, inev, evp),
         IncomingEvent::Detached(ref inev) => handle_hangup_detach(context, inev, evp),
-        IncomingEvent::Media(_) | IncomingEvent::Timeout(_) | IncomingEvent::SlowLink(_) => {
-            // Ignore these kinds of events.
-            Ok(Box::new(stream::empty()))
+        IncomingEvent::Media(ref inev) => handle_media(context, inev, evp),
+        IncomingEvent::SlowLink(ref inev) => handle_slow_link(context, inev, evp),
+        IncomingEvent::Timeout(ref inev) => handle_timeout(context, inev, evp),
+    }
+}
+
+fn handle_timeout<C: Context>(
+    context: &mut C,
+    inev: &TimeoutEvent,
+    evp: &IncomingEventProperties,
+) -> Result<MessageStream, AppError> {
+    let backend_id = evp.as_agent_id();
+    let conn = context.get_conn()?;
+
+    let maybe_backend = janus_backend::FindQuery::new()
+        .id(backend_id.to_owned())
+        .execute(&conn)?;
+
+    let backend = match maybe_backend {
+        // Ignore timeouts of sessions that have already been replaced.
+        Some(backend) if backend.session_id() == inev.session_id() => backend,
+        _ => return Ok(Box::new(stream::empty())),
+    };
+
+    // All the handles of the backend belong to the single session that has been torn down
+    // so close its active streams and put connected agents back into `ready` status.
+    let streams_with_rtc = conn.transaction::<_, AppError, _>(|| {
+        let streams_with_rtc = janus_rtc_stream::ListWithRtcQuery::new()
+            .active(true)
+            .backend_id(backend_id)
+            .execute(&conn)?;
+
+        let mut stopped_streams_with_rtc = Vec::with_capacity(streams_with_rtc.len());
+
+        for (stream, rtc) in streams_with_rtc {
+            if let Some(stream) = janus_rtc_stream::stop(stream.id(), &conn)? {
+                stopped_streams_with_rtc.push((stream, rtc));
+            }
         }
+
+        agent::BulkStatusUpdateQuery::new(agent::Status::Ready)
+            .backend_id(backend_id)
+            .status(agent::Status::Connected)
+            .execute(&conn)?;
+
+        Ok(stopped_streams_with_rtc)
+    })?;
+
+    let mut messages = Vec::with_capacity(streams_with_rtc.len() + 1);
+
+    for (stream, rtc) in streams_with_rtc {
+        let event = endpoint::rtc_stream::update_event(
+            rtc.room_id(),
+            stream,
+            context.start_timestamp(),
+            Some(evp.tracking()),
+        )?;
+
+        messages.push(Box::new(event) as Box<dyn IntoPublishableMessage + Send>);
+    }
+
+    // Recreate the session. The backend row is kept so that it doesn't lose its draining state;
+    // stale session and handle ids get replaced as soon as the new handle is created.
+    let backreq = context
+        .janus_client()
+        .create_session_request(
+            backend.capacity(),
+            backend.balancer_capacity(),
+            backend.group(),
+            evp,
+            context.start_timestamp(),
+        )
+        .error(AppErrorKind::MessageBuildingFailed)?;
+
+    messages.push(Box::new(backreq) as Box<dyn IntoPublishableMessage + Send>);
+    Ok(Box::new(stream::from_iter(messages)))
+}
+
+fn handle_media<C: Context>(
+    context: &mut C,
+    inev: &MediaEvent,
+    evp: &IncomingEventProperties,
+) -> Result<MessageStream, AppError> {
+    context.add_logger_tags(o!("rtc_stream_id" => inev.opaque_id().to_owned()));
+
+    let rtc_stream_id = Uuid::from_str(inev.opaque_id())
+        .map_err(|err| anyhow!("Failed to parse opaque id as UUID: {}", err))
+        .error(AppErrorKind::MessageParsingFailed)?;
+
+    if let Some(stats) = context.dynamic_stats() {
+        let state = if inev.receiving() { "up" } else { "down" };
+        stats.collect(format!("janus_media_{}_{}", inev.kind(), state), 1);
+    }
+
+    let query = janus_rtc_stream::UpdateMediaStateQuery::new(rtc_stream_id);
+
+    let query = match inev.kind() {
+        MediaKind::Audio => query.audio_receiving(inev.receiving()),
+        MediaKind::Video => query.video_receiving(inev.receiving()),
+    };
+
+    let maybe_rtc_stream = {
+        let conn = context.get_conn()?;
+        query.execute(&conn)?
+    };
+
+    // If the event relates to the publisher's handle,
+    // we will find the corresponding stream and send an event w/ updated stream object
+    // to the room's topic.
+    if let Some(rtc_stream) = maybe_rtc_stream {
+        let room = endpoint::helpers::find_room_by_rtc_id(
+            context,
+            rtc_stream.rtc_id(),
+            endpoint::helpers::RoomTimeRequirement::Open,
+        )?;
+
+        let event = endpoint::rtc_stream::update_event(
+            room.id(),
+            rtc_stream,
+            context.start_timestamp(),
+            Some(evp.tracking()),
+        )?;
+
+        let boxed_event = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
+        return Ok(Box::new(stream::once(boxed_event)));
+    }
+
+    Ok(Box::new(stream::empty()))
+}
+
+fn handle_slow_link<C: Context>(
+    context: &mut C,
+    inev: &SlowLinkEvent,
+    evp: &IncomingEventProperties,
+) -> Result<MessageStream, AppError> {
+    context.add_logger_tags(o!("rtc_stream_id" => inev.opaque_id().to_owned()));
+
+    let rtc_stream_id = Uuid::from_str(inev.opaque_id())
+        .map_err(|err| anyhow!("Failed to parse opaque id as UUID: {}", err))
+        .error(AppErrorKind::MessageParsingFailed)?;
+
+    if let Some(stats) = context.dynamic_stats() {
+        let direction = if inev.uplink() { "uplink" } else { "downlink" };
+        stats.collect(format!("janus_slow_link_{}", direction), 1);
     }
+
+    let maybe_rtc_stream = {
+        let conn = context.get_conn()?;
+        janus_rtc_stream::FindQuery::new(rtc_stream_id).execute(&conn)?
+    };
+
+    // Only the publisher's handle could be mapped to an agent through the stream.
+    if let Some(rtc_stream) = maybe_rtc_stream {
+        let room = endpoint::helpers::find_room_by_rtc_id(
+            context,
+            rtc_stream.rtc_id(),
+            endpoint::helpers::RoomTimeRequirement::Open,
+        )?;
+
+        let data = endpoint::agent::NetworkQualityEventData::new(
+            rtc_stream.sent_by().to_owned(),
+            rtc_stream.rtc_id(),
+            rtc_stream.id(),
+            inev.uplink(),
+        );
+
+        let event = endpoint::agent::network_quality_event(
+            room.id(),
+            data,
+            context.start_timestamp(),
+            evp.tracking(),
+        );
+
+        let boxed_event = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
+        return Ok(Box::new(stream::once(boxed_event)));
+    }
+
+    Ok(Box::new(stream::empty()))
 }
 
 fn handle_hangup_detach<C: Context, E: OpaqueId>(
@@ -561,7 +816,7 @@ fn handle_hangup_detach<C: Context, E: OpaqueId>(
                 room.id(),
                 rtc_stream,
                 context.start_timestamp(),
-                evp.tracking(),
+                Some(evp.tracking()),
             )?;
 
             let boxed_event = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
@@ -603,55 +858,756 @@ async fn handle_status_event_impl<C: Context>(
     if payload.online() {
         let event = context
             .janus_client()
-            .create_session_request(&payload, evp, context.start_timestamp())
+            .create_session_request(
+                payload.capacity(),
+                payload.balancer_capacity(),
+                payload.group(),
+                evp,
+                context.start_timestamp(),
+            )
             .error(AppErrorKind::MessageBuildingFailed)?;
 
         let boxed_event = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
         Ok(Box::new(stream::once(boxed_event)))
     } else {
-        let conn = context.get_conn()?;
+        let events = handle_backend_offline(context, evp.as_agent_id(), Some(evp.tracking()))?;
+        Ok(Box::new(stream::from_iter(events)))
+    }
+}
 
-        let streams_with_rtc = conn.transaction::<_, AppError, _>(|| {
-            let streams_with_rtc = janus_rtc_stream::ListWithRtcQuery::new()
-                .active(true)
-                .backend_id(evp.as_agent_id())
-                .execute(&conn)?;
+// Closes active streams of the backend, puts its connected agents back into `ready` status
+// and removes the backend. Returns `rtc_stream.update` events for the closed streams.
+fn handle_backend_offline<C: Context>(
+    context: &mut C,
+    backend_id: &AgentId,
+    tracking: Option<&TrackingProperties>,
+) -> Result<Vec<Box<dyn IntoPublishableMessage + Send>>, AppError> {
+    let conn = context.get_conn()?;
 
-            agent::BulkStatusUpdateQuery::new(agent::Status::Ready)
-                .backend_id(evp.as_agent_id())
-                .status(agent::Status::Connected)
-                .execute(&conn)?;
+    let streams_with_rtc = conn.transaction::<_, AppError, _>(|| {
+        let streams_with_rtc = janus_rtc_stream::ListWithRtcQuery::new()
+            .active(true)
+            .backend_id(backend_id)
+            .execute(&conn)?;
 
-            janus_backend::DeleteQuery::new(evp.as_agent_id()).execute(&conn)?;
-            Ok(streams_with_rtc)
-        })?;
+        agent::BulkStatusUpdateQuery::new(agent::Status::Ready)
+            .backend_id(backend_id)
+            .status(agent::Status::Connected)
+            .execute(&conn)?;
 
-        let now = Utc::now();
-        let mut events = Vec::with_capacity(streams_with_rtc.len());
+        janus_backend::DeleteQuery::new(backend_id).execute(&conn)?;
+        Ok(streams_with_rtc)
+    })?;
 
-        for (mut stream, rtc) in streams_with_rtc {
-            stream.set_time(stream.time().map(|t| (t.0, Bound::Excluded(now))));
+    let now = Utc::now();
+    let mut events = Vec::with_capacity(streams_with_rtc.len());
 
-            let event = endpoint::rtc_stream::update_event(
-                rtc.room_id(),
-                stream,
-                context.start_timestamp(),
-                evp.tracking(),
-            )?;
+    for (mut stream, rtc) in streams_with_rtc {
+        stream.set_time(stream.time().map(|t| (t.0, Bound::Excluded(now))));
 
-            events.push(Box::new(event) as Box<dyn IntoPublishableMessage + Send>);
-        }
+        let event = endpoint::rtc_stream::update_event(
+            rtc.room_id(),
+            stream,
+            context.start_timestamp(),
+            tracking,
+        )?;
 
-        Ok(Box::new(stream::from_iter(events)))
+        events.push(Box::new(event) as Box<dyn IntoPublishableMessage + Send>);
+    }
+
+    Ok(events)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Sends keepalive requests to sessions of all backends. Backends that haven't answered
+// `max_misses` keepalives in a row are considered offline.
+pub(crate) fn handle_keepalive<C: Context>(
+    context: &mut C,
+    config: &KeepaliveConfig,
+) -> Result<MessageStream, AppError> {
+    let max_misses = config.max_misses;
+    let period = chrono::Duration::seconds(config.period as i64);
+
+    let backends = {
+        let conn = context.get_conn()?;
+        janus_backend::increment_keepalive_misses(period, &conn)?
+    };
+
+    let mut messages = Vec::with_capacity(backends.len());
+
+    for backend in backends {
+        if backend.keepalive_misses() > max_misses {
+            warn!(
+                context.logger(),
+                "Janus backend {} missed {} keepalives, marking it offline",
+                backend.id(),
+                max_misses,
+            );
+
+            if let Some(stats) = context.dynamic_stats() {
+                stats.collect("janus_keepalive_offline", 1);
+            }
+
+            let events = handle_backend_offline(context, backend.id(), None)?;
+            messages.extend(events);
+        } else {
+            let backreq = context
+                .janus_client()
+                .keepalive_request(&backend, context.start_timestamp())
+                .error(AppErrorKind::MessageBuildingFailed)?;
+
+            messages.push(Box::new(backreq) as Box<dyn IntoPublishableMessage + Send>);
+        }
     }
+
+    Ok(Box::new(stream::from_iter(messages)))
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
+mod balancer;
 mod client;
 mod events;
 pub(crate) mod requests;
 mod responses;
 mod transactions;
 
+pub(crate) use balancer::Balancer;
 pub(crate) use client::Client;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    mod keepalive {
+        use serde_json::{json, Value as JsonValue};
+
+        use crate::db::janus_backend;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        fn build_keepalive_config() -> KeepaliveConfig {
+            KeepaliveConfig {
+                period: 10,
+                max_misses: 3,
+            }
+        }
+
+        #[test]
+        fn send_keepalive() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let backend = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    shared_helpers::insert_janus_backend(&conn)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let config = build_keepalive_config();
+                let messages = handle_keepalive(&mut context, &config).expect("Keepalive failed");
+                let messages = parse_messages(messages).await;
+
+                // Assert keepalive request to the backend's session.
+                let (payload, _reqp, topic) = find_request::<JsonValue>(messages.as_slice());
+
+                let expected_topic = format!(
+                    "agents/{}/api/{}/in/{}",
+                    backend.id(),
+                    JANUS_API_VERSION,
+                    context.config().id,
+                );
+
+                assert_eq!(topic, &expected_topic);
+                assert_eq!(payload["janus"], "keepalive");
+                assert_eq!(payload["session_id"], backend.session_id());
+
+                // Assert the keepalive is counted as a miss until it gets acknowledged.
+                let conn = context.get_conn().expect("Failed to get db conn");
+
+                let backend = janus_backend::FindQuery::new()
+                    .id(backend.id().to_owned())
+                    .execute(&conn)
+                    .expect("Failed to find backend")
+                    .expect("Backend not found");
+
+                assert_eq!(backend.keepalive_misses(), 1);
+            });
+        }
+
+        #[test]
+        fn mark_backend_offline() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let backend = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+
+                    janus_backend::UpdateQuery::new(backend.id())
+                        .keepalive_misses(3)
+                        .execute(&conn)
+                        .expect("Failed to update backend");
+
+                    backend
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let config = build_keepalive_config();
+                let messages = handle_keepalive(&mut context, &config).expect("Keepalive failed");
+                let messages = parse_messages(messages).await;
+                assert!(messages.is_empty());
+
+                // Assert the backend has been removed.
+                let conn = context.get_conn().expect("Failed to get db conn");
+
+                let maybe_backend = janus_backend::FindQuery::new()
+                    .id(backend.id().to_owned())
+                    .execute(&conn)
+                    .expect("Failed to find backend");
+
+                assert!(maybe_backend.is_none());
+            });
+        }
+
+        #[test]
+        fn send_keepalive_once_per_period() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let backend = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    shared_helpers::insert_janus_backend(&conn)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let config = build_keepalive_config();
+
+                let messages = handle_keepalive(&mut context, &config).expect("Keepalive failed");
+                assert_eq!(parse_messages(messages).await.len(), 1);
+
+                // Another replica ticking within the same period must neither send a keepalive
+                // nor count a miss.
+                let messages = handle_keepalive(&mut context, &config).expect("Keepalive failed");
+                assert!(parse_messages(messages).await.is_empty());
+
+                let conn = context.get_conn().expect("Failed to get db conn");
+
+                let backend = janus_backend::FindQuery::new()
+                    .id(backend.id().to_owned())
+                    .execute(&conn)
+                    .expect("Failed to find backend")
+                    .expect("Backend not found");
+
+                assert_eq!(backend.keepalive_misses(), 1);
+            });
+        }
+
+        #[test]
+        fn handle_keepalive_ack() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let backend = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+
+                    janus_backend::UpdateQuery::new(backend.id())
+                        .keepalive_misses(2)
+                        .execute(&conn)
+                        .expect("Failed to update backend");
+
+                    backend
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let config = build_keepalive_config();
+
+                let messages = handle_keepalive(&mut context, &config).expect("Keepalive failed");
+                let messages = parse_messages(messages).await;
+                let (payload, _reqp, _topic) = find_request::<JsonValue>(messages.as_slice());
+
+                // Make Janus acknowledge the keepalive.
+                let ack = json!({
+                    "janus": "ack",
+                    "transaction": payload["transaction"],
+                    "session_id": backend.session_id(),
+                });
+
+                let resp = MQTTIncomingResponse::new(ack.to_string(), build_respp(backend.id()));
+                let messages = handle_response(&mut context, &resp).await;
+                assert!(parse_messages(messages).await.is_empty());
+
+                // Assert misses are reset and the round trip latency is stored.
+                let conn = context.get_conn().expect("Failed to get db conn");
+
+                let backend = janus_backend::FindQuery::new()
+                    .id(backend.id().to_owned())
+                    .execute(&conn)
+                    .expect("Failed to find backend")
+                    .expect("Backend not found");
+
+                assert_eq!(backend.keepalive_misses(), 0);
+
+                let latency = backend.keepalive_latency().expect("Missing latency");
+                assert!(latency >= 0);
+            });
+        }
+    }
+
+    mod media {
+        use serde_json::{json, Value as JsonValue};
+
+        use crate::backend::janus::events::MediaEvent;
+        use crate::db::janus_rtc_stream;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        fn build_event(
+            backend: &janus_backend::Object,
+            rtc_stream: &janus_rtc_stream::Object,
+            kind: &str,
+            receiving: bool,
+        ) -> MediaEvent {
+            serde_json::from_value(json!({
+                "janus": "media",
+                "session_id": backend.session_id(),
+                "sender": backend.handle_id(),
+                "opaque_id": rtc_stream.id().to_string(),
+                "type": kind,
+                "receiving": receiving,
+            }))
+            .expect("Failed to parse media event")
+        }
+
+        #[test]
+        fn update_audio_receiving() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (backend, rtc, rtc_stream) = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+                    let rtc = shared_helpers::insert_rtc(&conn);
+
+                    let rtc_stream = factory::JanusRtcStream::new(USR_AUDIENCE)
+                        .backend(&backend)
+                        .rtc(&rtc)
+                        .sent_by(agent.agent_id())
+                        .insert(&conn);
+
+                    (backend, rtc, rtc_stream)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let inev = build_event(&backend, &rtc_stream, "audio", false);
+                let evp = build_evp(backend.id(), "ignore");
+
+                let messages = handle_media(&mut context, &inev, &evp).expect("Media failed");
+                let messages = parse_messages(messages).await;
+
+                // Assert the updated stream is broadcasted to the room.
+                let (payload, evp, topic) = find_event::<JsonValue>(messages.as_slice());
+                assert_eq!(evp.label(), "rtc_stream.update");
+                assert!(topic.ends_with(&format!("rooms/{}/events", rtc.room_id())));
+                assert_eq!(payload["id"], rtc_stream.id().to_string());
+
+                // Assert only the audio state has been updated.
+                let conn = context.get_conn().expect("Failed to get db conn");
+
+                let rtc_stream = janus_rtc_stream::FindQuery::new(rtc_stream.id())
+                    .execute(&conn)
+                    .expect("Failed to find rtc stream")
+                    .expect("Rtc stream not found");
+
+                assert_eq!(rtc_stream.audio_receiving(), Some(false));
+                assert_eq!(rtc_stream.video_receiving(), None);
+            });
+        }
+
+        #[test]
+        fn update_video_receiving() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let (backend, rtc_stream) = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+
+                    let rtc_stream = factory::JanusRtcStream::new(USR_AUDIENCE)
+                        .backend(&backend)
+                        .insert(&conn);
+
+                    (backend, rtc_stream)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+                let inev = build_event(&backend, &rtc_stream, "video", true);
+                let evp = build_evp(backend.id(), "ignore");
+
+                let messages = handle_media(&mut context, &inev, &evp).expect("Media failed");
+                let messages = parse_messages(messages).await;
+                let (_, evp, _) = find_event::<JsonValue>(messages.as_slice());
+                assert_eq!(evp.label(), "rtc_stream.update");
+
+                // Assert only the video state has been updated.
+                let conn = context.get_conn().expect("Failed to get db conn");
+
+                let rtc_stream = janus_rtc_stream::FindQuery::new(rtc_stream.id())
+                    .execute(&conn)
+                    .expect("Failed to find rtc stream")
+                    .expect("Rtc stream not found");
+
+                assert_eq!(rtc_stream.audio_receiving(), None);
+                assert_eq!(rtc_stream.video_receiving(), Some(true));
+            });
+        }
+
+        #[test]
+        fn ignore_subscriber_handle() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+
+                let backend = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    shared_helpers::insert_janus_backend(&conn)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let inev: MediaEvent = serde_json::from_value(json!({
+                    "janus": "media",
+                    "session_id": backend.session_id(),
+                    "sender": backend.handle_id(),
+                    "opaque_id": Uuid::new_v4().to_string(),
+                    "type": "audio",
+                    "receiving": true,
+                }))
+                .expect("Failed to parse media event");
+
+                let evp = build_evp(backend.id(), "ignore");
+                let messages = handle_media(&mut context, &inev, &evp).expect("Media failed");
+                assert!(parse_messages(messages).await.is_empty());
+            });
+        }
+    }
+
+    mod slow_link {
+        use serde_json::{json, Value as JsonValue};
+
+        use crate::backend::janus::events::SlowLinkEvent;
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn notify_network_quality() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+                let (backend, rtc, rtc_stream) = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+                    let rtc = shared_helpers::insert_rtc(&conn);
+
+                    let rtc_stream = factory::JanusRtcStream::new(USR_AUDIENCE)
+                        .backend(&backend)
+                        .rtc(&rtc)
+                        .sent_by(agent.agent_id())
+                        .insert(&conn);
+
+                    (backend, rtc, rtc_stream)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let inev: SlowLinkEvent = serde_json::from_value(json!({
+                    "janus": "slowlink",
+                    "session_id": backend.session_id(),
+                    "sender": backend.handle_id(),
+                    "opaque_id": rtc_stream.id().to_string(),
+                    "uplink": true,
+                }))
+                .expect("Failed to parse slow link event");
+
+                let evp = build_evp(backend.id(), "ignore");
+
+                let messages =
+                    handle_slow_link(&mut context, &inev, &evp).expect("Slow link failed");
+
+                let messages = parse_messages(messages).await;
+
+                // Assert the publisher's network quality is broadcasted to the room.
+                let (payload, evp, topic) = find_event::<JsonValue>(messages.as_slice());
+                assert_eq!(evp.label(), "agent.network_quality");
+                assert!(topic.ends_with(&format!("rooms/{}/events", rtc.room_id())));
+                assert_eq!(payload["agent_id"], agent.agent_id().to_string());
+                assert_eq!(payload["rtc_id"], rtc.id().to_string());
+                assert_eq!(payload["rtc_stream_id"], rtc_stream.id().to_string());
+                assert_eq!(payload["uplink"], true);
+            });
+        }
+    }
+
+    mod timeout {
+        use serde_json::{json, Value as JsonValue};
+        use svc_agent::AgentId;
+
+        use crate::backend::janus::events::TimeoutEvent;
+        use crate::db::{agent, janus_rtc_stream, rtc};
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        // Inserts a started stream on the backend and a connected agent in the stream's room.
+        fn insert_publisher(
+            conn: &diesel::pg::PgConnection,
+            backend: &janus_backend::Object,
+            agent_id: &AgentId,
+        ) -> (rtc::Object, janus_rtc_stream::Object) {
+            let rtc = shared_helpers::insert_rtc(conn);
+            shared_helpers::insert_agent(conn, agent_id, rtc.room_id());
+
+            let rtc_stream = factory::JanusRtcStream::new(USR_AUDIENCE)
+                .backend(backend)
+                .rtc(&rtc)
+                .sent_by(agent_id)
+                .insert(conn);
+
+            let rtc_stream = janus_rtc_stream::start(rtc_stream.id(), conn)
+                .expect("Failed to start rtc stream")
+                .expect("Rtc stream not found");
+
+            (rtc, rtc_stream)
+        }
+
+        fn find_agent_status(
+            conn: &diesel::pg::PgConnection,
+            agent_id: &AgentId,
+            rtc: &rtc::Object,
+        ) -> agent::Status {
+            agent::ListQuery::new()
+                .agent_id(agent_id)
+                .room_id(rtc.room_id())
+                .execute(conn)
+                .expect("Failed to list agents")
+                .first()
+                .expect("Agent not found")
+                .status()
+        }
+
+        #[test]
+        fn clean_up_session() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+                let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+
+                let (backend, rtc1, rtc_stream1, rtc2, rtc_stream2) = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+                    let other_backend = shared_helpers::insert_janus_backend(&conn);
+                    let (rtc1, rtc_stream1) = insert_publisher(&conn, &backend, agent1.agent_id());
+
+                    let (rtc2, rtc_stream2) =
+                        insert_publisher(&conn, &other_backend, agent2.agent_id());
+
+                    (backend, rtc1, rtc_stream1, rtc2, rtc_stream2)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let inev: TimeoutEvent = serde_json::from_value(json!({
+                    "janus": "timeout",
+                    "session_id": backend.session_id(),
+                }))
+                .expect("Failed to parse timeout event");
+
+                let evp = build_evp(backend.id(), "ignore");
+                let messages = handle_timeout(&mut context, &inev, &evp).expect("Timeout failed");
+                let messages = parse_messages(messages).await;
+
+                // Assert only the stream of the timed out backend has been closed.
+                let (payload, evp, topic) = find_event::<JsonValue>(messages.as_slice());
+                assert_eq!(evp.label(), "rtc_stream.update");
+                assert!(topic.ends_with(&format!("rooms/{}/events", rtc1.room_id())));
+                assert_eq!(payload["id"], rtc_stream1.id().to_string());
+                assert!(payload["time"][1].is_number());
+
+                // Assert the session gets recreated.
+                let (payload, _reqp, _topic) = find_request::<JsonValue>(messages.as_slice());
+                assert_eq!(payload["janus"], "create");
+                assert_eq!(messages.len(), 2);
+
+                let conn = context.get_conn().expect("Failed to get db conn");
+
+                let rtc_stream1 = janus_rtc_stream::FindQuery::new(rtc_stream1.id())
+                    .execute(&conn)
+                    .expect("Failed to find rtc stream")
+                    .expect("Rtc stream not found");
+
+                let rtc_stream2 = janus_rtc_stream::FindQuery::new(rtc_stream2.id())
+                    .execute(&conn)
+                    .expect("Failed to find rtc stream")
+                    .expect("Rtc stream not found");
+
+                let (_, stop1) = rtc_stream1.time().expect("Missing stream time");
+                let (_, stop2) = rtc_stream2.time().expect("Missing stream time");
+                assert!(matches!(stop1, Bound::Excluded(_)));
+                assert_eq!(stop2, Bound::Unbounded);
+
+                // Assert only the agents of the timed out backend are put back to `ready`.
+                let status1 = find_agent_status(&conn, agent1.agent_id(), &rtc1);
+                let status2 = find_agent_status(&conn, agent2.agent_id(), &rtc2);
+                assert_eq!(status1, agent::Status::Ready);
+                assert_eq!(status2, agent::Status::Connected);
+
+                // Assert the backend is kept.
+                let maybe_backend = janus_backend::FindQuery::new()
+                    .id(backend.id().to_owned())
+                    .execute(&conn)
+                    .expect("Failed to find backend");
+
+                assert!(maybe_backend.is_some());
+            });
+        }
+
+        #[test]
+        fn ignore_stale_session() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+
+                let (backend, rtc, rtc_stream) = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+                    let (rtc, rtc_stream) = insert_publisher(&conn, &backend, agent.agent_id());
+                    (backend, rtc, rtc_stream)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let inev: TimeoutEvent = serde_json::from_value(json!({
+                    "janus": "timeout",
+                    "session_id": backend.session_id() + 1,
+                }))
+                .expect("Failed to parse timeout event");
+
+                let evp = build_evp(backend.id(), "ignore");
+                let messages = handle_timeout(&mut context, &inev, &evp).expect("Timeout failed");
+                assert!(parse_messages(messages).await.is_empty());
+
+                // Assert nothing has changed.
+                let conn = context.get_conn().expect("Failed to get db conn");
+
+                let rtc_stream = janus_rtc_stream::FindQuery::new(rtc_stream.id())
+                    .execute(&conn)
+                    .expect("Failed to find rtc stream")
+                    .expect("Rtc stream not found");
+
+                let (_, stop) = rtc_stream.time().expect("Missing stream time");
+                assert_eq!(stop, Bound::Unbounded);
+
+                let status = find_agent_status(&conn, agent.agent_id(), &rtc);
+                assert_eq!(status, agent::Status::Connected);
+            });
+        }
+    }
+
+    mod backend_offline {
+        use serde_json::Value as JsonValue;
+
+        use crate::db::{agent, janus_rtc_stream};
+        use crate::test_helpers::prelude::*;
+
+        use super::super::*;
+
+        #[test]
+        fn close_streams_of_the_backend_only() {
+            async_std::task::block_on(async {
+                let db = TestDb::new();
+                let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+                let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+
+                let (backend, other_backend, rtc1, rtc_stream1, rtc_stream2) = {
+                    let conn = db.connection_pool().get().expect("Failed to get db conn");
+                    let backend = shared_helpers::insert_janus_backend(&conn);
+                    let other_backend = shared_helpers::insert_janus_backend(&conn);
+                    let rtc1 = shared_helpers::insert_rtc(&conn);
+                    let rtc2 = shared_helpers::insert_rtc(&conn);
+                    shared_helpers::insert_agent(&conn, agent1.agent_id(), rtc1.room_id());
+                    shared_helpers::insert_agent(&conn, agent2.agent_id(), rtc2.room_id());
+
+                    let rtc_stream1 = factory::JanusRtcStream::new(USR_AUDIENCE)
+                        .backend(&backend)
+                        .rtc(&rtc1)
+                        .sent_by(agent1.agent_id())
+                        .insert(&conn);
+
+                    let rtc_stream2 = factory::JanusRtcStream::new(USR_AUDIENCE)
+                        .backend(&other_backend)
+                        .rtc(&rtc2)
+                        .sent_by(agent2.agent_id())
+                        .insert(&conn);
+
+                    janus_rtc_stream::start(rtc_stream1.id(), &conn)
+                        .expect("Failed to start rtc stream");
+
+                    janus_rtc_stream::start(rtc_stream2.id(), &conn)
+                        .expect("Failed to start rtc stream");
+
+                    (backend, other_backend, rtc1, rtc_stream1, rtc_stream2)
+                };
+
+                let mut context = TestContext::new(db, TestAuthz::new());
+
+                let messages = handle_backend_offline(&mut context, backend.id(), None)
+                    .expect("Backend offline failed");
+
+                let messages = parse_messages(Box::new(stream::from_iter(messages))).await;
+
+                // Assert an update is sent for the stream of the offline backend only.
+                assert_eq!(messages.len(), 1);
+                let (payload, evp, topic) = find_event::<JsonValue>(messages.as_slice());
+                assert_eq!(evp.label(), "rtc_stream.update");
+                assert!(topic.ends_with(&format!("rooms/{}/events", rtc1.room_id())));
+                assert_eq!(payload["id"], rtc_stream1.id().to_string());
+
+                let conn = context.get_conn().expect("Failed to get db conn");
+
+                // Assert the other backend's stream is still active.
+                let active_streams = janus_rtc_stream::ListWithRtcQuery::new()
+                    .active(true)
+                    .execute(&conn)
+                    .expect("Failed to list rtc streams");
+
+                assert_eq!(active_streams.len(), 1);
+                assert_eq!(active_streams[0].0.id(), rtc_stream2.id());
+
+                // Assert only the agent of the offline backend is put back to `ready`.
+                let ready_agents = agent::ListQuery::new()
+                    .status(agent::Status::Ready)
+                    .execute(&conn)
+                    .expect("Failed to list agents");
+
+                assert_eq!(ready_agents.len(), 1);
+                assert_eq!(ready_agents[0].agent_id(), agent1.agent_id());
+
+                // Assert only the offline backend is removed.
+                let backends = janus_backend::ListQuery::new()
+                    .execute(&conn)
+                    .expect("Failed to list backends");
+
+                assert_eq!(backends.len(), 1);
+                assert_eq!(backends[0].id(), other_backend.id());
+            });
+        }
+    }
+}
@@ -24,6 +24,25 @@ impl CreateSessionRequest {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, Serialize)]
+pub(crate) struct KeepaliveRequest {
+    transaction: String,
+    janus: &'static str,
+    session_id: i64,
+}
+
+impl KeepaliveRequest {
+    pub(crate) fn new(transaction: &str, session_id: i64) -> Self {
+        Self {
+            transaction: transaction.to_owned(),
+            janus: "keepalive",
+            session_id,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Serialize)]
 pub(crate) struct CreateHandleRequest {
     transaction: String,
@@ -97,7 +97,14 @@ impl Client {
             Some(&rtc_stream_id.to_string()),
         );
 
-        self.register_transaction(to, start_timestamp, &props, &payload, self.timeout(METHOD));
+        self.register_transaction(
+            to,
+            start_timestamp,
+            &props,
+            &transaction,
+            &payload,
+            self.timeout(METHOD),
+        );
 
         Ok(OutgoingRequest::unicast(
             payload,
@@ -11,7 +11,6 @@ use svc_agent::{
 
 use crate::util::{generate_correlation_data, to_base64};
 
-use super::super::events::StatusEvent;
 use super::super::requests::CreateSessionRequest;
 use super::super::{Client, JANUS_API_VERSION};
 use super::Transaction;
@@ -24,6 +23,7 @@ const METHOD: &str = "janus_session.create";
 pub(crate) struct TransactionData {
     capacity: Option<i32>,
     balancer_capacity: Option<i32>,
+    group: Option<String>,
 }
 
 impl TransactionData {
@@ -31,6 +31,7 @@ impl TransactionData {
         Self {
             capacity: None,
             balancer_capacity: None,
+            group: None,
         }
     }
 
@@ -51,6 +52,15 @@ impl TransactionData {
         self.balancer_capacity = Some(balancer_capacity);
         self
     }
+
+    pub(crate) fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    pub(crate) fn set_group(&mut self, group: &str) -> &mut Self {
+        self.group = Some(group.to_owned());
+        self
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -58,21 +68,27 @@ impl TransactionData {
 impl Client {
     pub(crate) fn create_session_request(
         &self,
-        payload: &StatusEvent,
+        capacity: Option<i32>,
+        balancer_capacity: Option<i32>,
+        group: Option<&str>,
         evp: &IncomingEventProperties,
         start_timestamp: DateTime<Utc>,
     ) -> Result<OutgoingMessage<CreateSessionRequest>> {
         let to = evp.as_agent_id();
         let mut tn_data = TransactionData::new();
 
-        if let Some(capacity) = payload.capacity() {
+        if let Some(capacity) = capacity {
             tn_data.set_capacity(capacity);
         }
 
-        if let Some(balancer_capacity) = payload.balancer_capacity() {
+        if let Some(balancer_capacity) = balancer_capacity {
             tn_data.set_balancer_capacity(balancer_capacity);
         }
 
+        if let Some(group) = group {
+            tn_data.set_group(group);
+        }
+
         let transaction = Transaction::CreateSession(tn_data);
         let payload = CreateSessionRequest::new(&to_base64(&transaction)?);
 
@@ -84,7 +100,14 @@ impl Client {
         );
 
         props.set_tracking(evp.tracking().to_owned());
-        self.register_transaction(to, start_timestamp, &props, &payload, self.timeout(METHOD));
+        self.register_transaction(
+            to,
+            start_timestamp,
+            &props,
+            &transaction,
+            &payload,
+            self.timeout(METHOD),
+        );
 
         Ok(OutgoingRequest::unicast(
             payload,
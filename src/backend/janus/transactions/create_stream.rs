@@ -70,7 +70,14 @@ impl Client {
             Some(jsep),
         );
 
-        self.register_transaction(to, start_timestamp, &props, &payload, self.timeout(METHOD));
+        self.register_transaction(
+            to,
+            start_timestamp,
+            &props,
+            &transaction,
+            &payload,
+            self.timeout(METHOD),
+        );
 
         Ok(OutgoingRequest::unicast(
             payload,
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use svc_agent::mqtt::{
+    OutgoingMessage, OutgoingRequest, OutgoingRequestProperties, ShortTermTimingProperties,
+};
+
+use crate::db::janus_backend::Object as JanusBackend;
+use crate::util::{generate_correlation_data, to_base64};
+
+use super::super::requests::KeepaliveRequest;
+use super::super::{Client, JANUS_API_VERSION};
+use super::Transaction;
+
+const METHOD: &str = "janus_session.keepalive";
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct TransactionData {
+    session_id: i64,
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    sent_at: DateTime<Utc>,
+}
+
+impl TransactionData {
+    pub(crate) fn new(session_id: i64, sent_at: DateTime<Utc>) -> Self {
+        Self {
+            session_id,
+            sent_at,
+        }
+    }
+
+    pub(crate) fn session_id(&self) -> i64 {
+        self.session_id
+    }
+
+    pub(crate) fn sent_at(&self) -> DateTime<Utc> {
+        self.sent_at
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+impl Client {
+    pub(crate) fn keepalive_request(
+        &self,
+        backend: &JanusBackend,
+        start_timestamp: DateTime<Utc>,
+    ) -> Result<OutgoingMessage<KeepaliveRequest>> {
+        let to = backend.id();
+        let tn_data = TransactionData::new(backend.session_id(), Utc::now());
+        let transaction = Transaction::Keepalive(tn_data);
+        let payload = KeepaliveRequest::new(&to_base64(&transaction)?, backend.session_id());
+
+        let props = OutgoingRequestProperties::new(
+            METHOD,
+            &self.response_topic(to)?,
+            &generate_correlation_data(),
+            ShortTermTimingProperties::until_now(start_timestamp),
+        );
+
+        // The transaction is intentionally not registered in the watchdog:
+        // unanswered keepalives are counted as misses on the backend instead.
+        Ok(OutgoingRequest::unicast(
+            payload,
+            props,
+            to,
+            JANUS_API_VERSION,
+        ))
+    }
+}
@@ -1,4 +1,5 @@
 use serde_derive::{Deserialize, Serialize};
+use svc_agent::mqtt::IncomingRequestProperties;
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Deserialize, Serialize)]
@@ -8,16 +9,31 @@ pub(crate) enum Transaction {
     CreateSession(create_session::TransactionData),
     CreateStream(create_stream::TransactionData),
     CreateRtcHandle(create_rtc_handle::TransactionData),
+    Keepalive(keepalive::TransactionData),
     ReadStream(read_stream::TransactionData),
     Trickle(trickle::TransactionData),
     UploadStream(upload_stream::TransactionData),
 }
 
+impl Transaction {
+    /// Properties of the client's request the transaction has been initiated by if any.
+    pub(crate) fn reqp(&self) -> Option<&IncomingRequestProperties> {
+        match self {
+            Self::CreateRtcHandle(tn) => Some(tn.reqp()),
+            Self::CreateStream(tn) => Some(tn.reqp()),
+            Self::ReadStream(tn) => Some(tn.reqp()),
+            Self::Trickle(tn) => Some(tn.reqp()),
+            _ => None,
+        }
+    }
+}
+
 mod agent_leave;
 mod create_handle;
 mod create_rtc_handle;
 mod create_session;
 mod create_stream;
+mod keepalive;
 mod read_stream;
 mod trickle;
 mod upload_stream;
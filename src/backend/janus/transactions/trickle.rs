@@ -58,7 +58,14 @@ impl Client {
 
         let transaction = Transaction::Trickle(TransactionData::new(reqp));
         let payload = TrickleRequest::new(&to_base64(&transaction)?, session_id, handle_id, jsep);
-        self.register_transaction(to, start_timestamp, &props, &payload, self.timeout(METHOD));
+        self.register_transaction(
+            to,
+            start_timestamp,
+            &props,
+            &transaction,
+            &payload,
+            self.timeout(METHOD),
+        );
 
         Ok(OutgoingRequest::unicast(
             payload,
@@ -3,8 +3,8 @@ use chrono::{DateTime, Utc};
 use serde_derive::{Deserialize, Serialize};
 use svc_agent::{
     mqtt::{
-        IncomingRequestProperties, OutgoingMessage, OutgoingRequest, OutgoingRequestProperties,
-        ShortTermTimingProperties,
+        OutgoingMessage, OutgoingRequest, OutgoingRequestProperties, ShortTermTimingProperties,
+        TrackingProperties,
     },
     AgentId,
 };
@@ -40,7 +40,7 @@ impl TransactionData {
 impl Client {
     pub(crate) fn upload_stream_request(
         &self,
-        reqp: &IncomingRequestProperties,
+        maybe_tracking: Option<&TrackingProperties>,
         session_id: i64,
         handle_id: i64,
         body: UploadStreamRequestBody,
@@ -64,12 +64,15 @@ impl Client {
             ShortTermTimingProperties::until_now(start_timestamp),
         );
 
-        props.set_tracking(reqp.tracking().to_owned());
+        if let Some(tracking) = maybe_tracking {
+            props.set_tracking(tracking.to_owned());
+        }
 
         self.register_transaction(
             to,
             start_timestamp,
             &props,
+            &transaction,
             &payload,
             self.timeout(STREAM_UPLOAD_METHOD),
         );
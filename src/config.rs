@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt;
 
 use serde_derive::Deserialize;
 use svc_agent::{mqtt::AgentConfig, AccountId};
@@ -6,6 +7,8 @@ use svc_authn::jose::Algorithm;
 use svc_authz::ConfigMap as Authz;
 use svc_error::extension::sentry::Config as SentryConfig;
 
+use crate::template::Template;
+
 #[derive(Clone, Debug, Deserialize)]
 pub(crate) struct Config {
     pub(crate) id: AccountId,
@@ -16,12 +19,19 @@ pub(crate) struct Config {
     pub(crate) mqtt: AgentConfig,
     pub(crate) sentry: Option<SentryConfig>,
     pub(crate) backend: BackendConfig,
+    #[serde(default)]
+    pub(crate) balancer: BalancerConfig,
+    pub(crate) handle_id: HandleIdConfig,
     pub(crate) upload: UploadConfigMap,
     #[serde(default)]
     pub(crate) telemetry: TelemetryConfig,
     #[serde(default)]
     pub(crate) kruonis: KruonisConfig,
     pub(crate) metrics: Option<MetricsConfig>,
+    #[serde(default)]
+    pub(crate) message: MessageConfig,
+    pub(crate) vacuum: Option<VacuumConfig>,
+    pub(crate) webhook: Option<WebhookConfig>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -45,6 +55,105 @@ pub(crate) struct BackendConfig {
     pub(crate) default_timeout: u64,
     pub(crate) stream_upload_timeout: u64,
     pub(crate) transaction_watchdog_check_period: u64,
+    pub(crate) keepalive: Option<KeepaliveConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct VacuumConfig {
+    // Seconds between built-in vacuum runs.
+    pub(crate) interval: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct WebhookConfig {
+    // Seconds between delivery runs of the webhook outbox.
+    #[serde(default = "WebhookConfig::default_interval")]
+    pub(crate) interval: u64,
+    // Webhook endpoints by audience. Events of other audiences aren't enqueued.
+    #[serde(default)]
+    pub(crate) audiences: HashMap<String, WebhookAudienceConfig>,
+}
+
+impl WebhookConfig {
+    fn default_interval() -> u64 {
+        5
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub(crate) struct WebhookAudienceConfig {
+    pub(crate) url: String,
+    // HMAC-SHA256 key for the `X-Conference-Signature` header.
+    pub(crate) secret: String,
+    // Seconds to wait for the endpoint to respond.
+    #[serde(default = "WebhookAudienceConfig::default_timeout")]
+    pub(crate) timeout: u64,
+}
+
+impl WebhookAudienceConfig {
+    fn default_timeout() -> u64 {
+        5
+    }
+}
+
+// The config gets logged on start so keep the secret out of it.
+impl fmt::Debug for WebhookAudienceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WebhookAudienceConfig")
+            .field("url", &self.url)
+            .field("secret", &"[FILTERED]")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct KeepaliveConfig {
+    // Seconds between keepalive requests to each janus session.
+    pub(crate) period: u64,
+    // Number of keepalives in a row left unanswered to consider a backend offline.
+    pub(crate) max_misses: i32,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+pub(crate) struct BalancerConfig {
+    #[serde(default)]
+    pub(crate) strategy: BalancerStrategy,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BalancerStrategy {
+    // The most loaded backend capable to host the room's reserve.
+    BinPacking,
+    // The backend with the most free capacity.
+    LeastLoaded,
+    // Backends in turn regardless of their load.
+    RoundRobin,
+    // The least loaded backend relative to its balancer capacity.
+    Weighted,
+}
+
+impl Default for BalancerStrategy {
+    fn default() -> Self {
+        Self::BinPacking
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub(crate) struct HandleIdConfig {
+    pub(crate) key: String,
+    pub(crate) ttl: u64,
+}
+
+// The config gets logged on start so keep the signing key out of it.
+impl fmt::Debug for HandleIdConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HandleIdConfig")
+            .field("key", &"[FILTERED]")
+            .field("ttl", &self.ttl)
+            .finish()
+    }
 }
 
 pub(crate) type UploadConfigMap = HashMap<String, UploadConfig>;
@@ -53,6 +162,87 @@ pub(crate) type UploadConfigMap = HashMap<String, UploadConfig>;
 pub(crate) struct UploadConfig {
     pub(crate) backend: String,
     pub(crate) bucket: String,
+    // Recording object name in the bucket.
+    #[serde(
+        default = "UploadConfig::default_object",
+        deserialize_with = "crate::template::object_template"
+    )]
+    pub(crate) object: Template,
+    // Recording URI reported to the clients, may refer to the `{object}` name.
+    #[serde(
+        default = "UploadConfig::default_uri",
+        deserialize_with = "crate::template::uri_template"
+    )]
+    pub(crate) uri: Template,
+}
+
+impl UploadConfig {
+    fn default_object() -> Template {
+        Template::parse("{rtc_id}.source.webm").expect("Invalid default object template")
+    }
+
+    fn default_uri() -> Template {
+        Template::parse("s3://{bucket}/{object}").expect("Invalid default URI template")
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct MessageConfig {
+    // Labels of `message.broadcast` messages to store for `message.list`.
+    #[serde(default)]
+    pub(crate) persistent_labels: Vec<String>,
+    // Seconds to wait for a `message.callback` before replying to the `message.unicast` caller.
+    #[serde(default = "MessageConfig::default_unicast_timeout")]
+    pub(crate) unicast_timeout: u64,
+    #[serde(default)]
+    pub(crate) rate_limit: RateLimitConfig,
+}
+
+impl MessageConfig {
+    fn default_unicast_timeout() -> u64 {
+        10
+    }
+}
+
+impl Default for MessageConfig {
+    fn default() -> Self {
+        Self {
+            persistent_labels: vec![],
+            unicast_timeout: Self::default_unicast_timeout(),
+            rate_limit: RateLimitConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+pub(crate) struct RateLimitConfig {
+    // Applies to all messages unless overridden by audience or label. No limit when missing.
+    pub(crate) default: Option<TokenBucketConfig>,
+    #[serde(default)]
+    pub(crate) audiences: HashMap<String, TokenBucketConfig>,
+    #[serde(default)]
+    pub(crate) labels: HashMap<String, TokenBucketConfig>,
+}
+
+impl RateLimitConfig {
+    pub(crate) fn bucket_config(
+        &self,
+        audience: &str,
+        label: Option<&str>,
+    ) -> Option<&TokenBucketConfig> {
+        label
+            .and_then(|label| self.labels.get(label))
+            .or_else(|| self.audiences.get(audience))
+            .or_else(|| self.default.as_ref())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub(crate) struct TokenBucketConfig {
+    // Maximum burst of messages.
+    pub(crate) capacity: u32,
+    // Tokens added to the bucket per second.
+    pub(crate) refill_rate: f64,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -19,6 +19,7 @@ pub(crate) enum Status {
     InProgress,
     Ready,
     Connected,
+    Waiting,
 }
 
 #[derive(Debug, Serialize, Deserialize, Identifiable, Queryable, QueryableByName, Associations)]
@@ -33,8 +34,12 @@ pub(crate) struct Object {
     status: Status,
 }
 
-#[cfg(test)]
 impl Object {
+    pub(crate) fn agent_id(&self) -> &AgentId {
+        &self.agent_id
+    }
+
+    #[cfg(test)]
     pub(crate) fn status(&self) -> Status {
         self.status
     }
@@ -45,6 +50,7 @@ impl Object {
 pub(crate) struct ListQuery<'a> {
     agent_id: Option<&'a AgentId>,
     room_id: Option<Uuid>,
+    status: Option<Status>,
     offset: Option<i64>,
     limit: Option<i64>,
 }
@@ -54,6 +60,7 @@ impl<'a> ListQuery<'a> {
         Self {
             agent_id: None,
             room_id: None,
+            status: None,
             offset: None,
             limit: None,
         }
@@ -73,6 +80,13 @@ impl<'a> ListQuery<'a> {
         }
     }
 
+    pub(crate) fn status(self, status: Status) -> Self {
+        Self {
+            status: Some(status),
+            ..self
+        }
+    }
+
     pub(crate) fn offset(self, offset: i64) -> Self {
         Self {
             offset: Some(offset),
@@ -90,9 +104,13 @@ impl<'a> ListQuery<'a> {
     pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
         use diesel::prelude::*;
 
-        let mut q = agent::table
-            .into_boxed()
-            .filter(agent::status.eq_any(&[Status::Ready, Status::Connected]));
+        let mut q = agent::table.into_boxed();
+
+        // Only agents that have actually entered the room are listed by default.
+        q = match self.status {
+            Some(status) => q.filter(agent::status.eq(status)),
+            None => q.filter(agent::status.eq_any(&[Status::Ready, Status::Connected])),
+        };
 
         if let Some(agent_id) = self.agent_id {
             q = q.filter(agent::agent_id.eq(agent_id));
@@ -116,18 +134,48 @@ impl<'a> ListQuery<'a> {
 
 ///////////////////////////////////////////////////////////////////////////////
 
-pub(crate) struct CountQuery {
-    status: Option<Status>,
+pub(crate) struct CountQuery<'a> {
+    room_id: Option<Uuid>,
+    statuses: Vec<Status>,
+    in_progress_since: Option<DateTime<Utc>>,
+    except_agent_id: Option<&'a AgentId>,
 }
 
-impl CountQuery {
+impl<'a> CountQuery<'a> {
     pub(crate) fn new() -> Self {
-        Self { status: None }
+        Self {
+            room_id: None,
+            statuses: vec![],
+            in_progress_since: None,
+            except_agent_id: None,
+        }
     }
 
-    pub(crate) fn status(self, status: Status) -> Self {
+    pub(crate) fn room_id(self, room_id: Uuid) -> Self {
         Self {
-            status: Some(status),
+            room_id: Some(room_id),
+            ..self
+        }
+    }
+
+    // Multiple calls count agents in any of the given statuses.
+    pub(crate) fn status(mut self, status: Status) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    // Also counts agents in `in_progress` status that have started entering after the given time.
+    pub(crate) fn in_progress_since(self, since: DateTime<Utc>) -> Self {
+        Self {
+            in_progress_since: Some(since),
+            ..self
+        }
+    }
+
+    pub(crate) fn except_agent_id(self, agent_id: &'a AgentId) -> Self {
+        Self {
+            except_agent_id: Some(agent_id),
+            ..self
         }
     }
 
@@ -137,8 +185,26 @@ impl CountQuery {
 
         let mut query = agent::table.select(count(agent::id)).into_boxed();
 
-        if let Some(status) = self.status {
-            query = query.filter(agent::status.eq(status));
+        if let Some(room_id) = self.room_id {
+            query = query.filter(agent::room_id.eq(room_id));
+        }
+
+        match (self.statuses.is_empty(), self.in_progress_since) {
+            (true, None) => (),
+            (false, None) => {
+                query = query.filter(agent::status.eq_any(self.statuses.clone()));
+            }
+            (_, Some(since)) => {
+                let in_progress = agent::status
+                    .eq(Status::InProgress)
+                    .and(agent::created_at.gt(since));
+
+                query = query.filter(agent::status.eq_any(self.statuses.clone()).or(in_progress));
+            }
+        }
+
+        if let Some(agent_id) = self.except_agent_id {
+            query = query.filter(agent::agent_id.ne(agent_id));
         }
 
         query.get_result(conn)
@@ -166,7 +232,6 @@ impl<'a> InsertQuery<'a> {
         }
     }
 
-    #[cfg(test)]
     pub(crate) fn status(self, status: Status) -> Self {
         Self { status, ..self }
     }
@@ -175,11 +240,12 @@ impl<'a> InsertQuery<'a> {
         use crate::schema::agent::dsl::*;
         use diesel::{ExpressionMethods, RunQueryDsl};
 
+        // Entering the room again restarts the entrance.
         diesel::insert_into(agent)
             .values(self)
             .on_conflict((agent_id, room_id))
             .do_update()
-            .set(status.eq(Status::InProgress))
+            .set((status.eq(self.status), created_at.eq(diesel::dsl::now)))
             .get_result(conn)
     }
 }
@@ -225,6 +291,7 @@ impl<'a> UpdateQuery<'a> {
 
 #[derive(Debug)]
 pub(crate) struct BulkStatusUpdateQuery<'a> {
+    agent_id: Option<&'a AgentId>,
     room_id: Option<Uuid>,
     backend_id: Option<&'a AgentId>,
     status: Option<Status>,
@@ -234,6 +301,7 @@ pub(crate) struct BulkStatusUpdateQuery<'a> {
 impl<'a> BulkStatusUpdateQuery<'a> {
     pub(crate) fn new(new_status: Status) -> Self {
         Self {
+            agent_id: None,
             room_id: None,
             backend_id: None,
             status: None,
@@ -241,6 +309,13 @@ impl<'a> BulkStatusUpdateQuery<'a> {
         }
     }
 
+    pub(crate) fn agent_id(self, agent_id: &'a AgentId) -> Self {
+        Self {
+            agent_id: Some(agent_id),
+            ..self
+        }
+    }
+
     pub(crate) fn room_id(self, room_id: Uuid) -> Self {
         Self {
             room_id: Some(room_id),
@@ -268,6 +343,10 @@ impl<'a> BulkStatusUpdateQuery<'a> {
         conn.transaction::<_, Error, _>(|| {
             let mut query = diesel::update(agent::table).into_boxed();
 
+            if let Some(agent_id) = self.agent_id {
+                query = query.filter(agent::agent_id.eq(agent_id));
+            }
+
             if let Some(room_id) = self.room_id {
                 query = query.filter(agent::room_id.eq(room_id));
             }
@@ -298,6 +377,7 @@ impl<'a> BulkStatusUpdateQuery<'a> {
 pub(crate) struct DeleteQuery<'a> {
     agent_id: Option<&'a AgentId>,
     room_id: Option<Uuid>,
+    status: Option<Status>,
 }
 
 impl<'a> DeleteQuery<'a> {
@@ -305,6 +385,7 @@ impl<'a> DeleteQuery<'a> {
         Self {
             agent_id: None,
             room_id: None,
+            status: None,
         }
     }
 
@@ -322,6 +403,13 @@ impl<'a> DeleteQuery<'a> {
         }
     }
 
+    pub(crate) fn status(self, status: Status) -> Self {
+        Self {
+            status: Some(status),
+            ..self
+        }
+    }
+
     pub(crate) fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
         use diesel::prelude::*;
 
@@ -335,6 +423,10 @@ impl<'a> DeleteQuery<'a> {
             query = query.filter(agent::room_id.eq(room_id));
         }
 
+        if let Some(status) = self.status {
+            query = query.filter(agent::status.eq(status));
+        }
+
         query.execute(conn)
     }
 }
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use diesel::pg::PgConnection;
 use diesel::result::Error;
 use svc_agent::AgentId;
@@ -13,6 +13,11 @@ pub(crate) type AllColumns = (
     janus_backend::created_at,
     janus_backend::capacity,
     janus_backend::balancer_capacity,
+    janus_backend::draining,
+    janus_backend::group,
+    janus_backend::keepalive_misses,
+    janus_backend::keepalive_latency,
+    janus_backend::keepalive_sent_at,
 );
 
 pub(crate) const ALL_COLUMNS: AllColumns = (
@@ -22,6 +27,11 @@ pub(crate) const ALL_COLUMNS: AllColumns = (
     janus_backend::created_at,
     janus_backend::capacity,
     janus_backend::balancer_capacity,
+    janus_backend::draining,
+    janus_backend::group,
+    janus_backend::keepalive_misses,
+    janus_backend::keepalive_latency,
+    janus_backend::keepalive_sent_at,
 );
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -35,6 +45,11 @@ pub(crate) struct Object {
     created_at: DateTime<Utc>,
     capacity: Option<i32>,
     balancer_capacity: Option<i32>,
+    draining: bool,
+    group: Option<String>,
+    keepalive_misses: i32,
+    keepalive_latency: Option<i32>,
+    keepalive_sent_at: Option<DateTime<Utc>>,
 }
 
 impl Object {
@@ -49,12 +64,38 @@ impl Object {
     pub(crate) fn session_id(&self) -> i64 {
         self.session_id
     }
+
+    pub(crate) fn capacity(&self) -> Option<i32> {
+        self.capacity
+    }
+
+    pub(crate) fn balancer_capacity(&self) -> Option<i32> {
+        self.balancer_capacity
+    }
+
+    pub(crate) fn draining(&self) -> bool {
+        self.draining
+    }
+
+    pub(crate) fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    pub(crate) fn keepalive_misses(&self) -> i32 {
+        self.keepalive_misses
+    }
+
+    pub(crate) fn keepalive_latency(&self) -> Option<i32> {
+        self.keepalive_latency
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
 pub(crate) struct ListQuery<'a> {
     ids: Option<&'a [&'a AgentId]>,
+    draining: Option<bool>,
+    group: Option<&'a str>,
     offset: Option<i64>,
     limit: Option<i64>,
 }
@@ -63,6 +104,8 @@ impl<'a> ListQuery<'a> {
     pub(crate) fn new() -> Self {
         Self {
             ids: None,
+            draining: None,
+            group: None,
             offset: None,
             limit: None,
         }
@@ -75,6 +118,20 @@ impl<'a> ListQuery<'a> {
         }
     }
 
+    pub(crate) fn draining(self, draining: bool) -> Self {
+        Self {
+            draining: Some(draining),
+            ..self
+        }
+    }
+
+    pub(crate) fn group(self, group: &'a str) -> Self {
+        Self {
+            group: Some(group),
+            ..self
+        }
+    }
+
     pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
         use diesel::prelude::*;
 
@@ -82,13 +139,20 @@ impl<'a> ListQuery<'a> {
         if let Some(ids) = self.ids {
             q = q.filter(janus_backend::id.eq_any(ids))
         }
+        if let Some(draining) = self.draining {
+            q = q.filter(janus_backend::draining.eq(draining));
+        }
+        if let Some(group) = self.group {
+            q = q.filter(janus_backend::group.eq(group));
+        }
         if let Some(offset) = self.offset {
             q = q.offset(offset);
         }
         if let Some(limit) = self.limit {
             q = q.limit(limit);
         }
-        q.order_by(janus_backend::created_at).get_results(conn)
+        q.order_by((janus_backend::created_at, janus_backend::id))
+            .get_results(conn)
     }
 }
 
@@ -129,6 +193,8 @@ pub(crate) struct UpsertQuery<'a> {
     session_id: i64,
     capacity: Option<i32>,
     balancer_capacity: Option<i32>,
+    group: Option<&'a str>,
+    keepalive_misses: i32,
 }
 
 impl<'a> UpsertQuery<'a> {
@@ -139,6 +205,9 @@ impl<'a> UpsertQuery<'a> {
             session_id,
             capacity: None,
             balancer_capacity: None,
+            group: None,
+            // A fresh session starts with a clean keepalive record.
+            keepalive_misses: 0,
         }
     }
 
@@ -156,6 +225,13 @@ impl<'a> UpsertQuery<'a> {
         }
     }
 
+    pub(crate) fn group(self, group: &'a str) -> Self {
+        Self {
+            group: Some(group),
+            ..self
+        }
+    }
+
     pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
         use crate::schema::janus_backend::dsl::janus_backend;
         use diesel::RunQueryDsl;
@@ -171,6 +247,87 @@ impl<'a> UpsertQuery<'a> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, AsChangeset)]
+#[table_name = "janus_backend"]
+pub(crate) struct UpdateQuery<'a> {
+    id: &'a AgentId,
+    draining: Option<bool>,
+    keepalive_misses: Option<i32>,
+    keepalive_latency: Option<i32>,
+}
+
+impl<'a> UpdateQuery<'a> {
+    pub(crate) fn new(id: &'a AgentId) -> Self {
+        Self {
+            id,
+            draining: None,
+            keepalive_misses: None,
+            keepalive_latency: None,
+        }
+    }
+
+    pub(crate) fn draining(self, draining: bool) -> Self {
+        Self {
+            draining: Some(draining),
+            ..self
+        }
+    }
+
+    pub(crate) fn keepalive_misses(self, keepalive_misses: i32) -> Self {
+        Self {
+            keepalive_misses: Some(keepalive_misses),
+            ..self
+        }
+    }
+
+    pub(crate) fn keepalive_latency(self, keepalive_latency: i32) -> Self {
+        Self {
+            keepalive_latency: Some(keepalive_latency),
+            ..self
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        diesel::update(janus_backend::table.filter(janus_backend::id.eq(self.id)))
+            .set(self)
+            .get_result(conn)
+            .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Counts one more unanswered keepalive for every backend which hasn't been sent one
+// within the `period`. Returns updated backends.
+//
+// Since every replica runs the keepalive loop the period check makes only the first one
+// to get to a backend count the miss and send the keepalive request.
+pub(crate) fn increment_keepalive_misses(
+    period: Duration,
+    conn: &PgConnection,
+) -> Result<Vec<Object>, Error> {
+    use diesel::prelude::*;
+
+    let now = Utc::now();
+
+    diesel::update(
+        janus_backend::table.filter(
+            janus_backend::keepalive_sent_at
+                .is_null()
+                .or(janus_backend::keepalive_sent_at.le(now - period)),
+        ),
+    )
+    .set((
+        janus_backend::keepalive_misses.eq(janus_backend::keepalive_misses + 1),
+        janus_backend::keepalive_sent_at.eq(now),
+    ))
+    .get_results(conn)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 pub(crate) struct DeleteQuery<'a> {
     id: &'a AgentId,
 }
@@ -189,12 +346,14 @@ impl<'a> DeleteQuery<'a> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
-// Returns the most loaded backend capable to host the room with its reserve considering:
+// Load of each backend hosting in-progress recordings considering:
 // - room opening period;
 // - actual number of online agents;
-// - optional backend capacity;
 // - optional room reserve.
-const MOST_LOADED_SQL: &str = r#"
+// `taken` is the number of online agents and `load` also accounts the reserve.
+macro_rules! janus_backend_load_cte {
+    () => {
+        r#"
     WITH
         room_load AS (
             SELECT
@@ -213,7 +372,8 @@ const MOST_LOADED_SQL: &str = r#"
         janus_backend_load AS (
             SELECT
                 backend_id,
-                SUM(GREATEST(taken, reserve)) AS load
+                SUM(GREATEST(taken, reserve)) AS load,
+                SUM(taken)                    AS taken
             FROM (
                 SELECT DISTINCT ON(backend_id, room_id)
                     rec.backend_id,
@@ -231,6 +391,17 @@ const MOST_LOADED_SQL: &str = r#"
             ) AS sub
             GROUP BY backend_id
         )
+"#
+    };
+}
+
+// Returns the most loaded backend capable to host the room with its reserve
+// considering optional backend capacity.
+// Draining backends are skipped since they're being taken out of rotation.
+// When the group is specified only backends of this group are considered.
+const MOST_LOADED_SQL: &str = concat!(
+    janus_backend_load_cte!(),
+    r#"
     SELECT jb.*
     FROM janus_backend AS jb
     LEFT JOIN janus_backend_load AS jbl
@@ -238,58 +409,33 @@ const MOST_LOADED_SQL: &str = r#"
     LEFT JOIN room AS r2
     ON 1 = 1
     WHERE r2.id = $1
+    AND   NOT jb.draining
+    AND   ($2::TEXT IS NULL OR jb."group" = $2::TEXT)
     AND   COALESCE(jb.balancer_capacity, jb.capacity, 2147483647) - COALESCE(jbl.load, 0) >= COALESCE(r2.reserve, 0)
     ORDER BY COALESCE(jbl.load, 0) DESC
     LIMIT 1
-"#;
+"#
+);
 
-pub(crate) fn most_loaded(room_id: Uuid, conn: &PgConnection) -> Result<Option<Object>, Error> {
+pub(crate) fn most_loaded(
+    room_id: Uuid,
+    group: Option<&str>,
+    conn: &PgConnection,
+) -> Result<Option<Object>, Error> {
     use diesel::prelude::*;
-    use diesel::sql_types::Uuid;
+    use diesel::sql_types::{Nullable, Text, Uuid};
 
     diesel::sql_query(MOST_LOADED_SQL)
         .bind::<Uuid, _>(room_id)
+        .bind::<Nullable<Text>, _>(group)
         .get_result(conn)
         .optional()
 }
 
 // The same as above but finds the least loaded backend instead without considering the reserve.
-const LEAST_LOADED_SQL: &str = r#"
-    WITH
-        room_load AS (
-            SELECT
-                room_id,
-                COUNT(id) AS taken
-            FROM agent
-            WHERE status = 'connected'
-            GROUP BY room_id
-        ),
-        active_room AS (
-            SELECT *
-            FROM room
-            WHERE backend = 'janus'
-            AND   UPPER(time) BETWEEN NOW() AND NOW() + INTERVAL '1 day'
-        ),
-        janus_backend_load AS (
-            SELECT
-                backend_id,
-                SUM(taken) AS load
-            FROM (
-                SELECT DISTINCT ON(backend_id, room_id)
-                    rec.backend_id,
-                    rtc.room_id,
-                    COALESCE(rl.taken, 0) AS taken
-                FROM recording AS rec
-                INNER JOIN rtc
-                ON rtc.id = rec.rtc_id
-                LEFT JOIN active_room AS ar
-                ON ar.id = rtc.room_id
-                LEFT JOIN room_load AS rl
-                ON rl.room_id = rtc.room_id
-                WHERE rec.status = 'in_progress'
-            ) AS sub
-            GROUP BY backend_id
-        )
+const LEAST_LOADED_SQL: &str = concat!(
+    janus_backend_load_cte!(),
+    r#"
     SELECT jb.*
     FROM janus_backend AS jb
     LEFT JOIN janus_backend_load AS jbl
@@ -297,16 +443,61 @@ const LEAST_LOADED_SQL: &str = r#"
     LEFT JOIN room AS r2
     ON 1 = 1
     WHERE r2.id = $1
-    ORDER BY COALESCE(jb.balancer_capacity, jb.capacity, 2147483647) - COALESCE(jbl.load, 0) DESC
+    AND   NOT jb.draining
+    AND   ($2::TEXT IS NULL OR jb."group" = $2::TEXT)
+    ORDER BY COALESCE(jb.balancer_capacity, jb.capacity, 2147483647) - COALESCE(jbl.taken, 0) DESC
     LIMIT 1
-"#;
+"#
+);
 
-pub(crate) fn least_loaded(room_id: Uuid, conn: &PgConnection) -> Result<Option<Object>, Error> {
+pub(crate) fn least_loaded(
+    room_id: Uuid,
+    group: Option<&str>,
+    conn: &PgConnection,
+) -> Result<Option<Object>, Error> {
     use diesel::prelude::*;
-    use diesel::sql_types::Uuid;
+    use diesel::sql_types::{Nullable, Text, Uuid};
 
     diesel::sql_query(LEAST_LOADED_SQL)
         .bind::<Uuid, _>(room_id)
+        .bind::<Nullable<Text>, _>(group)
+        .get_result(conn)
+        .optional()
+}
+
+// Finds the backend with the least load relative to its capacity so that bigger backends
+// receive proportionally more rooms. Ties are resolved in favor of the bigger backend.
+const WEIGHTED_SQL: &str = concat!(
+    janus_backend_load_cte!(),
+    r#"
+    SELECT jb.*
+    FROM janus_backend AS jb
+    LEFT JOIN janus_backend_load AS jbl
+    ON jbl.backend_id = jb.id
+    LEFT JOIN room AS r2
+    ON 1 = 1
+    WHERE r2.id = $1
+    AND   NOT jb.draining
+    AND   ($2::TEXT IS NULL OR jb."group" = $2::TEXT)
+    ORDER BY
+        COALESCE(jbl.load, 0)::FLOAT
+            / NULLIF(COALESCE(jb.balancer_capacity, jb.capacity, 2147483647), 0) ASC,
+        COALESCE(jb.balancer_capacity, jb.capacity, 2147483647) DESC
+    LIMIT 1
+"#
+);
+
+pub(crate) fn weighted(
+    room_id: Uuid,
+    group: Option<&str>,
+    conn: &PgConnection,
+) -> Result<Option<Object>, Error> {
+    use diesel::prelude::*;
+    use diesel::sql_types::{Nullable, Text, Uuid};
+
+    diesel::sql_query(WEIGHTED_SQL)
+        .bind::<Uuid, _>(room_id)
+        .bind::<Nullable<Text>, _>(group)
         .get_result(conn)
         .optional()
 }
@@ -426,6 +617,16 @@ pub(crate) fn count(conn: &PgConnection) -> Result<i64, Error> {
         .get_result(conn)
 }
 
+pub(crate) fn draining_count(conn: &PgConnection) -> Result<i64, Error> {
+    use diesel::dsl::count;
+    use diesel::prelude::*;
+
+    janus_backend::table
+        .filter(janus_backend::draining.eq(true))
+        .select(count(janus_backend::id))
+        .get_result(conn)
+}
+
 #[derive(QueryableByName, Debug)]
 pub(crate) struct ReserveLoadQueryLoad {
     #[sql_type = "svc_agent::sql::Agent_id"]
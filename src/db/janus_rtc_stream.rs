@@ -8,7 +8,7 @@ use svc_agent::AgentId;
 use uuid::Uuid;
 
 use crate::db::rtc::Object as Rtc;
-use crate::schema::{janus_rtc_stream, rtc};
+use crate::schema::{agent, janus_rtc_stream, rtc};
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -25,6 +25,8 @@ type AllColumns = (
     janus_rtc_stream::sent_by,
     janus_rtc_stream::time,
     janus_rtc_stream::created_at,
+    janus_rtc_stream::audio_receiving,
+    janus_rtc_stream::video_receiving,
 );
 const ALL_COLUMNS: AllColumns = (
     janus_rtc_stream::id,
@@ -35,6 +37,8 @@ const ALL_COLUMNS: AllColumns = (
     janus_rtc_stream::sent_by,
     janus_rtc_stream::time,
     janus_rtc_stream::created_at,
+    janus_rtc_stream::audio_receiving,
+    janus_rtc_stream::video_receiving,
 );
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -53,6 +57,10 @@ pub(crate) struct Object {
     time: Option<Time>,
     #[serde(with = "ts_seconds")]
     created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audio_receiving: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    video_receiving: Option<bool>,
 }
 
 impl Object {
@@ -91,6 +99,16 @@ impl Object {
         self.created_at
     }
 
+    #[cfg(test)]
+    pub(crate) fn audio_receiving(&self) -> Option<bool> {
+        self.audio_receiving
+    }
+
+    #[cfg(test)]
+    pub(crate) fn video_receiving(&self) -> Option<bool> {
+        self.video_receiving
+    }
+
     pub(crate) fn set_time(&mut self, time: Option<Time>) -> &mut Self {
         self.time = time;
         self
@@ -99,11 +117,45 @@ impl Object {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug)]
+pub(crate) struct FindQuery {
+    id: Uuid,
+}
+
+impl FindQuery {
+    pub(crate) fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        janus_rtc_stream::table
+            .find(self.id)
+            .get_result(conn)
+            .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 const ACTIVE_SQL: &str = r#"(
     lower("janus_rtc_stream"."time") is not null
     and upper("janus_rtc_stream"."time") is null
 )"#;
 
+// Active streams and the ones that have been offered recently but haven't started yet.
+// Offers which never got to start are left unstopped so they expire after a while.
+const PUBLISHING_SQL: &str = r#"(
+    (
+        lower("janus_rtc_stream"."time") is not null
+        and upper("janus_rtc_stream"."time") is null
+    ) or (
+        "janus_rtc_stream"."time" is null
+        and "janus_rtc_stream"."created_at" > now() - interval '1 minute'
+    )
+)"#;
+
 #[derive(Debug, Default)]
 pub(crate) struct ListQuery {
     room_id: Option<Uuid>,
@@ -237,6 +289,10 @@ impl<'a> ListWithRtcQuery<'a> {
             Some(false) => q = q.filter(sql(&format!("not {}", ACTIVE_SQL))),
         }
 
+        if let Some(backend_id) = self.backend_id {
+            q = q.filter(janus_rtc_stream::backend_id.eq(backend_id));
+        }
+
         q.order_by(janus_rtc_stream::id)
             .select((self::ALL_COLUMNS, super::rtc::ALL_COLUMNS))
             .load(conn)
@@ -287,6 +343,78 @@ impl<'a> InsertQuery<'a> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+// Whether Janus is receiving audio and video on the publisher's handle.
+#[derive(Debug, AsChangeset)]
+#[table_name = "janus_rtc_stream"]
+pub(crate) struct UpdateMediaStateQuery {
+    id: Uuid,
+    audio_receiving: Option<bool>,
+    video_receiving: Option<bool>,
+}
+
+impl UpdateMediaStateQuery {
+    pub(crate) fn new(id: Uuid) -> Self {
+        Self {
+            id,
+            audio_receiving: None,
+            video_receiving: None,
+        }
+    }
+
+    pub(crate) fn audio_receiving(self, audio_receiving: bool) -> Self {
+        Self {
+            audio_receiving: Some(audio_receiving),
+            ..self
+        }
+    }
+
+    pub(crate) fn video_receiving(self, video_receiving: bool) -> Self {
+        Self {
+            video_receiving: Some(video_receiving),
+            ..self
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        diesel::update(janus_rtc_stream::table.filter(janus_rtc_stream::id.eq(self.id)))
+            .set(self)
+            .get_result(conn)
+            .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Counts agents other than the given one being in the room and having streams in it
+// which are either active or have been offered recently.
+pub(crate) fn count_publishers(
+    room_id: Uuid,
+    except_agent_id: &AgentId,
+    conn: &PgConnection,
+) -> Result<i64, Error> {
+    use diesel::dsl::sql;
+    use diesel::prelude::*;
+
+    let room_agent_ids = agent::table
+        .filter(agent::room_id.eq(room_id))
+        .select(agent::agent_id);
+
+    janus_rtc_stream::table
+        .inner_join(rtc::table)
+        .filter(rtc::room_id.eq(room_id))
+        .filter(sql(PUBLISHING_SQL))
+        .filter(janus_rtc_stream::sent_by.ne(except_agent_id))
+        .filter(janus_rtc_stream::sent_by.eq_any(room_agent_ids))
+        .select(janus_rtc_stream::sent_by)
+        .distinct()
+        .load::<AgentId>(conn)
+        .map(|agent_ids| agent_ids.len() as i64)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 const START_TIME_SQL: &str = "(TSTZRANGE(NOW(), NULL, '[)'))";
 
 pub(crate) fn start(id: Uuid, conn: &PgConnection) -> Result<Option<Object>, Error> {
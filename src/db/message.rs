@@ -0,0 +1,161 @@
+use std::ops::Bound;
+
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use svc_agent::AgentId;
+use uuid::Uuid;
+
+use super::room::{Object as Room, Time};
+use crate::schema::message;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Serialize, Deserialize, Identifiable, Queryable, QueryableByName, Associations)]
+#[belongs_to(Room, foreign_key = "room_id")]
+#[table_name = "message"]
+pub(crate) struct Object {
+    id: Uuid,
+    room_id: Uuid,
+    agent_id: AgentId,
+    label: String,
+    data: JsonValue,
+    #[serde(with = "ts_seconds")]
+    created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+impl Object {
+    pub(crate) fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub(crate) fn data(&self) -> &JsonValue {
+        &self.data
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct ListQuery<'a> {
+    room_id: Uuid,
+    label: Option<&'a str>,
+    time: Option<Time>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+impl<'a> ListQuery<'a> {
+    pub(crate) fn new(room_id: Uuid) -> Self {
+        Self {
+            room_id,
+            label: None,
+            time: None,
+            offset: None,
+            limit: None,
+        }
+    }
+
+    pub(crate) fn label(self, label: &'a str) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    pub(crate) fn time(self, time: Time) -> Self {
+        Self {
+            time: Some(time),
+            ..self
+        }
+    }
+
+    pub(crate) fn offset(self, offset: i64) -> Self {
+        Self {
+            offset: Some(offset),
+            ..self
+        }
+    }
+
+    pub(crate) fn limit(self, limit: i64) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+        use diesel::prelude::*;
+
+        let mut q = message::table
+            .filter(message::room_id.eq(self.room_id))
+            .into_boxed();
+
+        if let Some(label) = self.label {
+            q = q.filter(message::label.eq(label));
+        }
+
+        if let Some((start, end)) = self.time {
+            q = match start {
+                Bound::Included(start) => q.filter(message::created_at.ge(start)),
+                Bound::Excluded(start) => q.filter(message::created_at.gt(start)),
+                Bound::Unbounded => q,
+            };
+
+            q = match end {
+                Bound::Included(end) => q.filter(message::created_at.le(end)),
+                Bound::Excluded(end) => q.filter(message::created_at.lt(end)),
+                Bound::Unbounded => q,
+            };
+        }
+
+        if let Some(offset) = self.offset {
+            q = q.offset(offset);
+        }
+
+        if let Some(limit) = self.limit {
+            q = q.limit(limit);
+        }
+
+        q.order_by(message::created_at.desc()).get_results(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Insertable)]
+#[table_name = "message"]
+pub(crate) struct InsertQuery<'a> {
+    room_id: Uuid,
+    agent_id: &'a AgentId,
+    label: &'a str,
+    data: &'a JsonValue,
+}
+
+impl<'a> InsertQuery<'a> {
+    pub(crate) fn new(
+        room_id: Uuid,
+        agent_id: &'a AgentId,
+        label: &'a str,
+        data: &'a JsonValue,
+    ) -> Self {
+        Self {
+            room_id,
+            agent_id,
+            label,
+            data,
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
+        use diesel::prelude::*;
+
+        diesel::insert_into(message::table)
+            .values(self)
+            .get_result(conn)
+    }
+}
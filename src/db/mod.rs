@@ -28,6 +28,7 @@ pub mod sql {
     pub use super::agent::Agent_status;
     pub use super::recording::Recording_status;
     pub use super::room::Room_backend;
+    pub use super::room_role::Agent_role;
     pub use svc_agent::sql::{Account_id, Agent_id};
 }
 
@@ -35,6 +36,11 @@ pub(crate) mod agent;
 pub(crate) mod agent_stream;
 pub(crate) mod janus_backend;
 pub(crate) mod janus_rtc_stream;
+pub(crate) mod message;
 pub(crate) mod recording;
 pub(crate) mod room;
+pub(crate) mod room_ban;
+pub(crate) mod room_role;
 pub(crate) mod rtc;
+pub(crate) mod unicast_call;
+pub(crate) mod webhook;
@@ -19,6 +19,8 @@ pub(crate) type AllColumns = (
     recording::segments,
     recording::status,
     recording::backend_id,
+    recording::attempts,
+    recording::next_attempt_at,
 );
 
 pub(crate) const ALL_COLUMNS: AllColumns = (
@@ -27,6 +29,8 @@ pub(crate) const ALL_COLUMNS: AllColumns = (
     recording::segments,
     recording::status,
     recording::backend_id,
+    recording::attempts,
+    recording::next_attempt_at,
 );
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -42,6 +46,7 @@ pub(crate) enum Status {
     InProgress,
     Ready,
     Missing,
+    Failed,
 }
 
 impl fmt::Display for Status {
@@ -65,6 +70,9 @@ pub(crate) struct Object {
     segments: Option<Vec<Segment>>,
     status: Status,
     backend_id: AgentId,
+    attempts: i32,
+    #[serde(with = "crate::serde::ts_seconds_option")]
+    next_attempt_at: Option<DateTime<Utc>>,
 }
 
 impl Object {
@@ -87,6 +95,10 @@ impl Object {
     pub(crate) fn backend_id(&self) -> &AgentId {
         &self.backend_id
     }
+
+    pub(crate) fn attempts(&self) -> i32 {
+        self.attempts
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -113,6 +125,60 @@ impl FindQuery {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug)]
+pub(crate) struct ListQuery {
+    room_id: Uuid,
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+impl ListQuery {
+    pub(crate) fn new(room_id: Uuid) -> Self {
+        Self {
+            room_id,
+            offset: None,
+            limit: None,
+        }
+    }
+
+    pub(crate) fn offset(self, offset: i64) -> Self {
+        Self {
+            offset: Some(offset),
+            ..self
+        }
+    }
+
+    pub(crate) fn limit(self, limit: i64) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+        use crate::schema::rtc;
+        use diesel::prelude::*;
+
+        let mut q = recording::table
+            .inner_join(rtc::table)
+            .filter(rtc::room_id.eq(self.room_id))
+            .select(ALL_COLUMNS)
+            .into_boxed();
+
+        if let Some(offset) = self.offset {
+            q = q.offset(offset);
+        }
+
+        if let Some(limit) = self.limit {
+            q = q.limit(limit);
+        }
+
+        q.order_by(rtc::created_at.desc()).get_results(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Insertable)]
 #[table_name = "recording"]
 pub(crate) struct InsertQuery<'a> {
@@ -146,6 +212,8 @@ pub(crate) struct UpdateQuery {
     status: Option<Status>,
     started_at: Option<DateTime<Utc>>,
     segments: Option<Vec<Segment>>,
+    attempts: Option<i32>,
+    next_attempt_at: Option<Option<DateTime<Utc>>>,
 }
 
 impl UpdateQuery {
@@ -155,6 +223,8 @@ impl UpdateQuery {
             status: None,
             started_at: None,
             segments: None,
+            attempts: None,
+            next_attempt_at: None,
         }
     }
 
@@ -179,9 +249,45 @@ impl UpdateQuery {
         }
     }
 
+    pub(crate) fn attempts(self, attempts: i32) -> Self {
+        Self {
+            attempts: Some(attempts),
+            ..self
+        }
+    }
+
+    pub(crate) fn next_attempt_at(self, next_attempt_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            next_attempt_at: Some(next_attempt_at),
+            ..self
+        }
+    }
+
     pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
         use diesel::prelude::*;
 
         diesel::update(self).set(self).get_result(conn)
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Re-reads the recording locking it until the end of the current transaction without waiting.
+// Returns `None` when another transaction, possibly on another replica, already holds the lock
+// or the recording is no longer due for upload since it has been read.
+pub(crate) fn lock_for_upload(rtc_id: Uuid, conn: &PgConnection) -> Result<Option<Object>, Error> {
+    use diesel::prelude::*;
+
+    recording::table
+        .filter(recording::rtc_id.eq(rtc_id))
+        .filter(recording::status.eq(Status::InProgress))
+        .filter(
+            recording::next_attempt_at
+                .is_null()
+                .or(recording::next_attempt_at.le(Utc::now())),
+        )
+        .for_update()
+        .skip_locked()
+        .get_result(conn)
+        .optional()
+}
@@ -25,6 +25,11 @@ type AllColumns = (
     room::backend,
     room::reserve,
     room::tags,
+    room::backend_group,
+    room::max_agents,
+    room::max_publishers,
+    room::lobby,
+    room::parent_id,
 );
 
 const ALL_COLUMNS: AllColumns = (
@@ -35,6 +40,11 @@ const ALL_COLUMNS: AllColumns = (
     room::backend,
     room::reserve,
     room::tags,
+    room::backend_group,
+    room::max_agents,
+    room::max_publishers,
+    room::lobby,
+    room::parent_id,
 );
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -70,6 +80,15 @@ pub(crate) struct Object {
     #[serde(skip_serializing_if = "Option::is_none")]
     reserve: Option<i32>,
     tags: JsonValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    backend_group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_agents: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_publishers: Option<i32>,
+    lobby: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_id: Option<Uuid>,
 }
 
 impl Object {
@@ -85,6 +104,10 @@ impl Object {
         &self.time
     }
 
+    pub(crate) fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
     pub(crate) fn backend(&self) -> RoomBackend {
         self.backend
     }
@@ -104,6 +127,26 @@ impl Object {
     pub(crate) fn tags(&self) -> &JsonValue {
         &self.tags
     }
+
+    pub(crate) fn backend_group(&self) -> Option<&str> {
+        self.backend_group.as_deref()
+    }
+
+    pub(crate) fn max_agents(&self) -> Option<i32> {
+        self.max_agents
+    }
+
+    pub(crate) fn max_publishers(&self) -> Option<i32> {
+        self.max_publishers
+    }
+
+    pub(crate) fn lobby(&self) -> bool {
+        self.lobby
+    }
+
+    pub(crate) fn parent_id(&self) -> Option<Uuid> {
+        self.parent_id
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -158,6 +201,144 @@ impl FindQueryable for FindByRtcIdQuery {
     }
 }
 
+// Locks the room row until the end of the current transaction.
+pub(crate) fn lock(id: Uuid, conn: &PgConnection) -> Result<Option<Object>, Error> {
+    use diesel::prelude::*;
+
+    room::table
+        .filter(room::id.eq(id))
+        .for_update()
+        .get_result(conn)
+        .optional()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+const CLOSED_SQL: &str = r#"(
+    upper("room"."time") is not null
+    and upper("room"."time") < now()
+)"#;
+
+#[derive(Debug, Default)]
+pub(crate) struct ListQuery {
+    audience: Option<String>,
+    time: Option<Time>,
+    backend: Option<RoomBackend>,
+    closed: Option<bool>,
+    tags: Option<JsonValue>,
+    parent_id: Option<Uuid>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+impl ListQuery {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn audience(self, audience: &str) -> Self {
+        Self {
+            audience: Some(audience.to_owned()),
+            ..self
+        }
+    }
+
+    pub(crate) fn time(self, time: Time) -> Self {
+        Self {
+            time: Some(time),
+            ..self
+        }
+    }
+
+    pub(crate) fn backend(self, backend: RoomBackend) -> Self {
+        Self {
+            backend: Some(backend),
+            ..self
+        }
+    }
+
+    pub(crate) fn closed(self, closed: bool) -> Self {
+        Self {
+            closed: Some(closed),
+            ..self
+        }
+    }
+
+    pub(crate) fn tags(self, tags: JsonValue) -> Self {
+        Self {
+            tags: Some(tags),
+            ..self
+        }
+    }
+
+    pub(crate) fn parent_id(self, parent_id: Uuid) -> Self {
+        Self {
+            parent_id: Some(parent_id),
+            ..self
+        }
+    }
+
+    pub(crate) fn offset(self, offset: i64) -> Self {
+        Self {
+            offset: Some(offset),
+            ..self
+        }
+    }
+
+    pub(crate) fn limit(self, limit: i64) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+        use diesel::prelude::*;
+        use diesel::{
+            dsl::sql,
+            sql_types::{Jsonb, Tstzrange},
+        };
+
+        let mut q = room::table.into_boxed();
+
+        if let Some(ref audience) = self.audience {
+            q = q.filter(room::audience.eq(audience));
+        }
+
+        if let Some(time) = self.time {
+            q = q.filter(sql("\"room\".\"time\" && ").bind::<Tstzrange, _>(time));
+        }
+
+        if let Some(backend) = self.backend {
+            q = q.filter(room::backend.eq(backend));
+        }
+
+        match self.closed {
+            None => (),
+            Some(true) => q = q.filter(sql(CLOSED_SQL)),
+            Some(false) => q = q.filter(sql(&format!("not {}", CLOSED_SQL))),
+        }
+
+        if let Some(ref tags) = self.tags {
+            q = q.filter(sql("\"room\".\"tags\"::jsonb @> ").bind::<Jsonb, _>(tags.to_owned()));
+        }
+
+        if let Some(parent_id) = self.parent_id {
+            q = q.filter(room::parent_id.eq(parent_id));
+        }
+
+        if let Some(offset) = self.offset {
+            q = q.offset(offset);
+        }
+
+        if let Some(limit) = self.limit {
+            q = q.limit(limit);
+        }
+
+        q.order_by(room::created_at.desc()).get_results(conn)
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 // Filtering out rooms with every recording ready using left and inner joins
@@ -169,12 +350,13 @@ impl FindQueryable for FindByRtcIdQuery {
 // room2 | rtc3 | recording2     room2 | rtc3 | recording2
 // room3 | rtc4 | null           room3 | null | null
 pub(crate) fn finished_with_in_progress_recordings(
+    room_id: Option<Uuid>,
     conn: &PgConnection,
 ) -> Result<Vec<(Object, Recording, JanusBackend)>, Error> {
     use crate::schema;
     use diesel::{dsl::sql, prelude::*};
 
-    schema::room::table
+    let mut q = schema::room::table
         .inner_join(
             schema::rtc::table.inner_join(
                 schema::recording::table.inner_join(
@@ -186,6 +368,44 @@ pub(crate) fn finished_with_in_progress_recordings(
         .filter(room::backend.eq(RoomBackend::Janus))
         .filter(sql("upper(\"room\".\"time\") < now()"))
         .filter(schema::recording::status.eq(RecordingStatus::InProgress))
+        // Skip recordings being uploaded at the moment or waiting for a retry.
+        .filter(
+            schema::recording::next_attempt_at
+                .is_null()
+                .or(schema::recording::next_attempt_at.le(Utc::now())),
+        )
+        .select((
+            self::ALL_COLUMNS,
+            super::recording::ALL_COLUMNS,
+            super::janus_backend::ALL_COLUMNS,
+        ))
+        .into_boxed();
+
+    if let Some(room_id) = room_id {
+        q = q.filter(room::id.eq(room_id));
+    }
+
+    q.load(conn)
+}
+
+pub(crate) fn in_progress_recordings(
+    room_id: Uuid,
+    conn: &PgConnection,
+) -> Result<Vec<(Object, Recording, JanusBackend)>, Error> {
+    use crate::schema;
+    use diesel::prelude::*;
+
+    schema::room::table
+        .inner_join(
+            schema::rtc::table.inner_join(
+                schema::recording::table.inner_join(
+                    schema::janus_backend::table
+                        .on(schema::janus_backend::id.eq(schema::recording::backend_id)),
+                ),
+            ),
+        )
+        .filter(room::id.eq(room_id))
+        .filter(schema::recording::status.eq(RecordingStatus::InProgress))
         .select((
             self::ALL_COLUMNS,
             super::recording::ALL_COLUMNS,
@@ -204,6 +424,11 @@ pub(crate) struct InsertQuery<'a> {
     backend: RoomBackend,
     reserve: Option<i32>,
     tags: Option<&'a JsonValue>,
+    backend_group: Option<&'a str>,
+    max_agents: Option<i32>,
+    max_publishers: Option<i32>,
+    lobby: Option<bool>,
+    parent_id: Option<Uuid>,
 }
 
 impl<'a> InsertQuery<'a> {
@@ -214,6 +439,11 @@ impl<'a> InsertQuery<'a> {
             backend,
             reserve: None,
             tags: None,
+            backend_group: None,
+            max_agents: None,
+            max_publishers: None,
+            lobby: None,
+            parent_id: None,
         }
     }
 
@@ -231,6 +461,41 @@ impl<'a> InsertQuery<'a> {
         }
     }
 
+    pub(crate) fn backend_group(self, value: &'a str) -> Self {
+        Self {
+            backend_group: Some(value),
+            ..self
+        }
+    }
+
+    pub(crate) fn max_agents(self, value: i32) -> Self {
+        Self {
+            max_agents: Some(value),
+            ..self
+        }
+    }
+
+    pub(crate) fn max_publishers(self, value: i32) -> Self {
+        Self {
+            max_publishers: Some(value),
+            ..self
+        }
+    }
+
+    pub(crate) fn lobby(self, value: bool) -> Self {
+        Self {
+            lobby: Some(value),
+            ..self
+        }
+    }
+
+    pub(crate) fn parent_id(self, value: Uuid) -> Self {
+        Self {
+            parent_id: Some(value),
+            ..self
+        }
+    }
+
     pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
         use crate::schema::room::dsl::room;
         use diesel::RunQueryDsl;
@@ -268,6 +533,10 @@ pub(crate) struct UpdateQuery {
     backend: Option<RoomBackend>,
     reserve: Option<Option<i32>>,
     tags: Option<JsonValue>,
+    backend_group: Option<Option<String>>,
+    max_agents: Option<Option<i32>>,
+    max_publishers: Option<Option<i32>>,
+    lobby: Option<bool>,
 }
 
 impl UpdateQuery {
@@ -279,6 +548,10 @@ impl UpdateQuery {
             backend: None,
             reserve: None,
             tags: None,
+            backend_group: None,
+            max_agents: None,
+            max_publishers: None,
+            lobby: None,
         }
     }
 
@@ -302,6 +575,28 @@ impl UpdateQuery {
         Self { tags, ..self }
     }
 
+    pub(crate) fn backend_group(self, backend_group: Option<Option<String>>) -> Self {
+        Self {
+            backend_group,
+            ..self
+        }
+    }
+
+    pub(crate) fn max_agents(self, max_agents: Option<Option<i32>>) -> Self {
+        Self { max_agents, ..self }
+    }
+
+    pub(crate) fn max_publishers(self, max_publishers: Option<Option<i32>>) -> Self {
+        Self {
+            max_publishers,
+            ..self
+        }
+    }
+
+    pub(crate) fn lobby(self, lobby: Option<bool>) -> Self {
+        Self { lobby, ..self }
+    }
+
     pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
         use diesel::prelude::*;
 
@@ -340,7 +635,7 @@ mod tests {
             shared_helpers::insert_recording(&conn, &rtc1, &backend1);
             shared_helpers::insert_recording(&conn, &rtc2, &backend2);
 
-            let rooms = finished_with_in_progress_recordings(&conn)
+            let rooms = finished_with_in_progress_recordings(None, &conn)
                 .expect("finished_with_in_progress_recordings call failed");
 
             assert_eq!(rooms.len(), 2);
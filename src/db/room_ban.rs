@@ -0,0 +1,79 @@
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use serde_derive::{Deserialize, Serialize};
+use svc_agent::AccountId;
+use uuid::Uuid;
+
+use super::room::Object as Room;
+use crate::schema::room_ban;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Serialize, Deserialize, Identifiable, Queryable, QueryableByName, Associations)]
+#[belongs_to(Room, foreign_key = "room_id")]
+#[table_name = "room_ban"]
+#[primary_key(room_id, account_id)]
+pub(crate) struct Object {
+    room_id: Uuid,
+    account_id: AccountId,
+    #[serde(with = "ts_seconds")]
+    created_at: DateTime<Utc>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct FindQuery<'a> {
+    room_id: Uuid,
+    account_id: &'a AccountId,
+}
+
+impl<'a> FindQuery<'a> {
+    pub(crate) fn new(room_id: Uuid, account_id: &'a AccountId) -> Self {
+        Self {
+            room_id,
+            account_id,
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        room_ban::table
+            .filter(room_ban::room_id.eq(self.room_id))
+            .filter(room_ban::account_id.eq(self.account_id))
+            .get_result(conn)
+            .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Insertable)]
+#[table_name = "room_ban"]
+pub(crate) struct InsertQuery<'a> {
+    room_id: Uuid,
+    account_id: &'a AccountId,
+}
+
+impl<'a> InsertQuery<'a> {
+    pub(crate) fn new(room_id: Uuid, account_id: &'a AccountId) -> Self {
+        Self {
+            room_id,
+            account_id,
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        // Banning an already banned account is a no-op.
+        diesel::insert_into(room_ban::table)
+            .values(self)
+            .on_conflict((room_ban::room_id, room_ban::account_id))
+            .do_nothing()
+            .execute(conn)
+    }
+}
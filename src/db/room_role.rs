@@ -0,0 +1,152 @@
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use serde_derive::{Deserialize, Serialize};
+use svc_agent::AccountId;
+use uuid::Uuid;
+
+use super::room::Object as Room;
+use crate::schema::room_role;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, DbEnum, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[PgType = "agent_role"]
+#[DieselType = "Agent_role"]
+pub(crate) enum Role {
+    Host,
+    Speaker,
+    Listener,
+}
+
+#[derive(
+    Clone, Debug, Serialize, Deserialize, Identifiable, Queryable, QueryableByName, Associations,
+)]
+#[belongs_to(Room, foreign_key = "room_id")]
+#[table_name = "room_role"]
+#[primary_key(room_id, account_id)]
+pub(crate) struct Object {
+    room_id: Uuid,
+    account_id: AccountId,
+    role: Role,
+    #[serde(with = "ts_seconds")]
+    created_at: DateTime<Utc>,
+}
+
+impl Object {
+    pub(crate) fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    pub(crate) fn role(&self) -> Role {
+        self.role
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct FindQuery<'a> {
+    room_id: Uuid,
+    account_id: &'a AccountId,
+}
+
+impl<'a> FindQuery<'a> {
+    pub(crate) fn new(room_id: Uuid, account_id: &'a AccountId) -> Self {
+        Self {
+            room_id,
+            account_id,
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        room_role::table
+            .filter(room_role::room_id.eq(self.room_id))
+            .filter(room_role::account_id.eq(self.account_id))
+            .get_result(conn)
+            .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct ListQuery {
+    room_id: Uuid,
+}
+
+impl ListQuery {
+    pub(crate) fn new(room_id: Uuid) -> Self {
+        Self { room_id }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+        use diesel::prelude::*;
+
+        room_role::table
+            .filter(room_role::room_id.eq(self.room_id))
+            .get_results(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Insertable)]
+#[table_name = "room_role"]
+pub(crate) struct UpsertQuery<'a> {
+    room_id: Uuid,
+    account_id: &'a AccountId,
+    role: Role,
+}
+
+impl<'a> UpsertQuery<'a> {
+    pub(crate) fn new(room_id: Uuid, account_id: &'a AccountId, role: Role) -> Self {
+        Self {
+            room_id,
+            account_id,
+            role,
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
+        use diesel::prelude::*;
+
+        // An account has a single role in the room so granting another one replaces it.
+        diesel::insert_into(room_role::table)
+            .values(self)
+            .on_conflict((room_role::room_id, room_role::account_id))
+            .do_update()
+            .set(room_role::role.eq(self.role))
+            .get_result(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct DeleteQuery<'a> {
+    room_id: Uuid,
+    account_id: &'a AccountId,
+}
+
+impl<'a> DeleteQuery<'a> {
+    pub(crate) fn new(room_id: Uuid, account_id: &'a AccountId) -> Self {
+        Self {
+            room_id,
+            account_id,
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        diesel::delete(room_role::table)
+            .filter(room_role::room_id.eq(self.room_id))
+            .filter(room_role::account_id.eq(self.account_id))
+            .execute(conn)
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::{DateTime, Utc};
+use diesel::{pg::PgConnection, result::Error};
+use serde_json::Value as JsonValue;
+
+use crate::schema::unicast_call;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "unicast_call"]
+#[primary_key(correlation_data)]
+pub(crate) struct Object {
+    correlation_data: String,
+    reqp: JsonValue,
+    started_at: DateTime<Utc>,
+    deadline: DateTime<Utc>,
+}
+
+impl Object {
+    pub(crate) fn correlation_data(&self) -> &str {
+        &self.correlation_data
+    }
+
+    pub(crate) fn reqp(&self) -> &JsonValue {
+        &self.reqp
+    }
+
+    pub(crate) fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    pub(crate) fn deadline(&self) -> DateTime<Utc> {
+        self.deadline
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Insertable)]
+#[table_name = "unicast_call"]
+pub(crate) struct InsertQuery<'a> {
+    correlation_data: &'a str,
+    reqp: JsonValue,
+    started_at: DateTime<Utc>,
+    deadline: DateTime<Utc>,
+}
+
+impl<'a> InsertQuery<'a> {
+    pub(crate) fn new(
+        correlation_data: &'a str,
+        reqp: JsonValue,
+        started_at: DateTime<Utc>,
+        deadline: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            correlation_data,
+            reqp,
+            started_at,
+            deadline,
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        diesel::insert_into(unicast_call::table)
+            .values(self)
+            .on_conflict_do_nothing()
+            .execute(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct DeleteQuery<'a> {
+    correlation_data: &'a str,
+}
+
+impl<'a> DeleteQuery<'a> {
+    pub(crate) fn new(correlation_data: &'a str) -> Self {
+        Self { correlation_data }
+    }
+
+    /// Returns the deleted call or `None` if it has already been finished or timed out
+    /// by another replica.
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        diesel::delete(
+            unicast_call::table.filter(unicast_call::correlation_data.eq(self.correlation_data)),
+        )
+        .get_result(conn)
+        .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Deletes the calls past their deadline and returns them.
+///
+/// A row can be deleted only once so each timed out call is returned to a single replica
+/// and only if a callback to it hasn't been handled yet.
+pub(crate) fn delete_expired(
+    now: DateTime<Utc>,
+    conn: &PgConnection,
+) -> Result<Vec<Object>, Error> {
+    use diesel::prelude::*;
+
+    diesel::delete(unicast_call::table.filter(unicast_call::deadline.lt(now))).get_results(conn)
+}
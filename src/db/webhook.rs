@@ -0,0 +1,179 @@
+use chrono::{DateTime, Utc};
+use diesel::{pg::PgConnection, result::Error};
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
+
+use crate::schema::webhook;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "webhook"]
+pub(crate) struct Object {
+    id: Uuid,
+    audience: String,
+    label: String,
+    payload: JsonValue,
+    attempts: i32,
+    next_attempt_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+}
+
+impl Object {
+    pub(crate) fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub(crate) fn audience(&self) -> &str {
+        &self.audience
+    }
+
+    pub(crate) fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub(crate) fn payload(&self) -> &JsonValue {
+        &self.payload
+    }
+
+    pub(crate) fn attempts(&self) -> i32 {
+        self.attempts
+    }
+
+    pub(crate) fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[cfg(test)]
+impl Object {
+    pub(crate) fn next_attempt_at(&self) -> DateTime<Utc> {
+        self.next_attempt_at
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct DueListQuery {
+    limit: i64,
+}
+
+impl DueListQuery {
+    pub(crate) fn new(limit: i64) -> Self {
+        Self { limit }
+    }
+
+    /// Returns webhooks due for delivery locking them until the end of the transaction.
+    /// Webhooks locked by another replica are skipped.
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+        use diesel::prelude::*;
+
+        webhook::table
+            .filter(webhook::next_attempt_at.le(Utc::now()))
+            .order_by(webhook::next_attempt_at.asc())
+            .limit(self.limit)
+            .for_update()
+            .skip_locked()
+            .get_results(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Insertable)]
+#[table_name = "webhook"]
+pub(crate) struct InsertQuery<'a> {
+    audience: &'a str,
+    label: &'a str,
+    payload: JsonValue,
+}
+
+impl<'a> InsertQuery<'a> {
+    pub(crate) fn new(audience: &'a str, label: &'a str, payload: JsonValue) -> Self {
+        Self {
+            audience,
+            label,
+            payload,
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
+        use diesel::RunQueryDsl;
+
+        diesel::insert_into(webhook::table)
+            .values(self)
+            .get_result(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Identifiable, AsChangeset)]
+#[table_name = "webhook"]
+pub(crate) struct UpdateQuery {
+    id: Uuid,
+    attempts: Option<i32>,
+    next_attempt_at: Option<DateTime<Utc>>,
+}
+
+impl UpdateQuery {
+    pub(crate) fn new(id: Uuid) -> Self {
+        Self {
+            id,
+            attempts: None,
+            next_attempt_at: None,
+        }
+    }
+
+    pub(crate) fn attempts(self, attempts: i32) -> Self {
+        Self {
+            attempts: Some(attempts),
+            ..self
+        }
+    }
+
+    pub(crate) fn next_attempt_at(self, next_attempt_at: DateTime<Utc>) -> Self {
+        Self {
+            next_attempt_at: Some(next_attempt_at),
+            ..self
+        }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
+        use diesel::prelude::*;
+
+        diesel::update(self).set(self).get_result(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct DeleteQuery {
+    id: Uuid,
+}
+
+impl DeleteQuery {
+    pub(crate) fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+
+    pub(crate) fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        diesel::delete(webhook::table.filter(webhook::id.eq(self.id))).execute(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Returns the enqueue time of the oldest undelivered webhook.
+pub(crate) fn oldest_created_at(conn: &PgConnection) -> Result<Option<DateTime<Utc>>, Error> {
+    use diesel::dsl::min;
+    use diesel::prelude::*;
+
+    webhook::table
+        .select(min(webhook::created_at))
+        .get_result(conn)
+}
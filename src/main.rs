@@ -95,6 +95,7 @@ mod db;
 #[allow(unused_imports)]
 mod schema;
 mod serde;
+mod template;
 #[cfg(test)]
 mod test_helpers;
 mod util;
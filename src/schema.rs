@@ -34,6 +34,11 @@ table! {
         created_at -> Timestamptz,
         capacity -> Nullable<Int4>,
         balancer_capacity -> Nullable<Int4>,
+        draining -> Bool,
+        group -> Nullable<Text>,
+        keepalive_misses -> Int4,
+        keepalive_latency -> Nullable<Int4>,
+        keepalive_sent_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -50,6 +55,22 @@ table! {
         sent_by -> Agent_id,
         time -> Nullable<Tstzrange>,
         created_at -> Timestamptz,
+        audio_receiving -> Nullable<Bool>,
+        video_receiving -> Nullable<Bool>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    message (id) {
+        id -> Uuid,
+        room_id -> Uuid,
+        agent_id -> Agent_id,
+        label -> Text,
+        data -> Jsonb,
+        created_at -> Timestamptz,
     }
 }
 
@@ -63,6 +84,8 @@ table! {
         segments -> Nullable<Array<Int8range>>,
         status -> Recording_status,
         backend_id -> Agent_id,
+        attempts -> Int4,
+        next_attempt_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -78,6 +101,34 @@ table! {
         backend -> Room_backend,
         reserve -> Nullable<Int4>,
         tags -> Json,
+        backend_group -> Nullable<Text>,
+        max_agents -> Nullable<Int4>,
+        max_publishers -> Nullable<Int4>,
+        lobby -> Bool,
+        parent_id -> Nullable<Uuid>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    room_ban (room_id, account_id) {
+        room_id -> Uuid,
+        account_id -> Account_id,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    room_role (room_id, account_id) {
+        room_id -> Uuid,
+        account_id -> Account_id,
+        role -> Agent_role,
+        created_at -> Timestamptz,
     }
 }
 
@@ -92,11 +143,41 @@ table! {
     }
 }
 
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    unicast_call (correlation_data) {
+        correlation_data -> Text,
+        reqp -> Jsonb,
+        started_at -> Timestamptz,
+        deadline -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    webhook (id) {
+        id -> Uuid,
+        audience -> Text,
+        label -> Text,
+        payload -> Jsonb,
+        attempts -> Int4,
+        next_attempt_at -> Timestamptz,
+        created_at -> Timestamptz,
+    }
+}
+
 joinable!(agent -> room (room_id));
 joinable!(agent_stream -> agent (sent_by));
 joinable!(janus_rtc_stream -> janus_backend (backend_id));
 joinable!(janus_rtc_stream -> rtc (rtc_id));
+joinable!(message -> room (room_id));
 joinable!(recording -> rtc (rtc_id));
+joinable!(room_ban -> room (room_id));
+joinable!(room_role -> room (room_id));
 joinable!(rtc -> room (room_id));
 
 allow_tables_to_appear_in_same_query!(
@@ -104,7 +185,12 @@ allow_tables_to_appear_in_same_query!(
     agent_stream,
     janus_backend,
     janus_rtc_stream,
+    message,
     recording,
     room,
+    room_ban,
+    room_role,
     rtc,
+    unicast_call,
+    webhook,
 );
@@ -0,0 +1,201 @@
+use std::fmt;
+
+use anyhow::Result;
+use serde::de::{self, Deserialize, Deserializer};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A value to substitute in place of `{placeholder}` in a template.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Placeholder {
+    Audience,
+    Bucket,
+    Date,
+    Object,
+    RoomId,
+    RtcId,
+    Tag(String),
+}
+
+impl Placeholder {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "audience" => Ok(Self::Audience),
+            "bucket" => Ok(Self::Bucket),
+            "date" => Ok(Self::Date),
+            "object" => Ok(Self::Object),
+            "room_id" => Ok(Self::RoomId),
+            "rtc_id" => Ok(Self::RtcId),
+            _ => match name.strip_prefix("tags.") {
+                Some(key) if !key.is_empty() => Ok(Self::Tag(key.to_owned())),
+                _ => Err(anyhow!("unknown placeholder '{{{}}}'", name)),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A string with `{placeholder}` substitutions parsed upfront so that
+/// a malformed template gets rejected while loading the config.
+///
+/// Literal braces are written as `{{` and `}}`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub(crate) fn parse(source: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| anyhow!("unclosed placeholder in '{}'", source))?;
+
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+
+                    let placeholder = Placeholder::parse(&rest[..end])?;
+                    parts.push(Part::Placeholder(placeholder));
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err(anyhow!("unmatched '}}' in '{}'", source)),
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self {
+            source: source.to_owned(),
+            parts,
+        })
+    }
+
+    pub(crate) fn contains(&self, placeholder: &Placeholder) -> bool {
+        self.parts
+            .iter()
+            .any(|part| part == &Part::Placeholder(placeholder.to_owned()))
+    }
+
+    pub(crate) fn render<F>(&self, value: F) -> String
+    where
+        F: Fn(&Placeholder) -> String,
+    {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(literal) => literal.to_owned(),
+                Part::Placeholder(placeholder) => value(placeholder),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Deserializes a recording object name template which must not refer to the object itself.
+///
+/// The template must contain `{rtc_id}` so that recordings of the same room
+/// don't get uploaded to the same object overwriting each other.
+pub(crate) fn object_template<'de, D>(deserializer: D) -> Result<Template, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let template = deserialize_template(deserializer)?;
+
+    if template.contains(&Placeholder::Object) {
+        let err = format!("'{{object}}' is not allowed in '{}'", template);
+        return Err(de::Error::custom(err));
+    }
+
+    if !template.contains(&Placeholder::RtcId) {
+        let err = format!("'{{rtc_id}}' is missing in '{}'", template);
+        return Err(de::Error::custom(err));
+    }
+
+    Ok(template)
+}
+
+/// Deserializes a recording URI template.
+pub(crate) fn uri_template<'de, D>(deserializer: D) -> Result<Template, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_template(deserializer)
+}
+
+fn deserialize_template<'de, D>(deserializer: D) -> Result<Template, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let source = String::deserialize(deserializer)?;
+    Template::parse(&source).map_err(de::Error::custom)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &Template) -> String {
+        template.render(|placeholder| match placeholder {
+            Placeholder::Tag(key) => format!("tag:{}", key),
+            other => format!("{:?}", other),
+        })
+    }
+
+    #[test]
+    fn render_template() {
+        let template = Template::parse("{audience}/{date}/{tags.scope}/{rtc_id}.webm")
+            .expect("Failed to parse template");
+
+        assert_eq!(render(&template), "Audience/Date/tag:scope/RtcId.webm");
+        assert!(!template.contains(&Placeholder::Object));
+    }
+
+    #[test]
+    fn render_escaped_braces() {
+        let template = Template::parse("{{{room_id}}}").expect("Failed to parse template");
+        assert_eq!(render(&template), "{RoomId}");
+    }
+
+    #[test]
+    fn parse_invalid_template() {
+        assert!(Template::parse("{rtc_id").is_err());
+        assert!(Template::parse("rtc_id}").is_err());
+        assert!(Template::parse("{unknown}").is_err());
+        assert!(Template::parse("{tags.}").is_err());
+    }
+}
@@ -9,7 +9,9 @@ use svc_authz::ClientMap as Authz;
 
 use crate::app::context::{Context, GlobalContext, JanusTopics, MessageContext};
 use crate::app::metrics::DynamicStatsCollector;
-use crate::backend::janus::Client as JanusClient;
+use crate::app::rate_limiter::RateLimiter;
+use crate::app::unicast_watchdog::UnicastWatchdog;
+use crate::backend::janus::{Balancer as JanusBalancer, Client as JanusClient};
 use crate::config::Config;
 use crate::db::ConnectionPool as Db;
 
@@ -19,7 +21,7 @@ use super::{SVC_AUDIENCE, USR_AUDIENCE};
 
 ///////////////////////////////////////////////////////////////////////////////
 
-fn build_config() -> Config {
+pub(crate) fn build_config() -> Config {
     let id = format!("conference.{}", SVC_AUDIENCE);
     let broker_id = format!("mqtt-gateway.{}", SVC_AUDIENCE);
     let backend_id = format!("janus-gateway.{}", SVC_AUDIENCE);
@@ -43,11 +45,26 @@ fn build_config() -> Config {
             "stream_upload_timeout": 600,
             "transaction_watchdog_check_period": 1,
         },
+        "handle_id": {
+            "key": "test_handle_id_key",
+            "ttl": 3600,
+        },
         "upload": {
             USR_AUDIENCE: {
                 "backend": "EXAMPLE",
                 "bucket": format!("origin.webinar.{}", USR_AUDIENCE),
             }
+        },
+        "message": {
+            "persistent_labels": ["chat"],
+            "rate_limit": {
+                "labels": {
+                    "limited": {
+                        "capacity": 1,
+                        "refill_rate": 0.001,
+                    }
+                }
+            }
         }
     });
 
@@ -63,7 +80,10 @@ pub(crate) struct TestContext {
     db: TestDb,
     agent_id: AgentId,
     janus_client: Arc<JanusClient>,
+    janus_balancer: Arc<JanusBalancer>,
     janus_topics: JanusTopics,
+    rate_limiter: Arc<RateLimiter>,
+    unicast_watchdog: Arc<UnicastWatchdog>,
     logger: Logger,
     start_timestamp: DateTime<Utc>,
 }
@@ -71,22 +91,35 @@ pub(crate) struct TestContext {
 impl TestContext {
     pub(crate) fn new(db: TestDb, authz: TestAuthz) -> Self {
         let config = build_config();
+
         let agent_id = AgentId::new(&config.agent_label, config.id.clone());
 
-        let janus_client = JanusClient::start(&config.backend, agent_id.clone())
+        let janus_client = JanusClient::start(&config.backend, agent_id.clone(), None)
             .expect("Failed to start janus client");
 
+        let janus_balancer = JanusBalancer::new(config.balancer.strategy);
+        // No timeout checking thread since the test DB has a single connection.
+        let unicast_watchdog =
+            UnicastWatchdog::new(&config.message, db.connection_pool().to_owned());
+
         Self {
             config,
             authz: authz.into(),
             db,
             agent_id,
             janus_client: Arc::new(janus_client),
+            janus_balancer: Arc::new(janus_balancer),
             janus_topics: JanusTopics::new("ignore", "ignore", "ignore"),
+            rate_limiter: Arc::new(RateLimiter::new()),
+            unicast_watchdog: Arc::new(unicast_watchdog),
             logger: crate::LOG.new(o!()),
             start_timestamp: Utc::now(),
         }
     }
+
+    pub(crate) fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
 }
 
 impl GlobalContext for TestContext {
@@ -110,6 +143,10 @@ impl GlobalContext for TestContext {
         self.janus_client.clone()
     }
 
+    fn janus_balancer(&self) -> &JanusBalancer {
+        &self.janus_balancer
+    }
+
     fn janus_topics(&self) -> &JanusTopics {
         &self.janus_topics
     }
@@ -126,6 +163,14 @@ impl GlobalContext for TestContext {
         None
     }
 
+    fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    fn unicast_watchdog(&self) -> Option<&UnicastWatchdog> {
+        Some(&self.unicast_watchdog)
+    }
+
     fn get_metrics(&self) -> anyhow::Result<Vec<crate::app::metrics::Metric>> {
         Ok(vec![])
     }
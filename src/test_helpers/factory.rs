@@ -1,5 +1,6 @@
 use diesel::pg::PgConnection;
 use rand::Rng;
+use serde_json::Value as JsonValue;
 use svc_agent::AgentId;
 use uuid::Uuid;
 
@@ -15,6 +16,12 @@ pub(crate) struct Room {
     time: Option<db::room::Time>,
     backend: db::room::RoomBackend,
     reserve: Option<i32>,
+    tags: Option<JsonValue>,
+    backend_group: Option<String>,
+    max_agents: Option<i32>,
+    max_publishers: Option<i32>,
+    lobby: bool,
+    parent_id: Option<Uuid>,
 }
 
 impl Room {
@@ -24,6 +31,12 @@ impl Room {
             time: None,
             backend: db::room::RoomBackend::None,
             reserve: None,
+            tags: None,
+            backend_group: None,
+            max_agents: None,
+            max_publishers: None,
+            lobby: false,
+            parent_id: None,
         }
     }
 
@@ -52,6 +65,45 @@ impl Room {
         Self { backend, ..self }
     }
 
+    pub(crate) fn tags(self, tags: &JsonValue) -> Self {
+        Self {
+            tags: Some(tags.to_owned()),
+            ..self
+        }
+    }
+
+    pub(crate) fn backend_group(self, backend_group: &str) -> Self {
+        Self {
+            backend_group: Some(backend_group.to_owned()),
+            ..self
+        }
+    }
+
+    pub(crate) fn max_agents(self, max_agents: i32) -> Self {
+        Self {
+            max_agents: Some(max_agents),
+            ..self
+        }
+    }
+
+    pub(crate) fn max_publishers(self, max_publishers: i32) -> Self {
+        Self {
+            max_publishers: Some(max_publishers),
+            ..self
+        }
+    }
+
+    pub(crate) fn lobby(self, lobby: bool) -> Self {
+        Self { lobby, ..self }
+    }
+
+    pub(crate) fn parent_id(self, parent_id: Uuid) -> Self {
+        Self {
+            parent_id: Some(parent_id),
+            ..self
+        }
+    }
+
     pub(crate) fn insert(self, conn: &PgConnection) -> db::room::Object {
         let audience = self.audience.expect("Audience not set");
         let time = self.time.expect("Time not set");
@@ -62,6 +114,27 @@ impl Room {
             q = q.reserve(reserve);
         }
 
+        if let Some(ref tags) = self.tags {
+            q = q.tags(tags);
+        }
+
+        if let Some(ref backend_group) = self.backend_group {
+            q = q.backend_group(backend_group);
+        }
+
+        if let Some(max_agents) = self.max_agents {
+            q = q.max_agents(max_agents);
+        }
+
+        if let Some(max_publishers) = self.max_publishers {
+            q = q.max_publishers(max_publishers);
+        }
+
+        if let Some(parent_id) = self.parent_id {
+            q = q.parent_id(parent_id);
+        }
+
+        q = q.lobby(self.lobby);
         q.execute(conn).expect("Failed to insert room")
     }
 }
@@ -150,6 +223,7 @@ pub(crate) struct JanusBackend {
     session_id: i64,
     capacity: Option<i32>,
     balancer_capacity: Option<i32>,
+    group: Option<String>,
 }
 
 impl JanusBackend {
@@ -160,6 +234,7 @@ impl JanusBackend {
             session_id,
             capacity: None,
             balancer_capacity: None,
+            group: None,
         }
     }
 
@@ -177,6 +252,13 @@ impl JanusBackend {
         }
     }
 
+    pub(crate) fn group(self, group: &str) -> Self {
+        Self {
+            group: Some(group.to_owned()),
+            ..self
+        }
+    }
+
     pub(crate) fn insert(&self, conn: &PgConnection) -> db::janus_backend::Object {
         let mut q = db::janus_backend::UpsertQuery::new(&self.id, self.handle_id, self.session_id);
 
@@ -188,6 +270,10 @@ impl JanusBackend {
             q = q.balancer_capacity(balancer_capacity);
         }
 
+        if let Some(ref group) = self.group {
+            q = q.group(group);
+        }
+
         q.execute(conn).expect("Failed to insert janus_backend")
     }
 }
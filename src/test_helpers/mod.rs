@@ -2,7 +2,10 @@ use async_std::prelude::*;
 use chrono::Utc;
 use serde::de::DeserializeOwned;
 use serde_json::json;
-use svc_agent::mqtt::{IncomingEventProperties, IncomingRequestProperties};
+use svc_agent::mqtt::{
+    IncomingEventProperties, IncomingRequestProperties, IncomingResponseProperties,
+};
+use svc_agent::AgentId;
 
 use crate::app::endpoint::{EventHandler, RequestHandler};
 use crate::app::error::Error as AppError;
@@ -25,13 +28,19 @@ pub(crate) async fn handle_request<H: RequestHandler>(
     agent: &TestAgent,
     payload: H::Payload,
 ) -> Result<Vec<OutgoingEnvelope>, AppError> {
-    let agent_id = agent.agent_id().to_string();
+    let reqp = build_reqp(agent.agent_id(), "ignore");
+    let messages = H::handle(context, payload, &reqp).await?;
+    Ok(parse_messages(messages).await)
+}
+
+pub(crate) fn build_reqp(agent_id: &AgentId, method: &str) -> IncomingRequestProperties {
+    let agent_id = agent_id.to_string();
     let now = Utc::now().timestamp().to_string();
 
     let reqp_json = json!({
         "type": "request",
         "correlation_data": "ignore",
-        "method": "ignore",
+        "method": method,
         "agent_id": agent_id,
         "connection_mode": "default",
         "connection_version": "v2",
@@ -44,11 +53,7 @@ pub(crate) async fn handle_request<H: RequestHandler>(
         "session_tracking_label": "16cc4294-0b13-11ea-91ae-60f81db6d53e.16ee876e-0b13-11ea-8c32-60f81db6d53e 2565f962-0b13-11ea-9359-60f81db6d53e.25c2b97c-0b13-11ea-9f20-60f81db6d53e",
     });
 
-    let reqp = serde_json::from_value::<IncomingRequestProperties>(reqp_json)
-        .expect("Failed to parse reqp");
-
-    let messages = H::handle(context, payload, &reqp).await?;
-    Ok(parse_messages(messages).await)
+    serde_json::from_value::<IncomingRequestProperties>(reqp_json).expect("Failed to parse reqp")
 }
 
 pub(crate) async fn handle_event<H: EventHandler>(
@@ -56,12 +61,18 @@ pub(crate) async fn handle_event<H: EventHandler>(
     agent: &TestAgent,
     payload: H::Payload,
 ) -> Result<Vec<OutgoingEnvelope>, AppError> {
-    let agent_id = agent.agent_id().to_string();
+    let evp = build_evp(agent.agent_id(), "ignore");
+    let messages = H::handle(context, payload, &evp).await?;
+    Ok(parse_messages(messages).await)
+}
+
+pub(crate) fn build_evp(agent_id: &AgentId, label: &str) -> IncomingEventProperties {
+    let agent_id = agent_id.to_string();
     let now = Utc::now().timestamp().to_string();
 
     let evp_json = json!({
         "type": "event",
-        "label": "ignore",
+        "label": label,
         "agent_id": agent_id,
         "connection_mode": "default",
         "connection_version": "v2",
@@ -73,14 +84,38 @@ pub(crate) async fn handle_event<H: EventHandler>(
         "session_tracking_label": "16cc4294-0b13-11ea-91ae-60f81db6d53e.16ee876e-0b13-11ea-8c32-60f81db6d53e 2565f962-0b13-11ea-9359-60f81db6d53e.25c2b97c-0b13-11ea-9f20-60f81db6d53e",
     });
 
-    let evp =
-        serde_json::from_value::<IncomingEventProperties>(evp_json).expect("Failed to parse evp");
+    serde_json::from_value::<IncomingEventProperties>(evp_json).expect("Failed to parse evp")
+}
 
-    let messages = H::handle(context, payload, &evp).await?;
-    Ok(parse_messages(messages).await)
+pub(crate) fn build_respp(agent_id: &AgentId) -> IncomingResponseProperties {
+    build_respp_with_correlation_data(agent_id, "ignore")
+}
+
+pub(crate) fn build_respp_with_correlation_data(
+    agent_id: &AgentId,
+    correlation_data: &str,
+) -> IncomingResponseProperties {
+    let now = Utc::now().timestamp().to_string();
+
+    let respp_json = json!({
+        "type": "response",
+        "status": "200",
+        "correlation_data": correlation_data,
+        "agent_id": agent_id,
+        "connection_mode": "default",
+        "connection_version": "v2",
+        "broker_agent_id": format!("alpha.mqtt-gateway.{}", SVC_AUDIENCE),
+        "broker_timestamp": now,
+        "broker_processing_timestamp": now,
+        "broker_initial_processing_timestamp": now,
+        "tracking_id": "16911d40-0b13-11ea-8171-60f81db6d53e.14097484-0c8d-11ea-bb82-60f81db6d53e.147b2994-0c8d-11ea-8933-60f81db6d53e",
+        "session_tracking_label": "16cc4294-0b13-11ea-91ae-60f81db6d53e.16ee876e-0b13-11ea-8c32-60f81db6d53e 2565f962-0b13-11ea-9359-60f81db6d53e.25c2b97c-0b13-11ea-9f20-60f81db6d53e",
+    });
+
+    serde_json::from_value::<IncomingResponseProperties>(respp_json).expect("Failed to parse respp")
 }
 
-async fn parse_messages(mut messages: MessageStream) -> Vec<OutgoingEnvelope> {
+pub(crate) async fn parse_messages(mut messages: MessageStream) -> Vec<OutgoingEnvelope> {
     let mut parsed_messages = vec![];
 
     while let Some(message) = messages.next().await {
@@ -185,9 +220,9 @@ pub(crate) mod prelude {
 
     #[allow(unused_imports)]
     pub(crate) use super::{
-        agent::TestAgent, authz::TestAuthz, context::TestContext, db::TestDb, factory, find_event,
-        find_request, find_response, handle_event, handle_request, shared_helpers, SVC_AUDIENCE,
-        USR_AUDIENCE,
+        agent::TestAgent, authz::TestAuthz, build_evp, build_reqp, build_respp,
+        context::TestContext, db::TestDb, factory, find_event, find_request, find_response,
+        handle_event, handle_request, parse_messages, shared_helpers, SVC_AUDIENCE, USR_AUDIENCE,
     };
 }
 
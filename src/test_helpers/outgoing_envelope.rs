@@ -62,10 +62,20 @@ impl OutgoingResponseProperties {
 #[derive(Debug, Deserialize)]
 pub(crate) struct OutgoingRequestProperties {
     method: String,
+    correlation_data: String,
+    response_topic: String,
 }
 
 impl OutgoingRequestProperties {
     pub(crate) fn method(&self) -> &str {
         &self.method
     }
+
+    pub(crate) fn correlation_data(&self) -> &str {
+        &self.correlation_data
+    }
+
+    pub(crate) fn response_topic(&self) -> &str {
+        &self.response_topic
+    }
 }